@@ -1,7 +1,8 @@
 use futures::executor::block_on;
 use jni::sys::jlong;
+use jni::objects::GlobalRef;
 use std::sync::Arc;
-use crate::{java_util::{arc_from_handle, arc_to_handle, arc_dispose_handle, JavaHandle}, window_surface::{WindowSurface, WindowSurfaceDesc}, ui::{UserInterface}, math::rect::Rect} ;
+use crate::{java_util::{arc_from_handle, arc_to_handle, arc_dispose_handle, JavaHandle, JNIResult}, window_surface::{WindowSurface, WindowSurfaceDesc}, ui::{UserInterface}, skybox::SkyboxPass, math::rect::Rect, resource::{texture_resource::TextureReadback, gpu_memory::GpuMemoryTracker, upload_queue::UploadQueue}} ;
 use std::cell::RefCell;
 use std::sync::Mutex;
 use std::cell::Cell;
@@ -24,7 +25,15 @@ pub struct EngineKernel {
    pub window_surface: Mutex<WindowSurface>,
     
    pub user_interface: RefCell<UserInterface>,
-   pub frame_encoder: Mutex<Cell<Option<FrameContext>>>
+   pub skybox: Mutex<SkyboxPass>,
+   pub frame_encoder: Mutex<Cell<Option<FrameContext>>>,
+   // readbacks waiting on the gpu together with the java future they complete
+   pub texture_readbacks: Mutex<Vec<(TextureReadback, GlobalRef)>>,
+   pub memory: Arc<GpuMemoryTracker>,
+   // kept apart from the surface so resources can be queued for upload without waiting on the frame
+   pub uploads: Mutex<UploadQueue>,
+   // uploads waiting on the gpu together with the java future they complete
+   pub upload_futures: Mutex<Vec<(u64, GlobalRef)>>
 
 }
 
@@ -36,12 +45,19 @@ impl EngineKernel {
     pub fn new(instance: wgpu::Instance, desc: &EngineKernelDesc) -> Self {
         let surface = block_on(WindowSurface::create(&instance, &desc.surface));
 
-        let ui = UserInterface::new(&surface.device, &surface.surface_info());
+        let memory = Arc::new(GpuMemoryTracker::default());
+        let ui = UserInterface::new(&surface.device, &surface.surface_info(), &memory);
+        let skybox = SkyboxPass::new(&surface.device, surface.surface_info(), &memory);
         Self {
            instance,
            window_surface:  Mutex::new(surface),
            user_interface: RefCell::new(ui),
-           frame_encoder: Mutex::new(Cell::new(None))
+           skybox: Mutex::new(skybox),
+           frame_encoder: Mutex::new(Cell::new(None)),
+           texture_readbacks: Mutex::new(Vec::new()),
+           uploads: Mutex::new(UploadQueue::new(&memory)),
+           upload_futures: Mutex::new(Vec::new()),
+           memory
         }
     }
 
@@ -58,6 +74,7 @@ impl EngineKernel {
         let surface = self.window_surface.lock().expect("failed to lock surface");
         let mut ui = self.user_interface.borrow_mut();
         ui.cmd_prepare();
+        self.skybox.lock().expect("failed to lock skybox").cmd_prepare();
         let frame_encoder = self.frame_encoder.lock().expect("Could not lock frame_encoder");
         frame_encoder.set(Some(FrameContext {
                 encoder: surface.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
@@ -65,7 +82,8 @@ impl EngineKernel {
     }
 
 
-    pub fn cmd_dispatch(&self) {
+    // the frame is presented even if the ui reports an error
+    pub fn cmd_dispatch(&self) -> JNIResult<()> {
         let window_surface = self.window_surface.lock().expect("failed to lock surface");
         let frame = 
             window_surface.surface
@@ -79,11 +97,23 @@ impl EngineKernel {
         self.frame_encoder.lock().expect("Could not lock frame_encoder").swap(&frame_context_cell);
         let mut frame_context = frame_context_cell.into_inner().expect("cmd_prepare");
 
-        let mut ui = self.user_interface.borrow_mut();
+        // uploads are recorded first so the ui can already sample them this frame
+        let mut uploads = self.uploads.lock().expect("failed to lock uploads");
+        uploads.flush(&window_surface.device, &mut frame_context.encoder);
+
         let frame_texture = &frame.texture;
         let size = frame_texture.size();
+        self.skybox.lock().expect("failed to lock skybox").cmd_dispatch(
+            &view,
+            size,
+            &window_surface.device,
+            &window_surface.queue,
+            &mut frame_context.encoder
+        );
+
+        let mut ui = self.user_interface.borrow_mut();
         
-        ui.cmd_dispatch(
+        let result = ui.cmd_dispatch(
             &Rect {
                 min: [0.0,0.0],
                 max: [size.width as f32, size.height as f32],
@@ -95,7 +125,9 @@ impl EngineKernel {
         );
          
         window_surface.queue.submit(std::iter::once(frame_context.encoder.finish()));
+        uploads.after_submit(&window_surface.queue);
         frame.present();
+        result
     }
 }
 
@@ -1,10 +1,10 @@
 @group(0) @binding(0)
-var default_sampler: sampler;
-@group(0) @binding(1)
-var tile_sampler: sampler;
+var u_sampler: sampler;
 
 struct PushConstants {
     texture_style: u32,
+    // normalized depth of the slice sampled from a 3D texture
+    slice_depth: f32,
 }
 var<push_constant> pc: PushConstants;
 
@@ -16,6 +16,8 @@ struct FrameUniform {
 var<uniform> u_frame: FrameUniform;
 @group(1) @binding(1)
 var u_tex: texture_2d<f32>;
+@group(1) @binding(2)
+var u_tex_3d: texture_3d<f32>;
 
 struct VertexOutput {
     @builtin(position) vertex: vec4<f32>,
@@ -27,9 +29,11 @@ struct VertexOutput {
 fn vs_main(
   @location(0) position: vec2<f32>,
   @location(1) uv: vec2<f32>,
-  @location(2) color: vec4<f32>
+  @location(2) color: vec4<f32>,
+  // moves replayed command lists, zero for draws of the frame
+  @location(4) offset: vec2<f32>
 ) -> VertexOutput {
-   let pos: vec4<f32> = u_frame.view_transform * vec4<f32>(position.x, position.y, 0.0, 1.0);
+   let pos: vec4<f32> = u_frame.view_transform * vec4<f32>(position.x + offset.x, position.y + offset.y, 0.0, 1.0);
    var result: VertexOutput;
    result.vertex = pos;
    result.uv = uv;
@@ -37,11 +41,40 @@ fn vs_main(
    return result;
 }
 
+// every blend mode expects premultiplied color. the *_premultiplied entries are for textures that already are,
+// only the tint is premultiplied there.
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
 
 @fragment
 fn fs_main(
     @location(0) uv: vec2<f32>,
     @location(1) color: vec4<f32>,
 ) -> @location(0) vec4<f32> {
-    return textureSample(u_tex, default_sampler, uv.xy) * color;
+    return premultiply(textureSample(u_tex, u_sampler, uv.xy) * color);
+}
+
+@fragment
+fn fs_main_premultiplied(
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+) -> @location(0) vec4<f32> {
+    return textureSample(u_tex, u_sampler, uv.xy) * premultiply(color);
+}
+
+@fragment
+fn fs_main_3d(
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+) -> @location(0) vec4<f32> {
+    return premultiply(textureSample(u_tex_3d, u_sampler, vec3<f32>(uv.xy, pc.slice_depth)) * color);
+}
+
+@fragment
+fn fs_main_3d_premultiplied(
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+) -> @location(0) vec4<f32> {
+    return textureSample(u_tex_3d, u_sampler, vec3<f32>(uv.xy, pc.slice_depth)) * premultiply(color);
 }
@@ -0,0 +1,60 @@
+@group(0) @binding(0)
+var u_sampler: sampler;
+
+struct FrameUniform {
+  view_transform: mat4x4<f32> 
+}
+
+@group(1) @binding(0)
+var<uniform> u_frame: FrameUniform;
+@group(1) @binding(3)
+var u_textures: binding_array<texture_2d<f32>>;
+
+struct VertexOutput {
+    @builtin(position) vertex: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) texture: u32,
+};
+
+@vertex
+fn vs_main(
+  @location(0) position: vec2<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>,
+  @location(3) texture: u32,
+  @location(4) offset: vec2<f32>,
+  // replayed command lists don't know their slot when they are recorded, it comes with the instance
+  @location(5) instance_texture: u32
+) -> VertexOutput {
+   let pos: vec4<f32> = u_frame.view_transform * vec4<f32>(position.x + offset.x, position.y + offset.y, 0.0, 1.0);
+   var result: VertexOutput;
+   result.vertex = pos;
+   result.uv = uv;
+   result.color = color;
+   result.texture = texture + instance_texture;
+   return result;
+}
+
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+
+// every vertex picks its texture from the binding array, so draws of different textures share a draw call
+@fragment
+fn fs_main_array(
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) texture: u32,
+) -> @location(0) vec4<f32> {
+    return premultiply(textureSample(u_textures[texture], u_sampler, uv.xy) * color);
+}
+
+@fragment
+fn fs_main_array_premultiplied(
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) texture: u32,
+) -> @location(0) vec4<f32> {
+    return textureSample(u_textures[texture], u_sampler, uv.xy) * premultiply(color);
+}
@@ -1,4 +1,4 @@
-use jni::{sys::{jlong}, objects::{JObject, JValue}};
+use jni::{sys::{jlong}, objects::{JObject, JString, JValue}};
 use std::sync::Arc;
 use std::marker::PhantomData;
 
@@ -55,24 +55,53 @@ pub trait JavaHandleContainer<T> {
 
 pub type JNIResult<T> = Result<T, JNIError >;
 
+#[derive(Debug)]
 pub enum JNIError { 
     Generic(String),
+    IllegalArgument(String),
+    IllegalState(String),
     //NullException(String)
 }
 
-pub fn to_java_exception<'local>(mut env: JNIEnv<'local>, error: &JNIError) -> jni::errors::Result<JObject<'local>> {
-    match error {
-        JNIError::Generic(err) => {
-            let error_string = env.new_string(err)?;
-            env.new_object(
-                "java/lang/Error",
-                "(Ljava/lang/String;)V",
-                &[
-                    (&error_string).into(),
-                ],
-            )
+impl JNIError {
+    fn java_class(&self) -> &'static str {
+        match self {
+            JNIError::Generic(_) => "java/lang/Error",
+            JNIError::IllegalArgument(_) => "java/lang/IllegalArgumentException",
+            JNIError::IllegalState(_) => "java/lang/IllegalStateException",
         }
     }
+
+    fn message(&self) -> &str {
+        match self {
+            JNIError::Generic(err) | JNIError::IllegalArgument(err) | JNIError::IllegalState(err) => err,
+        }
+    }
+}
+
+pub fn to_java_exception<'local>(env: &mut JNIEnv<'local>, error: &JNIError) -> jni::errors::Result<JObject<'local>> {
+    let error_string = env.new_string(error.message())?;
+    env.new_object(
+        error.java_class(),
+        "(Ljava/lang/String;)V",
+        &[
+            (&error_string).into(),
+        ],
+    )
+}
+
+// a String field that may be null
+pub fn get_string_field(env: &mut JNIEnv, obj: &JObject, name: &str) -> Option<String> {
+    let value = env.get_field(obj, name, "Ljava/lang/String;").expect("failed to get string field").l().expect("expect object type");
+    if value.is_null() {
+        return None;
+    }
+    Some(env.get_string(&JString::from(value)).expect("invalid string").into())
+}
+
+// raises the error as a pending exception, the native method has to return right after
+pub fn throw_java_exception(env: &mut JNIEnv, error: &JNIError) {
+    env.throw_new(error.java_class(), error.message()).expect("failed to throw exception");
 }
 
 pub fn set_joml_vector2f(mut env: JNIEnv, o: &mut JObject, x: f32, y: f32) {
@@ -93,6 +122,13 @@ pub fn set_joml_vector4f(mut env: JNIEnv, o: &mut JObject, x: f32, y: f32, z: f3
     env.set_field(&o, "w", "F", JValue::Float(w)).expect("failed to set w");
 }
 
+pub fn set_joml_rectanglef(mut env: JNIEnv, o: &mut JObject, min_x: f32, min_y: f32, max_x: f32, max_y: f32) {
+    env.set_field(&o, "minX", "F", JValue::Float(min_x)).expect("failed to set minX");
+    env.set_field(&o, "minY", "F", JValue::Float(min_y)).expect("failed to set minY");
+    env.set_field(&o, "maxX", "F", JValue::Float(max_x)).expect("failed to set maxX");
+    env.set_field(&o, "maxY", "F", JValue::Float(max_y)).expect("failed to set maxY");
+}
+
 pub fn arc_from_handle<T>(ptr: jlong) -> Option<Arc<T>> {
     if ptr == 0 {
         panic!("invalid handle");
@@ -0,0 +1,91 @@
+use jni::{JNIEnv, objects::{JObject, JClass, JByteBuffer}, sys::{jint, jlong}};
+use std::sync::Arc;
+
+use crate::{resource::texture_atlas::{TextureAtlas, TextureAtlasDesc, AtlasSubTexture}, ui::JavaHandle, java_util::{set_joml_vector2f, set_joml_rectanglef, throw_java_exception, JNIError}, engine_kernel::EngineKernel};
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createTextureAtlas<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, desc: JObject<'local>) -> jlong {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let page_size = env.get_field(&desc, "pageSize", "I").unwrap().i().unwrap();
+    let max_page_size = env.get_field(&desc, "maxPageSize", "I").unwrap().i().unwrap();
+    let padding = env.get_field(&desc, "padding", "I").unwrap().i().unwrap();
+    let mip_levels = env.get_field(&desc, "mipLevels", "I").unwrap().i().unwrap();
+    let srgb = env.get_field(&desc, "srgb", "Z").unwrap().z().unwrap();
+    let max_texture_size = kernel.window_surface.lock().expect("failed to lock surface").device.limits().max_texture_dimension_2d;
+    if page_size < 0 || padding < 0 || mip_levels < 0 {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("invalid atlas page size {}, padding {} or mip levels {}", page_size, padding, mip_levels)));
+        return 0;
+    }
+    if max_page_size < 0 || max_page_size as u32 > max_texture_size {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("atlas max page size {} exceeds the device limit of {}", max_page_size, max_texture_size)));
+        return 0;
+    }
+
+    let atlas = TextureAtlas::new(TextureAtlasDesc {
+        page_size: page_size as u32,
+        max_page_size: max_page_size as u32,
+        padding: padding as u32,
+        mip_levels: mip_levels as u32,
+        format: if srgb { wgpu::TextureFormat::Rgba8UnormSrgb } else { wgpu::TextureFormat::Rgba8Unorm }
+    }, &kernel.memory);
+    match atlas {
+        Ok(atlas) => TextureAtlas::to_handle(Arc::new(atlas)),
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraAtlas_00024JNI_drop<'local>(mut _env: JNIEnv<'local>, _class: JClass, atlas_ptr: jlong) {
+    TextureAtlas::drop_handle(atlas_ptr);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraAtlas_00024JNI_add<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, atlas_ptr: jlong,
+        width: jint, height: jint, buffer: JByteBuffer<'local>) -> jlong {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let atlas = TextureAtlas::from_handle(atlas_ptr).expect("atlas invalid");
+
+    let buf_size = env
+        .get_direct_buffer_capacity(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};
+
+    if width < 0 || height < 0 {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("invalid atlas image size {}x{}", width, height)));
+        return 0;
+    }
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    match atlas.insert(&surface.device, &surface.queue, width as u32, height as u32, slice) {
+        Ok(sub_texture) => AtlasSubTexture::to_handle(Arc::new(sub_texture)),
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraSubTexture_00024JNI_drop<'local>(mut _env: JNIEnv<'local>, _class: JClass, sub_texture_ptr: jlong) {
+    AtlasSubTexture::drop_handle(sub_texture_ptr);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraSubTexture_00024JNI_getSize<'local>(env: JNIEnv<'local>, _class: JClass, sub_texture_ptr: jlong, mut vec2_obj: JObject<'local>) {
+    let sub_texture = AtlasSubTexture::from_handle(sub_texture_ptr).expect("sub texture invalid");
+    let size = sub_texture.size();
+    set_joml_vector2f(env, &mut vec2_obj, size[0] as f32, size[1] as f32);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraSubTexture_00024JNI_getUV<'local>(env: JNIEnv<'local>, _class: JClass, sub_texture_ptr: jlong, mut rect_obj: JObject<'local>) {
+    let sub_texture = AtlasSubTexture::from_handle(sub_texture_ptr).expect("sub texture invalid");
+    let (_, uv) = sub_texture.resolve();
+    set_joml_rectanglef(env, &mut rect_obj, uv.min[0], uv.min[1], uv.max[0], uv.max[1]);
+}
@@ -1,29 +1,42 @@
-use jni::{JNIEnv, objects::{JClass, JObject}, sys::{jint, jlong}};
+use jni::{JNIEnv, objects::{JClass, JObject}, sys::{jfloat, jint, jlong}};
 use raw_window_handle::{WindowsDisplayHandle, Win32WindowHandle, XlibWindowHandle, XlibDisplayHandle};
 use core::ffi::{c_void, c_ulong};
 use std::sync::Arc;
 use crate::ui::JavaHandle;
+use crate::resource::texture_resource::TextureResource;
+use crate::java_util::{throw_java_exception, JNIError, JNIResult};
 use crate::engine_kernel::{EngineKernel, EngineKernelDesc, EngineEvent, ResizePayload};
 use crate::window_surface::{WindowSurfaceDesc, WindowDesc, Win32WindowDesc, X11WindowDesc};
+use super::jni_texture::{resolve_texture_readbacks, resolve_uploads};
 
+// codes match EngineKernel.EngineKernelBuild.WindowType
 #[repr(u32)]
 enum JavaWindowType {
-    Win32,
-    X11
+    Win32 = 0,
+    X11 = 1
 }
 
+impl JavaWindowType {
+    fn from_code(code: jint) -> Option<Self> {
+        match code {
+            0 => Some(JavaWindowType::Win32),
+            1 => Some(JavaWindowType::X11),
+            _ => None
+        }
+    }
+}
 
-#[no_mangle]
-pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_create<'local>(mut env: JNIEnv<'local>, _class: JClass, desc: JObject<'local>) -> jlong  {
-    env_logger::init();
-    let window_type = env.get_field(&desc, "windowType", "I").unwrap().i().unwrap() ;
-    let display_ptr = env.get_field(&desc, "displayHandle", "J").unwrap().j().unwrap() ;
-    let window_ptr = env.get_field(&desc, "windowHandle", "J").unwrap().j().unwrap() ;
-    
-    let java_window_type : JavaWindowType = unsafe { std::mem::transmute::<jint, JavaWindowType>(window_type) }.into();
-    
-    let window_desc = match java_window_type {
+fn window_desc_from_java(window_type: jint, window_ptr: jlong, display_ptr: jlong) -> JNIResult<WindowDesc> {
+    let window_type = JavaWindowType::from_code(window_type)
+        .ok_or_else(|| JNIError::IllegalArgument(format!("invalid window type {}", window_type)))?;
+    if window_ptr == 0 {
+        return Err(JNIError::IllegalArgument("window handle is null".to_string()));
+    }
+    Ok(match window_type {
         JavaWindowType::X11 => {
+            if display_ptr == 0 {
+                return Err(JNIError::IllegalArgument("X11 windows need a display handle".to_string()));
+            }
             let mut win = X11WindowDesc {
                window: XlibWindowHandle::empty(),
                display: XlibDisplayHandle::empty() 
@@ -40,6 +53,24 @@ pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_cre
             win.window.hwnd = window_ptr as *mut c_void;
             WindowDesc::Win32(win)
         }
+    })
+}
+
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_create<'local>(mut env: JNIEnv<'local>, _class: JClass, desc: JObject<'local>) -> jlong  {
+    // a kernel can be created again after a descriptor was rejected
+    let _ = env_logger::try_init();
+    let window_type = env.get_field(&desc, "windowType", "I").unwrap().i().unwrap() ;
+    let display_ptr = env.get_field(&desc, "displayHandle", "J").unwrap().j().unwrap() ;
+    let window_ptr = env.get_field(&desc, "windowHandle", "J").unwrap().j().unwrap() ;
+    
+    let window_desc = match window_desc_from_java(window_type, window_ptr, display_ptr) {
+        Ok(window_desc) => window_desc,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
     };
 
     let window_surface_desc: WindowSurfaceDesc = WindowSurfaceDesc {
@@ -78,7 +109,28 @@ pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_cmd
 }
 
 #[no_mangle]
-pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_cmdDispatch(_jni: JNIEnv, _class: JClass, kernel_ptr: jlong) {
+pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_cmdDispatch(mut env: JNIEnv, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let result = kernel.cmd_dispatch();
+    resolve_texture_readbacks(&mut env, &kernel);
+    resolve_uploads(&mut env, &kernel);
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_EngineKernel_00024JNI_cmdDrawSkybox(mut env: JNIEnv, _class: JClass, kernel_ptr: jlong, tex_ptr: jlong, cube: jint,
+        rot_x: jfloat, rot_y: jfloat, rot_z: jfloat, rot_w: jfloat, fov_y: jfloat, tint_color: jint) {
     let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
-    kernel.cmd_dispatch();
+    let Some(texture_resource) = TextureResource::from_handle(tex_ptr) else { panic!("invalid tex resource") };
+    if cube < 0 {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("cube {} out of range", cube)));
+        return;
+    }
+    let rotation = glam::Quat::from_xyzw(rot_x, rot_y, rot_z, rot_w);
+    let mut skybox = kernel.skybox.lock().expect("failed to lock skybox");
+    if let Err(err) = skybox.cmd_draw(&texture_resource, cube as u32, rotation, fov_y, tint_color as u32) {
+        throw_java_exception(&mut env, &err);
+    }
 }
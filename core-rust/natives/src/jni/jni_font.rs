@@ -0,0 +1,144 @@
+use jni::{JNIEnv, objects::{JClass, JByteBuffer, JLongArray, JObject, JString, JValue}, sys::{jfloat, jlong}};
+use std::sync::Arc;
+
+use crate::{resource::{font_resource::FontResource, text_layout::{layout_text, TextAlign, TextLayout, TextLayoutDesc}}, ui::JavaHandle, java_util::{set_joml_vector2f, throw_java_exception, JNIError, JNIResult}};
+use ab_glyph::{Font, ScaleFont};
+
+// codes match TeraFont.TextAlign
+fn text_align_from_java(code: i32) -> JNIResult<TextAlign> {
+    match code {
+        0 => Ok(TextAlign::Left),
+        1 => Ok(TextAlign::Center),
+        2 => Ok(TextAlign::Right),
+        3 => Ok(TextAlign::Start),
+        4 => Ok(TextAlign::End),
+        _ => Err(JNIError::IllegalArgument(format!("invalid text align {}", code)))
+    }
+}
+
+pub fn text_layout_desc_from_java(env: &mut JNIEnv, desc: &JObject) -> JNIResult<TextLayoutDesc> {
+    let size = env.get_field(desc, "size", "F").unwrap().f().unwrap();
+    let max_width = env.get_field(desc, "maxWidth", "F").unwrap().f().unwrap();
+    let max_lines = env.get_field(desc, "maxLines", "I").unwrap().i().unwrap();
+    let wrap = env.get_field(desc, "wrap", "Z").unwrap().z().unwrap();
+    let ellipsis = env.get_field(desc, "ellipsis", "Z").unwrap().z().unwrap();
+    let align = env.get_field(desc, "align", "I").unwrap().i().unwrap();
+    text_layout_desc(size, max_width, max_lines, wrap, ellipsis, align)
+}
+
+// the fields of TeraFont.TextLayoutDesc
+pub fn text_layout_desc(size: f32, max_width: f32, max_lines: i32, wrap: bool, ellipsis: bool, align: i32) -> JNIResult<TextLayoutDesc> {
+    let align = text_align_from_java(align)?;
+    if max_lines < 0 {
+        return Err(JNIError::IllegalArgument(format!("invalid max lines {}", max_lines)));
+    }
+    Ok(TextLayoutDesc {
+        size,
+        // 0 leaves the width and line count unbounded
+        max_width: (max_width != 0.0).then_some(max_width),
+        max_lines: (max_lines != 0).then_some(max_lines as u32),
+        wrap,
+        ellipsis,
+        align
+    })
+}
+
+fn set_float_array_field(env: &mut JNIEnv, obj: &JObject, name: &str, values: &[f32]) {
+    let array = env.new_float_array(values.len() as i32).expect("failed to allocate array");
+    env.set_float_array_region(&array, 0, values).expect("failed to fill array");
+    env.set_field(obj, name, "[F", JValue::Object(&array)).expect("failed to set array");
+}
+
+fn set_int_array_field(env: &mut JNIEnv, obj: &JObject, name: &str, values: &[i32]) {
+    let array = env.new_int_array(values.len() as i32).expect("failed to allocate array");
+    env.set_int_array_region(&array, 0, values).expect("failed to fill array");
+    env.set_field(obj, name, "[I", JValue::Object(&array)).expect("failed to set array");
+}
+
+// copies the layout into a TeraFont.TextLayout
+fn set_java_text_layout(env: &mut JNIEnv, obj: &JObject, layout: &TextLayout) {
+    env.set_field(obj, "width", "F", JValue::Float(layout.size[0])).expect("failed to set width");
+    env.set_field(obj, "height", "F", JValue::Float(layout.size[1])).expect("failed to set height");
+    env.set_field(obj, "lineAdvance", "F", JValue::Float(layout.line_advance)).expect("failed to set lineAdvance");
+    let glyphs = &layout.glyphs;
+    set_float_array_field(env, obj, "glyphX", &glyphs.iter().map(|glyph| glyph.pos[0]).collect::<Vec<_>>());
+    set_float_array_field(env, obj, "glyphY", &glyphs.iter().map(|glyph| glyph.pos[1]).collect::<Vec<_>>());
+    set_float_array_field(env, obj, "glyphAdvance", &glyphs.iter().map(|glyph| glyph.advance).collect::<Vec<_>>());
+    set_int_array_field(env, obj, "glyphCharIndex", &glyphs.iter().map(|glyph| glyph.char_index as i32).collect::<Vec<_>>());
+    let lines = &layout.lines;
+    set_int_array_field(env, obj, "lineGlyphStart", &lines.iter().map(|line| line.glyph_start as i32).collect::<Vec<_>>());
+    set_int_array_field(env, obj, "lineCharStart", &lines.iter().map(|line| line.char_start as i32).collect::<Vec<_>>());
+    set_float_array_field(env, obj, "lineBaseline", &lines.iter().map(|line| line.baseline).collect::<Vec<_>>());
+    set_float_array_field(env, obj, "lineX", &lines.iter().map(|line| line.x).collect::<Vec<_>>());
+    set_float_array_field(env, obj, "lineWidth", &lines.iter().map(|line| line.width).collect::<Vec<_>>());
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createFont<'local>(mut env: JNIEnv<'local>, _class: JClass, _kernel_ptr: jlong, buffer: JByteBuffer<'local>) -> jlong {
+    let buf_size = env.get_direct_buffer_capacity(&buffer)
+            .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    // the font keeps its own copy, the buffer can be reused once this returns
+    let data = unsafe {std::slice::from_raw_parts(buf, buf_size)}.to_vec();
+    match FontResource::from_bytes(data) {
+        Ok(font) => FontResource::to_handle(Arc::new(font)),
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraFont_00024JNI_drop<'local>(mut _env: JNIEnv<'local>, _class: JClass, font_ptr: jlong) {
+    FontResource::drop_handle(font_ptr);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraFont_00024JNI_setFallbacks<'local>(mut env: JNIEnv<'local>, _class: JClass, font_ptr: jlong, fallback_ptrs: JLongArray<'local>) {
+    let font = FontResource::from_handle(font_ptr).expect("font invalid");
+    let length = env.get_array_length(&fallback_ptrs).expect("invalid fallback array");
+    let mut ptrs = vec![0; length as usize];
+    env.get_long_array_region(&fallback_ptrs, 0, &mut ptrs).expect("invalid fallback array");
+    let fallbacks = ptrs.into_iter().map(|ptr| FontResource::from_handle(ptr).expect("fallback font invalid")).collect();
+    if let Err(err) = font.set_fallbacks(fallbacks) {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraFont_00024JNI_getLineHeight<'local>(mut env: JNIEnv<'local>, _class: JClass, font_ptr: jlong, size: jfloat) -> jfloat {
+    let font = FontResource::from_handle(font_ptr).expect("font invalid");
+    if !(size > 0.0 && size.is_finite()) {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("invalid font size {}", size)));
+        return 0.0;
+    }
+    let scaled = font.font.as_scaled(font.px_scale(size));
+    scaled.height() + scaled.line_gap()
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraFont_00024JNI_measure<'local>(mut env: JNIEnv<'local>, _class: JClass, font_ptr: jlong, desc: JObject<'local>, text: JString<'local>, mut vec2_obj: JObject<'local>) {
+    let font = FontResource::from_handle(font_ptr).expect("font invalid");
+    let text: String = env.get_string(&text).expect("invalid text").into();
+    let layout = text_layout_desc_from_java(&mut env, &desc)
+        .and_then(|desc| layout_text(&font, &desc, &text));
+    match layout {
+        Ok(layout) => set_joml_vector2f(env, &mut vec2_obj, layout.size[0], layout.size[1]),
+        Err(err) => throw_java_exception(&mut env, &err)
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraFont_00024JNI_layout<'local>(mut env: JNIEnv<'local>, _class: JClass, font_ptr: jlong, desc: JObject<'local>, text: JString<'local>, result: JObject<'local>) {
+    let font = FontResource::from_handle(font_ptr).expect("font invalid");
+    let text: String = env.get_string(&text).expect("invalid text").into();
+    let layout = text_layout_desc_from_java(&mut env, &desc)
+        .and_then(|desc| layout_text(&font, &desc, &text));
+    match layout {
+        Ok(layout) => set_java_text_layout(&mut env, &result, &layout),
+        Err(err) => throw_java_exception(&mut env, &err)
+    }
+}
@@ -0,0 +1,56 @@
+use jni::{JNIEnv, objects::{JObject, JClass, JValue}, sys::{jint, jlong, jlongArray}};
+
+use crate::{resource::gpu_memory::MemoryCategory, ui::JavaHandle, java_util::{throw_java_exception, JNIError}, engine_kernel::EngineKernel};
+
+fn category_from_java(env: &mut JNIEnv, code: jint) -> Option<MemoryCategory> {
+    let category = MemoryCategory::from_code(code as u32);
+    if category.is_none() {
+        throw_java_exception(env, &JNIError::IllegalArgument(format!("invalid memory category {}", code)));
+    }
+    category
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_GpuMemory_00024JNI_getTotal<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, category: jint) -> jlong {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(category) = category_from_java(&mut env, category) else { return 0 };
+    kernel.memory.total(category) as jlong
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_GpuMemory_00024JNI_getAllocations<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, list: JObject<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    for entry in kernel.memory.entries() {
+        let label = env.new_string(&entry.label).expect("failed to create label");
+        let allocation = env.new_object("org/terasology/engine/rust/GpuMemory$Allocation", "(JIJLjava/lang/String;)V", &[
+            JValue::Long(entry.id as jlong),
+            JValue::Int(entry.category as jint),
+            JValue::Long(entry.size as jlong),
+            JValue::Object(&label)
+        ]).expect("failed to create allocation");
+        env.call_method(&list, "add", "(Ljava/lang/Object;)Z", &[JValue::Object(&allocation)])
+            .expect("failed to add allocation");
+        env.delete_local_ref(allocation).expect("failed to delete allocation");
+        env.delete_local_ref(label).expect("failed to delete label");
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_GpuMemory_00024JNI_setBudget<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, category: jint, budget: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(category) = category_from_java(&mut env, category) else { return };
+    // a negative budget removes it
+    kernel.memory.set_budget(category, (budget >= 0).then_some(budget as u64));
+}
+
+// every category that went over its budget as (category, used, budget) triples
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_GpuMemory_00024JNI_takeBudgetEvents<'local>(env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) -> jlongArray {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let events: Vec<jlong> = kernel.memory.take_budget_events().iter()
+        .flat_map(|event| [event.category as jlong, event.used as jlong, event.budget as jlong])
+        .collect();
+    let result = env.new_long_array(events.len() as i32).expect("failed to allocate budget events");
+    env.set_long_array_region(&result, 0, &events).expect("failed to write budget events");
+    result.into_raw()
+}
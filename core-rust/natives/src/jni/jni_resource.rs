@@ -1,25 +1,17 @@
-use jni::{sys::jlong, objects::{JClass, JByteBuffer, JObject}, JNIEnv};
+use jni::{sys::{jboolean, jint, jlong, JNI_FALSE, JNI_TRUE}, objects::{JClass, JByteBuffer, JObject}, JNIEnv};
 use std::sync::Arc;
 use wgpu::util::DeviceExt;
-use crate::{resource::texture_resource::TextureResource, ui::JavaHandle, engine_kernel::EngineKernel};
-use super::jni_texture::JavaTextureDesc;
+use crate::{resource::{texture_resource::TextureResource, texture_decode::{decode_texture, texture_data_size, DecodeHints}}, ui::JavaHandle, engine_kernel::EngineKernel, java_util::{get_string_field, throw_java_exception, JNIError}};
+use super::jni_texture::{JavaImageFormat, JavaTextureDesc, texture_usages_from_java, validate_texture_desc};
 
 #[no_mangle]
 pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createTextureResourceFromBuffer<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, desc: JObject<'local>, buffer: JByteBuffer<'local>) -> jlong {
-    let texture_desc = JavaTextureDesc::new(&mut env, desc);
-    let wgpu_texture_desc = wgpu::TextureDescriptor {
-        size: wgpu::Extent3d {
-            width: texture_desc.width as u32,
-            height: texture_desc.height as u32,
-            depth_or_array_layers: texture_desc.layers as u32 
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: (&texture_desc.dim).into(), 
-        format: (&texture_desc.format).into(),
-        usage:  wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        label: None,
-        view_formats: &[],
+    let texture_desc = match JavaTextureDesc::new(&mut env, desc) {
+        Ok(texture_desc) => texture_desc,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
     };
     let buf_size = env.get_direct_buffer_capacity(&buffer)
             .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
@@ -30,40 +22,128 @@ pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_
     let kernel = EngineKernel::from_handle(kernel_ptr).expect("kernel invalid");
     // TODO: this is going to make all this single threaded 
     let surface = kernel.window_surface.lock().expect("failed to lock surface"); 
+    let wgpu_texture_desc = match texture_desc.to_texture_descriptor(&surface) {
+        Ok(wgpu_texture_desc) => wgpu_texture_desc,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
+    };
+    // the buffer holds every mip of the first layer, then every mip of the next one
+    let required = match texture_data_size(wgpu_texture_desc.format, wgpu_texture_desc.dimension, wgpu_texture_desc.size, wgpu_texture_desc.mip_level_count) {
+        Ok(required) => required,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
+    };
+    if buf_size < required {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("texture data holds {} bytes but {} are required for every layer and mip level", buf_size, required)));
+        return 0;
+    }
     let texture = surface.device.create_texture_with_data(
             &surface.queue,
             &wgpu_texture_desc
         , slice); 
     
-    TextureResource::to_handle(Arc::new(TextureResource {
-        texture
-    }))
+    let mut resource = TextureResource::new(&kernel.memory, texture, texture_desc.label.as_deref());
+    if texture_desc.is_cube() {
+        resource = resource.with_cube_faces();
+    }
+    TextureResource::to_handle(Arc::new(resource))
 }
 
 #[no_mangle]
 pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createTextureResource<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, desc: JObject<'local>) -> jlong {
-    let texture_desc = JavaTextureDesc::new(&mut env, desc);
+    let texture_desc = match JavaTextureDesc::new(&mut env, desc) {
+        Ok(texture_desc) => texture_desc,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
+    };
+    let kernel = EngineKernel::from_handle(kernel_ptr).expect("kernel invalid");
+    // TODO: this is going to make all this single threaded 
+    let surface = kernel.window_surface.lock().expect("failed to lock surface"); 
+    let wgpu_texture_desc = match texture_desc.to_texture_descriptor(&surface) {
+        Ok(wgpu_texture_desc) => wgpu_texture_desc,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
+    };
+    let texture = surface.device.create_texture(&wgpu_texture_desc); 
+    
+    let mut resource = TextureResource::new(&kernel.memory, texture, texture_desc.label.as_deref());
+    if texture_desc.is_cube() {
+        resource = resource.with_cube_faces();
+    }
+    TextureResource::to_handle(Arc::new(resource))
+}
+
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createTextureResourceFromEncoded<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, hints: JObject<'local>, buffer: JByteBuffer<'local>) -> jlong {
+    let srgb = env.get_field(&hints, "srgb", "Z").unwrap().z().unwrap();
+    let usage = env.get_field(&hints, "usage", "I").unwrap().i().unwrap();
+    let label = get_string_field(&mut env, &hints, "label");
+    let buf_size = env.get_direct_buffer_capacity(&buffer)
+            .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};   
+
+    let decoded = match decode_texture(slice, &DecodeHints { srgb }) {
+        Ok(decoded) => decoded,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return 0;
+        }
+    };
     let wgpu_texture_desc = wgpu::TextureDescriptor {
-        size: wgpu::Extent3d {
-            width: texture_desc.width as u32,
-            height: texture_desc.height as u32,
-            depth_or_array_layers: texture_desc.layers as u32 
-        },
-        mip_level_count: 1,
+        size: decoded.size,
+        mip_level_count: decoded.mip_level_count,
         sample_count: 1,
-        dimension: (&texture_desc.dim).into(), 
-        format: (&texture_desc.format).into(),
-        usage:  wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        label: None,
+        dimension: decoded.dimension,
+        format: decoded.format,
+        usage: texture_usages_from_java(usage as u32),
+        label: label.as_deref(),
         view_formats: &[],
     };
     let kernel = EngineKernel::from_handle(kernel_ptr).expect("kernel invalid");
-    // TODO: this is going to make all this single threaded 
     let surface = kernel.window_surface.lock().expect("failed to lock surface"); 
-    let texture = surface.device.create_texture(&wgpu_texture_desc); 
+    if let Err(err) = validate_texture_desc(&surface.device, &wgpu_texture_desc) {
+        throw_java_exception(&mut env, &err);
+        return 0;
+    }
+    let texture = surface.device.create_texture_with_data(
+            &surface.queue,
+            &wgpu_texture_desc,
+            &decoded.data);
     
-    TextureResource::to_handle(Arc::new(TextureResource {
-        texture
-    }))
+    let mut resource = TextureResource::new(&kernel.memory, texture, label.as_deref());
+    if decoded.cube {
+        resource = resource.with_cube_faces();
+    }
+    TextureResource::to_handle(Arc::new(resource))
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_setUploadBudget<'local>(_env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, bytes: jlong) {
+    let kernel = EngineKernel::from_handle(kernel_ptr).expect("kernel invalid");
+    kernel.uploads.lock().expect("failed to lock uploads").set_frame_budget(bytes.max(0) as u64);
 }
 
+// whether textures of the format can be created on this device, compressed and some depth formats are optional
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_isFormatSupported<'local>(_env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, format: jint) -> jboolean {
+    let kernel = EngineKernel::from_handle(kernel_ptr).expect("kernel invalid");
+    let Some(format) = JavaImageFormat::from_code(format).filter(|format| *format != JavaImageFormat::UNKNOWN) else { return JNI_FALSE };
+    let format: wgpu::TextureFormat = (&format).into();
+    let surface = kernel.window_surface.lock().expect("failed to lock surface");
+    match surface.device.features().contains(format.required_features()) {
+        true => JNI_TRUE,
+        false => JNI_FALSE
+    }
+}
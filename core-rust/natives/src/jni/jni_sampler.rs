@@ -0,0 +1,84 @@
+use jni::{JNIEnv, objects::{JObject, JClass}, sys::jlong};
+use std::sync::Arc;
+
+use crate::{resource::sampler_resource::{SamplerResource, SamplerDesc}, ui::JavaHandle, java_util::{throw_java_exception, JNIError, JNIResult}, engine_kernel::EngineKernel};
+
+// codes match TeraSampler.FilterMode, TeraSampler.AddressMode and TeraSampler.CompareFunction
+fn filter_mode_from_java(code: i32) -> JNIResult<wgpu::FilterMode> {
+    match code {
+        0 => Ok(wgpu::FilterMode::Nearest),
+        1 => Ok(wgpu::FilterMode::Linear),
+        _ => Err(JNIError::IllegalArgument(format!("invalid filter mode {}", code)))
+    }
+}
+
+fn address_mode_from_java(code: i32) -> JNIResult<wgpu::AddressMode> {
+    match code {
+        0 => Ok(wgpu::AddressMode::ClampToEdge),
+        1 => Ok(wgpu::AddressMode::Repeat),
+        2 => Ok(wgpu::AddressMode::MirrorRepeat),
+        _ => Err(JNIError::IllegalArgument(format!("invalid address mode {}", code)))
+    }
+}
+
+// 0 disables comparison
+fn compare_function_from_java(code: i32) -> JNIResult<Option<wgpu::CompareFunction>> {
+    Ok(Some(match code {
+        0 => return Ok(None),
+        1 => wgpu::CompareFunction::Never,
+        2 => wgpu::CompareFunction::Less,
+        3 => wgpu::CompareFunction::Equal,
+        4 => wgpu::CompareFunction::LessEqual,
+        5 => wgpu::CompareFunction::Greater,
+        6 => wgpu::CompareFunction::NotEqual,
+        7 => wgpu::CompareFunction::GreaterEqual,
+        8 => wgpu::CompareFunction::Always,
+        _ => return Err(JNIError::IllegalArgument(format!("invalid compare function {}", code)))
+    }))
+}
+
+fn sampler_desc_from_java(env: &mut JNIEnv, desc: &JObject) -> JNIResult<SamplerDesc> {
+    let mut int_field = |name: &str| env.get_field(desc, name, "I").unwrap().i().unwrap();
+    let mag_filter = filter_mode_from_java(int_field("magFilter"))?;
+    let min_filter = filter_mode_from_java(int_field("minFilter"))?;
+    let mipmap_filter = filter_mode_from_java(int_field("mipmapFilter"))?;
+    let address_modes = [
+        address_mode_from_java(int_field("addressModeU"))?,
+        address_mode_from_java(int_field("addressModeV"))?,
+        address_mode_from_java(int_field("addressModeW"))?
+    ];
+    let anisotropy = int_field("anisotropy");
+    let compare = compare_function_from_java(int_field("compare"))?;
+    let lod_min_clamp = env.get_field(desc, "lodMinClamp", "F").unwrap().f().unwrap();
+    let lod_max_clamp = env.get_field(desc, "lodMaxClamp", "F").unwrap().f().unwrap();
+    Ok(SamplerDesc {
+        mag_filter,
+        min_filter,
+        mipmap_filter,
+        address_modes,
+        anisotropy: anisotropy.clamp(0, u16::MAX as i32) as u16,
+        lod_min_clamp,
+        lod_max_clamp,
+        compare
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_ResourceManager_00024JNI_createSampler<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, desc: JObject<'local>) -> jlong {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let surface = kernel.window_surface.lock().expect("failed to lock surface");
+    let sampler = sampler_desc_from_java(&mut env, &desc)
+        .and_then(|desc| SamplerResource::new(&surface.device, &desc));
+    match sampler {
+        Ok(sampler) => SamplerResource::to_handle(Arc::new(sampler)),
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraSampler_00024JNI_drop<'local>(mut _env: JNIEnv<'local>, _class: JClass, sampler_ptr: jlong) {
+    SamplerResource::drop_handle(sampler_ptr);
+}
@@ -1,41 +1,192 @@
-use jni::{JNIEnv, objects::{JObject, JClass, JByteBuffer}, sys::{jint, jlong}};
+use jni::{JNIEnv, objects::{GlobalRef, JObject, JClass, JByteBuffer, JValue}, sys::{jint, jlong}};
 
-use crate::{resource::texture_resource::TextureResource, ui::JavaHandle, java_util::set_joml_vector2f, engine_kernel::EngineKernel};
-use crate::resource::texture_resource::TextureFormatExt;
+use crate::{window_surface::WindowSurface, resource::texture_resource::{TextureResource, TextureRegion}, ui::JavaHandle, java_util::{get_string_field, set_joml_vector2f, throw_java_exception, to_java_exception, JNIError, JNIResult}, engine_kernel::EngineKernel};
+
+// bits of TeraTexture.TextureUsage, TEXTURE_BINDING and COPY_DST are always set
+const TEXTURE_USAGE_COPY_SRC: u32 = 0x1;
+const TEXTURE_USAGE_RENDER_ATTACHMENT: u32 = 0x2;
 
 pub struct JavaTextureDesc {
    pub width: u32,
    pub height: u32,
    pub layers: u32,
+   pub mips: u32,
+   pub usage: u32,
    pub dim: JavaTextureDim,
-   pub format: JavaImageFormat
+   pub format: JavaImageFormat,
+   pub label: Option<String>
 }
 
 impl JavaTextureDesc {
-    pub fn new<'local>(env: &mut JNIEnv<'local>, obj: JObject<'local>) -> Self {
-        let width = env.get_field(&obj, "width", "I").unwrap().i().unwrap();
-        let height = env.get_field(&obj, "height", "I").unwrap().i().unwrap();
-        let layer = env.get_field(&obj, "layers", "I").unwrap().i().unwrap();
-        let dim = env.get_field(&obj, "dim", "I").unwrap().i().unwrap();
-        let format = env.get_field(&obj, "format", "I").unwrap().i().unwrap();
-    
-        let texture_format  = unsafe { std::mem::transmute::<jint, JavaImageFormat>(format) };
-        let texture_dim  = unsafe { std::mem::transmute::<jint, JavaTextureDim> (dim) };
-        Self {
+    // only checks the fields on their own, `to_texture_descriptor` checks them against the device
+    pub fn new<'local>(env: &mut JNIEnv<'local>, obj: JObject<'local>) -> JNIResult<Self> {
+        let mut int_field = |name: &str| env.get_field(&obj, name, "I").unwrap().i().unwrap();
+        let width = int_field("width");
+        let height = int_field("height");
+        let layers = int_field("layers");
+        let mips = int_field("mipLevels");
+        let usage = int_field("usage");
+        let dim = int_field("dim");
+        let format = int_field("format");
+        let label = get_string_field(env, &obj, "label");
+
+        let format = JavaImageFormat::from_code(format)
+            .filter(|format| *format != JavaImageFormat::UNKNOWN)
+            .ok_or_else(|| JNIError::IllegalArgument(format!("invalid image format {}", format)))?;
+        let dim = JavaTextureDim::from_code(dim)
+            .ok_or_else(|| JNIError::IllegalArgument(format!("invalid texture dimension {}", dim)))?;
+        if width <= 0 || height <= 0 || layers <= 0 {
+            return Err(JNIError::IllegalArgument(format!("texture size {}x{}x{} has to be positive", width, height, layers)));
+        }
+        if mips <= 0 {
+            return Err(JNIError::IllegalArgument(format!("texture needs at least one mip level, got {}", mips)));
+        }
+        if usage as u32 & !(TEXTURE_USAGE_COPY_SRC | TEXTURE_USAGE_RENDER_ATTACHMENT) != 0 {
+            return Err(JNIError::IllegalArgument(format!("invalid texture usage flags {:#x}", usage)));
+        }
+        Ok(Self {
             width: width as u32,
             height: height as u32,
-            layers: layer as u32,
-            dim: texture_dim,
-            format: texture_format
+            layers: layers as u32,
+            mips: mips as u32,
+            usage: usage as u32,
+            dim,
+            format,
+            label
+        })
+    }
+
+    pub fn texture_usages(&self) -> wgpu::TextureUsages {
+        texture_usages_from_java(self.usage)
+    }
+
+    pub fn is_cube(&self) -> bool {
+        self.dim == JavaTextureDim::DIM_CUBE
+    }
+
+    pub fn to_texture_descriptor(&self, surface: &WindowSurface) -> JNIResult<wgpu::TextureDescriptor<'_>> {
+        if self.is_cube() {
+            if self.width != self.height {
+                return Err(JNIError::IllegalArgument(format!("cube faces have to be square, got {}x{}", self.width, self.height)));
+            }
+            if !self.layers.is_multiple_of(6) {
+                return Err(JNIError::IllegalArgument(format!("cube textures need 6 layers per cube, got {}", self.layers)));
+            }
+            if self.layers > 6 && !surface.adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::CUBE_ARRAY_TEXTURES) {
+                return Err(JNIError::IllegalArgument("cube array textures are not supported by the device".to_string()));
+            }
         }
+        let desc = wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: self.layers
+            },
+            mip_level_count: self.mips,
+            sample_count: 1,
+            dimension: (&self.dim).into(),
+            format: (&self.format).into(),
+            usage: self.texture_usages(),
+            label: self.label.as_deref(),
+            view_formats: &[],
+        };
+        validate_texture_desc(&surface.device, &desc)?;
+        Ok(desc)
+    }
+}
+
+pub fn texture_usages_from_java(usage: u32) -> wgpu::TextureUsages {
+    let mut usages = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+    if usage & TEXTURE_USAGE_COPY_SRC != 0 {
+        usages |= wgpu::TextureUsages::COPY_SRC;
+    }
+    if usage & TEXTURE_USAGE_RENDER_ATTACHMENT != 0 {
+        usages |= wgpu::TextureUsages::RENDER_ATTACHMENT;
     }
+    usages
 }
 
+fn validate_texture_usages(device: &wgpu::Device, format: wgpu::TextureFormat, usages: wgpu::TextureUsages) -> JNIResult<()> {
+    let missing = format.required_features() - device.features();
+    if !missing.is_empty() {
+        return Err(JNIError::IllegalArgument(format!("texture format {:?} requires unsupported device features {:?}", format, missing)));
+    }
+    let allowed = format.guaranteed_format_features(device.features()).allowed_usages;
+    if !allowed.contains(usages) {
+        return Err(JNIError::IllegalArgument(format!("texture format {:?} does not support {:?} usage", format, usages - allowed)));
+    }
+    Ok(())
+}
+
+// checks everything wgpu would otherwise report as a device error, so bad descriptors from java end up as exceptions
+pub fn validate_texture_desc(device: &wgpu::Device, desc: &wgpu::TextureDescriptor) -> JNIResult<()> {
+    let limits = device.limits();
+    let size = desc.size;
+    if size.width == 0 || size.height == 0 || size.depth_or_array_layers == 0 {
+        return Err(JNIError::IllegalArgument(format!("texture size {}x{}x{} has to be positive", size.width, size.height, size.depth_or_array_layers)));
+    }
+    let (max_extent, max_layers) = match desc.dimension {
+        wgpu::TextureDimension::D1 => {
+            if size.height != 1 || size.depth_or_array_layers != 1 {
+                return Err(JNIError::IllegalArgument(format!("1D textures need a height and layer count of 1, got {}x{}", size.height, size.depth_or_array_layers)));
+            }
+            (limits.max_texture_dimension_1d, 1)
+        },
+        wgpu::TextureDimension::D2 => (limits.max_texture_dimension_2d, limits.max_texture_array_layers),
+        wgpu::TextureDimension::D3 => (limits.max_texture_dimension_3d, limits.max_texture_dimension_3d)
+    };
+    if size.width > max_extent || size.height > max_extent {
+        return Err(JNIError::IllegalArgument(format!("texture size {}x{} exceeds the device limit of {} for {:?} textures", size.width, size.height, max_extent, desc.dimension)));
+    }
+    if size.depth_or_array_layers > max_layers {
+        return Err(JNIError::IllegalArgument(format!("{} layers exceed the device limit of {} for {:?} textures", size.depth_or_array_layers, max_layers, desc.dimension)));
+    }
+    let max_mips = size.max_mips(desc.dimension);
+    if desc.mip_level_count == 0 || desc.mip_level_count > max_mips {
+        return Err(JNIError::IllegalArgument(format!("{} mip levels are invalid, a {}x{} texture has at most {}", desc.mip_level_count, size.width, size.height, max_mips)));
+    }
+
+    let format = desc.format;
+    let (block_width, block_height) = format.block_dimensions();
+    if format.is_compressed() {
+        if desc.dimension != wgpu::TextureDimension::D2 {
+            return Err(JNIError::IllegalArgument(format!("compressed format {:?} is only supported for 2D textures", format)));
+        }
+        if !size.width.is_multiple_of(block_width) || !size.height.is_multiple_of(block_height) {
+            return Err(JNIError::IllegalArgument(format!("texture size {}x{} is not a multiple of the {}x{} block size of {:?}", size.width, size.height, block_width, block_height, format)));
+        }
+    }
+    if format.is_depth_stencil_format() && desc.dimension != wgpu::TextureDimension::D2 {
+        return Err(JNIError::IllegalArgument(format!("depth format {:?} is only supported for 2D textures", format)));
+    }
+    if desc.usage.contains(wgpu::TextureUsages::RENDER_ATTACHMENT) && desc.dimension != wgpu::TextureDimension::D2 {
+        return Err(JNIError::IllegalArgument(format!("only 2D textures can be render attachments, got {:?}", desc.dimension)));
+    }
+    validate_texture_usages(device, format, desc.usage)
+}
+
+// codes match TeraTexture.TextureDimension
 #[repr(u32)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
 pub enum JavaTextureDim {
-    DIM_1D,
-    DIM_2D,
-    DIM_3D
+    DIM_1D = 0,
+    DIM_2D = 1,
+    DIM_3D = 2,
+    // a 2D array whose layers are the faces of one or more cubes
+    DIM_CUBE = 3
+}
+
+impl JavaTextureDim {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(JavaTextureDim::DIM_1D),
+            1 => Some(JavaTextureDim::DIM_2D),
+            2 => Some(JavaTextureDim::DIM_3D),
+            3 => Some(JavaTextureDim::DIM_CUBE),
+            _ => None
+        }
+    }
 }
 
 impl From<&JavaTextureDim> for wgpu::TextureDimension {
@@ -44,32 +195,104 @@ impl From<&JavaTextureDim> for wgpu::TextureDimension {
            JavaTextureDim::DIM_1D => wgpu::TextureDimension::D1, 
            JavaTextureDim::DIM_2D => wgpu::TextureDimension::D2, 
            JavaTextureDim::DIM_3D => wgpu::TextureDimension::D3, 
+           JavaTextureDim::DIM_CUBE => wgpu::TextureDimension::D2,
         }
     }
 }
 
+// codes match TeraTexture.ImageFormat, gaps leave room to grow each family
 #[repr(u32)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
 pub enum JavaImageFormat {
-    UNKNOWN,
-    R8_UNORM,
-    R8_SNORM,
-    R8_UINT,
-    R8_SINT,
-    R8G8_UNORM,
-    R8G8_SNORM,
-    R8G8_UINT,
-    R8G8_SINT,
-    R16_UNORM,
-    R16_SNORM,
-    R16_UINT,
-    R16_SINT,
-    R8G8B8A8_UNORM,
-    R8G8B8A8_SNORM,
-    R8G8B8A8_UINT,
-    R8G8B8A8_SINT,
-    R8G8B8A8_SRGB,
+    UNKNOWN = 0,
+    R8_UNORM = 1,
+    R8_SNORM = 2,
+    R8_UINT = 3,
+    R8_SINT = 4,
+    R8G8_UNORM = 5,
+    R8G8_SNORM = 6,
+    R8G8_UINT = 7,
+    R8G8_SINT = 8,
+    R16_UNORM = 9,
+    R16_SNORM = 10,
+    R16_UINT = 11,
+    R16_SINT = 12,
+    R8G8B8A8_UNORM = 13,
+    R8G8B8A8_SNORM = 14,
+    R8G8B8A8_UINT = 15,
+    R8G8B8A8_SINT = 16,
+    R8G8B8A8_SRGB = 17,
+
+    B8G8R8A8_UNORM = 18,
+    B8G8R8A8_SRGB = 19,
+
+    R16_FLOAT = 32,
+    R16G16B16A16_FLOAT = 33,
+    R32_FLOAT = 34,
+    R11G11B10_FLOAT = 35,
+
+    D16_UNORM = 48,
+    D24_PLUS = 49,
+    D24_PLUS_S8_UINT = 50,
+    D32_FLOAT = 51,
+    D32_FLOAT_S8_UINT = 52,
+    S8_UINT = 53,
+
+    BC1_RGBA_UNORM = 64,
+    BC1_RGBA_SRGB = 65,
+    BC2_RGBA_UNORM = 66,
+    BC2_RGBA_SRGB = 67,
+    BC3_RGBA_UNORM = 68,
+    BC3_RGBA_SRGB = 69,
+    BC4_R_UNORM = 70,
+    BC4_R_SNORM = 71,
+    BC5_RG_UNORM = 72,
+    BC5_RG_SNORM = 73,
+    BC6H_RGB_UFLOAT = 74,
+    BC6H_RGB_FLOAT = 75,
+    BC7_RGBA_UNORM = 76,
+    BC7_RGBA_SRGB = 77,
+
+    ETC2_RGB8_UNORM = 96,
+    ETC2_RGB8_SRGB = 97,
+    ETC2_RGB8A1_UNORM = 98,
+    ETC2_RGB8A1_SRGB = 99,
+    ETC2_RGBA8_UNORM = 100,
+    ETC2_RGBA8_SRGB = 101,
+    EAC_R11_UNORM = 102,
+    EAC_R11_SNORM = 103,
+    EAC_RG11_UNORM = 104,
+    EAC_RG11_SNORM = 105,
 }
 
+impl JavaImageFormat {
+    const ALL: [JavaImageFormat; 54] = [
+        JavaImageFormat::UNKNOWN, JavaImageFormat::R8_UNORM, JavaImageFormat::R8_SNORM,
+        JavaImageFormat::R8_UINT, JavaImageFormat::R8_SINT, JavaImageFormat::R8G8_UNORM,
+        JavaImageFormat::R8G8_SNORM, JavaImageFormat::R8G8_UINT, JavaImageFormat::R8G8_SINT,
+        JavaImageFormat::R16_UNORM, JavaImageFormat::R16_SNORM, JavaImageFormat::R16_UINT,
+        JavaImageFormat::R16_SINT, JavaImageFormat::R8G8B8A8_UNORM, JavaImageFormat::R8G8B8A8_SNORM,
+        JavaImageFormat::R8G8B8A8_UINT, JavaImageFormat::R8G8B8A8_SINT, JavaImageFormat::R8G8B8A8_SRGB,
+        JavaImageFormat::B8G8R8A8_UNORM, JavaImageFormat::B8G8R8A8_SRGB, JavaImageFormat::R16_FLOAT,
+        JavaImageFormat::R16G16B16A16_FLOAT, JavaImageFormat::R32_FLOAT, JavaImageFormat::R11G11B10_FLOAT,
+        JavaImageFormat::D16_UNORM, JavaImageFormat::D24_PLUS, JavaImageFormat::D24_PLUS_S8_UINT,
+        JavaImageFormat::D32_FLOAT, JavaImageFormat::D32_FLOAT_S8_UINT, JavaImageFormat::S8_UINT,
+        JavaImageFormat::BC1_RGBA_UNORM, JavaImageFormat::BC1_RGBA_SRGB, JavaImageFormat::BC2_RGBA_UNORM,
+        JavaImageFormat::BC2_RGBA_SRGB, JavaImageFormat::BC3_RGBA_UNORM, JavaImageFormat::BC3_RGBA_SRGB,
+        JavaImageFormat::BC4_R_UNORM, JavaImageFormat::BC4_R_SNORM, JavaImageFormat::BC5_RG_UNORM,
+        JavaImageFormat::BC5_RG_SNORM, JavaImageFormat::BC6H_RGB_UFLOAT, JavaImageFormat::BC6H_RGB_FLOAT,
+        JavaImageFormat::BC7_RGBA_UNORM, JavaImageFormat::BC7_RGBA_SRGB, JavaImageFormat::ETC2_RGB8_UNORM,
+        JavaImageFormat::ETC2_RGB8_SRGB, JavaImageFormat::ETC2_RGB8A1_UNORM,
+        JavaImageFormat::ETC2_RGB8A1_SRGB, JavaImageFormat::ETC2_RGBA8_UNORM,
+        JavaImageFormat::ETC2_RGBA8_SRGB, JavaImageFormat::EAC_R11_UNORM, JavaImageFormat::EAC_R11_SNORM,
+        JavaImageFormat::EAC_RG11_UNORM, JavaImageFormat::EAC_RG11_SNORM
+    ];
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        JavaImageFormat::ALL.iter().copied().find(|format| *format as i32 == code)
+    }
+}
 
 impl From<&JavaImageFormat> for wgpu::TextureFormat {
     fn from(item: &JavaImageFormat) -> Self {
@@ -93,7 +316,48 @@ impl From<&JavaImageFormat> for wgpu::TextureFormat {
             JavaImageFormat::R8G8B8A8_UINT => wgpu::TextureFormat::Rgba8Uint,
             JavaImageFormat::R8G8B8A8_SINT => wgpu::TextureFormat::Rgba8Sint,
             JavaImageFormat::R8G8B8A8_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
-            _ => panic!("invalid image format"),
+
+            JavaImageFormat::B8G8R8A8_UNORM => wgpu::TextureFormat::Bgra8Unorm,
+            JavaImageFormat::B8G8R8A8_SRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+
+            JavaImageFormat::R16_FLOAT => wgpu::TextureFormat::R16Float,
+            JavaImageFormat::R16G16B16A16_FLOAT => wgpu::TextureFormat::Rgba16Float,
+            JavaImageFormat::R32_FLOAT => wgpu::TextureFormat::R32Float,
+            JavaImageFormat::R11G11B10_FLOAT => wgpu::TextureFormat::Rg11b10Float,
+
+            JavaImageFormat::D16_UNORM => wgpu::TextureFormat::Depth16Unorm,
+            JavaImageFormat::D24_PLUS => wgpu::TextureFormat::Depth24Plus,
+            JavaImageFormat::D24_PLUS_S8_UINT => wgpu::TextureFormat::Depth24PlusStencil8,
+            JavaImageFormat::D32_FLOAT => wgpu::TextureFormat::Depth32Float,
+            JavaImageFormat::D32_FLOAT_S8_UINT => wgpu::TextureFormat::Depth32FloatStencil8,
+            JavaImageFormat::S8_UINT => wgpu::TextureFormat::Stencil8,
+
+            JavaImageFormat::BC1_RGBA_UNORM => wgpu::TextureFormat::Bc1RgbaUnorm,
+            JavaImageFormat::BC1_RGBA_SRGB => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            JavaImageFormat::BC2_RGBA_UNORM => wgpu::TextureFormat::Bc2RgbaUnorm,
+            JavaImageFormat::BC2_RGBA_SRGB => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+            JavaImageFormat::BC3_RGBA_UNORM => wgpu::TextureFormat::Bc3RgbaUnorm,
+            JavaImageFormat::BC3_RGBA_SRGB => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            JavaImageFormat::BC4_R_UNORM => wgpu::TextureFormat::Bc4RUnorm,
+            JavaImageFormat::BC4_R_SNORM => wgpu::TextureFormat::Bc4RSnorm,
+            JavaImageFormat::BC5_RG_UNORM => wgpu::TextureFormat::Bc5RgUnorm,
+            JavaImageFormat::BC5_RG_SNORM => wgpu::TextureFormat::Bc5RgSnorm,
+            JavaImageFormat::BC6H_RGB_UFLOAT => wgpu::TextureFormat::Bc6hRgbUfloat,
+            JavaImageFormat::BC6H_RGB_FLOAT => wgpu::TextureFormat::Bc6hRgbFloat,
+            JavaImageFormat::BC7_RGBA_UNORM => wgpu::TextureFormat::Bc7RgbaUnorm,
+            JavaImageFormat::BC7_RGBA_SRGB => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+
+            JavaImageFormat::ETC2_RGB8_UNORM => wgpu::TextureFormat::Etc2Rgb8Unorm,
+            JavaImageFormat::ETC2_RGB8_SRGB => wgpu::TextureFormat::Etc2Rgb8UnormSrgb,
+            JavaImageFormat::ETC2_RGB8A1_UNORM => wgpu::TextureFormat::Etc2Rgb8A1Unorm,
+            JavaImageFormat::ETC2_RGB8A1_SRGB => wgpu::TextureFormat::Etc2Rgb8A1UnormSrgb,
+            JavaImageFormat::ETC2_RGBA8_UNORM => wgpu::TextureFormat::Etc2Rgba8Unorm,
+            JavaImageFormat::ETC2_RGBA8_SRGB => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+            JavaImageFormat::EAC_R11_UNORM => wgpu::TextureFormat::EacR11Unorm,
+            JavaImageFormat::EAC_R11_SNORM => wgpu::TextureFormat::EacR11Snorm,
+            JavaImageFormat::EAC_RG11_UNORM => wgpu::TextureFormat::EacRg11Unorm,
+            JavaImageFormat::EAC_RG11_SNORM => wgpu::TextureFormat::EacRg11Snorm,
+            JavaImageFormat::UNKNOWN => panic!("invalid image format"),
         }
     }
 }
@@ -124,15 +388,189 @@ pub extern "system" fn Java_org_terasology_engine_rust_TeraTexture_00024JNI_writ
     let surface = kernel.window_surface.lock().expect("failed to resolve surface");
     let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};
 
-    let format = texture_resource.texture.format().bit_size_block() / 8;
-    surface.queue.write_texture(
-        texture_resource.texture.as_image_copy(),
-        &slice,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(format),
-            rows_per_image: None,
+    // queued uploads would overwrite a direct write, see UploadQueue
+    let mut uploads = kernel.uploads.lock().expect("failed to lock uploads");
+    let result = match uploads.has_pending(&texture_resource) {
+        true => uploads.write_texture_all(&texture_resource, slice).map(|_| ()),
+        false => texture_resource.write_all(&surface.queue, slice)
+    };
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraTexture_00024JNI_writeTextureRegion<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, texture_ptr: jlong,
+        x: jint, y: jint, layer: jint, mip: jint, width: jint, height: jint, buffer: JByteBuffer<'local>, stride: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let texture_resource = TextureResource::from_handle(texture_ptr).expect("texture invalid"); 
+
+    let buf_size = env
+        .get_direct_buffer_capacity(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};
+
+    let region = TextureRegion {
+        x: x as u32,
+        y: y as u32,
+        layer: layer as u32,
+        mip: mip as u32,
+        width: width as u32,
+        height: height as u32
+    };
+    // queued uploads would overwrite a direct write, see UploadQueue
+    let mut uploads = kernel.uploads.lock().expect("failed to lock uploads");
+    let result = match uploads.has_pending(&texture_resource) {
+        true => uploads.write_texture(&texture_resource, &region, slice, stride as u32).map(|_| ()),
+        false => texture_resource.write_region(&surface.queue, &region, slice, stride as u32)
+    };
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraTexture_00024JNI_readTexture<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, texture_ptr: jlong,
+        x: jint, y: jint, width: jint, height: jint, mip: jint, layer: jint, future: JObject<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let texture_resource = TextureResource::from_handle(texture_ptr).expect("texture invalid"); 
+
+    let region = TextureRegion {
+        x: x as u32,
+        y: y as u32,
+        layer: layer as u32,
+        mip: mip as u32,
+        width: width as u32,
+        height: height as u32
+    };
+    let readback = {
+        let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+        texture_resource.read_region(&surface.device, &surface.queue, &kernel.memory, &region)
+    };
+    match readback {
+        Ok(readback) => {
+            let future_ref = env.new_global_ref(future).expect("failed to reference future");
+            kernel.texture_readbacks.lock().expect("failed to lock readbacks").push((readback, future_ref));
+        },
+        Err(err) => throw_java_exception(&mut env, &err)
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraTexture_00024JNI_writeTextureBufferAsync<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, texture_ptr: jlong,
+        buffer: JByteBuffer<'local>, future: JObject<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let texture_resource = TextureResource::from_handle(texture_ptr).expect("texture invalid"); 
+
+    let buf_size = env
+        .get_direct_buffer_capacity(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};
+
+    let upload = kernel.uploads.lock().expect("failed to lock uploads").write_texture_all(&texture_resource, slice);
+    queue_upload_future(&mut env, &kernel, upload, future);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraTexture_00024JNI_writeTextureRegionAsync<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, texture_ptr: jlong,
+        x: jint, y: jint, layer: jint, mip: jint, width: jint, height: jint, buffer: JByteBuffer<'local>, stride: jint, future: JObject<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let texture_resource = TextureResource::from_handle(texture_ptr).expect("texture invalid"); 
+
+    let buf_size = env
+        .get_direct_buffer_capacity(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let buf = env
+        .get_direct_buffer_address(&buffer)
+        .expect("Unable to get address to direct buffer. Buffer must be allocated direct.");
+    let slice = unsafe {std::slice::from_raw_parts(buf, buf_size)};
+
+    let region = TextureRegion {
+        x: x as u32,
+        y: y as u32,
+        layer: layer as u32,
+        mip: mip as u32,
+        width: width as u32,
+        height: height as u32
+    };
+    let upload = kernel.uploads.lock().expect("failed to lock uploads").write_texture(&texture_resource, &region, slice, stride as u32);
+    queue_upload_future(&mut env, &kernel, upload, future);
+}
+
+fn queue_upload_future(env: &mut JNIEnv, kernel: &EngineKernel, upload: JNIResult<u64>, future: JObject) {
+    match upload {
+        Ok(id) => {
+            let future_ref = env.new_global_ref(future).expect("failed to reference future");
+            kernel.upload_futures.lock().expect("failed to lock upload futures").push((id, future_ref));
         },
-        wgpu::Extent3d::default(),
-    );
+        Err(err) => throw_java_exception(env, &err)
+    }
+}
+
+// completes the java futures of every upload the gpu has finished
+pub fn resolve_uploads(env: &mut JNIEnv, kernel: &EngineKernel) {
+    // polling also hands finished staging chunks back to the upload queue
+    kernel.window_surface.lock().expect("failed to resolve surface").device.poll(wgpu::Maintain::Poll);
+    let completed = kernel.uploads.lock().expect("failed to lock uploads").take_completed();
+    if completed.is_empty() {
+        return;
+    }
+    // completing runs the dependent stages on this thread, they may queue more uploads
+    let finished: Vec<(u64, GlobalRef)> = kernel.upload_futures.lock().expect("failed to lock upload futures")
+        .extract_if(.., |(id, _)| completed.contains(id))
+        .collect();
+    for (_, future) in finished {
+        env.call_method(future.as_obj(), "complete", "(Ljava/lang/Object;)Z", &[JValue::Object(&JObject::null())])
+            .expect("failed to complete upload");
+    }
+}
+
+// completes the java futures of every readback the gpu has finished
+pub fn resolve_texture_readbacks(env: &mut JNIEnv, kernel: &EngineKernel) {
+    if kernel.texture_readbacks.lock().expect("failed to lock readbacks").is_empty() {
+        return;
+    }
+    kernel.window_surface.lock().expect("failed to resolve surface").device.poll(wgpu::Maintain::Poll);
+
+    // completing runs the dependent stages on this thread, they may read back more textures
+    let resolved: Vec<(JNIResult<Vec<u8>>, GlobalRef)> = {
+        let mut readbacks = kernel.texture_readbacks.lock().expect("failed to lock readbacks");
+        let mut resolved = Vec::new();
+        let mut index = 0;
+        while index < readbacks.len() {
+            let Some(result) = readbacks[index].0.try_resolve() else {
+                index += 1;
+                continue;
+            };
+            let (_, future) = readbacks.swap_remove(index);
+            resolved.push((result, future));
+        }
+        resolved
+    };
+    for (result, future) in resolved {
+        match result {
+            Ok(data) => {
+                let java_buffer = env.call_static_method("java/nio/ByteBuffer", "allocateDirect", "(I)Ljava/nio/ByteBuffer;", &[JValue::Int(data.len() as jint)])
+                    .and_then(|value| value.l())
+                    .expect("failed to allocate readback buffer");
+                let java_buffer = JByteBuffer::from(java_buffer);
+                let address = env.get_direct_buffer_address(&java_buffer).expect("failed to resolve readback buffer");
+                unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), address, data.len()) };
+                env.call_method(future.as_obj(), "complete", "(Ljava/lang/Object;)Z", &[JValue::Object(&java_buffer)])
+                    .expect("failed to complete readback");
+            },
+            Err(err) => {
+                let exception = to_java_exception(env, &err).expect("failed to create exception");
+                env.call_method(future.as_obj(), "completeExceptionally", "(Ljava/lang/Throwable;)Z", &[JValue::Object(&exception)])
+                    .expect("failed to complete readback");
+            }
+        }
+    }
 }
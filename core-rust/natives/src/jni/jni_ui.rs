@@ -1,5 +1,38 @@
-use jni::{sys::{jlong, jfloat, jint}, objects::{JClass, JByteBuffer, JObject}, JNIEnv};
-use crate::{engine_kernel::EngineKernel, ui::JavaHandle, math::rect::Rect, resource::texture_resource::TextureResource};
+use jni::{sys::{jlong, jfloat, jint, jboolean, JNI_FALSE}, objects::{JClass, JFloatArray, JObject, JString}, JNIEnv};
+use super::jni_font::text_layout_desc_from_java;
+use crate::{engine_kernel::EngineKernel, ui::{JavaHandle, NineSlice, UIBlendMode, UICommandList, UserInterface}, math::rect::Rect, resource::{texture_resource::TextureResource, texture_atlas::AtlasSubTexture, sampler_resource::SamplerResource, font_resource::FontResource, text_layout::layout_text}, java_util::{throw_java_exception, JNIError, JNIResult}};
+
+// a sampler pointer of 0 selects the default sampler
+pub fn set_draw_sampler(ui: &mut UserInterface, sampler_ptr: jlong) -> JNIResult<()> {
+    let sampler = match sampler_ptr {
+        0 => None,
+        ptr => Some(SamplerResource::from_handle(ptr).expect("invalid sampler"))
+    };
+    ui.cmd_set_sampler(sampler)
+}
+
+// codes match UIRenderer.BlendMode
+pub fn blend_mode_from_java(code: jint) -> JNIResult<UIBlendMode> {
+    match code {
+        0 => Ok(UIBlendMode::Alpha),
+        1 => Ok(UIBlendMode::Premultiplied),
+        2 => Ok(UIBlendMode::Additive),
+        3 => Ok(UIBlendMode::Multiply),
+        4 => Ok(UIBlendMode::Replace),
+        _ => Err(JNIError::IllegalArgument(format!("invalid blend mode {}", code)))
+    }
+}
+
+// an RGBA color packed into an int, like the tint color of draws
+pub fn clear_color_from_java(color: jint) -> wgpu::Color {
+    let c: [u8; 4] = bytemuck::cast(color);
+    wgpu::Color {
+        r: c[3] as f64 / 255.0,
+        g: c[2] as f64 / 255.0,
+        b: c[1] as f64 / 255.0,
+        a: c[0] as f64 / 255.0
+    }
+}
 
 #[no_mangle]
 pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUISetCrop(_jni: JNIEnv, _class: JClass,
@@ -19,22 +52,136 @@ pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUI
     ui.cmd_set_crop(None);
 }
 
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIPushCrop(_jni: JNIEnv, _class: JClass,
+        kernel_ptr: jlong, min_x: jfloat, min_y: jfloat, max_x: jfloat, max_y: jfloat) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    ui.cmd_push_crop(Rect {
+        min: [min_x, min_y],
+        max: [max_x, max_y]
+    });
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIPopCrop<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = ui.cmd_pop_crop() {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIPushTransform(_jni: JNIEnv, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    ui.cmd_push_transform();
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIPopTransform<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = ui.cmd_pop_transform() {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUITranslate(_jni: JNIEnv, _class: JClass, kernel_ptr: jlong, x: jfloat, y: jfloat) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    ui.cmd_translate([x, y]);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIRotate(_jni: JNIEnv, _class: JClass, kernel_ptr: jlong, radians: jfloat) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    ui.cmd_rotate(radians);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIScale(_jni: JNIEnv, _class: JClass, kernel_ptr: jlong, x: jfloat, y: jfloat) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    ui.cmd_scale([x, y]);
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUISetBlendMode<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong, blend_mode: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    match blend_mode_from_java(blend_mode) {
+        Ok(blend) => ui.cmd_set_blend_mode(blend),
+        Err(err) => throw_java_exception(&mut env, &err)
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIBeginList<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = ui.cmd_begin_list() {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIEndList<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) -> jlong {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    match ui.cmd_end_list(&surface.device) {
+        Ok(list) => UICommandList::to_handle(list),
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            0
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawList<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        list_ptr: jlong,
+        x: jfloat, y: jfloat,
+        clip: jboolean,
+        clip_min_x: jfloat, clip_min_y: jfloat, clip_max_x: jfloat, clip_max_y: jfloat) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(list) = UICommandList::from_handle(list_ptr) else { panic!("invalid command list") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    let clip = (clip != JNI_FALSE).then_some(Rect {
+        min: [clip_min_x, clip_min_y],
+        max: [clip_max_x, clip_max_y]
+    });
+    if let Err(err) = ui.cmd_draw_list(&list, [x, y], clip) {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_TeraUICommandList_00024JNI_drop<'local>(mut _env: JNIEnv<'local>, _class: JClass, list_ptr: jlong) {
+    UICommandList::drop_handle(list_ptr);
+}
+
 #[no_mangle]
 pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawTexture<'local>(mut env: JNIEnv<'local>, _class: JClass, 
         kernel_ptr: jlong,
         tex_ptr: jlong,
+        layer: jint,
         uv_min_x: jfloat, uv_min_y: jfloat, uv_max_x: jfloat, uv_max_y: jfloat,
         pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
-        tint_color: jint) {
+        tint_color: jint,
+        sampler_ptr: jlong) {
         let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
         let Some(texture_resource) = TextureResource::from_handle(tex_ptr) else {panic!("invalid tex resource")};
        
-        let surface = kernel.window_surface.lock().expect("failed to resolve surface");
         let mut ui = kernel.user_interface.borrow_mut();
-        ui.cmd_draw_texture(
-            &surface.queue,
-            &surface.device,
+        let result = set_draw_sampler(&mut ui, sampler_ptr).and_then(|_| ui.cmd_draw_texture(
             &texture_resource,
+            layer as u32,
             &Rect {
                 min: [uv_min_x, uv_min_y],
                 max: [uv_max_x, uv_max_y]
@@ -44,8 +191,308 @@ pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUI
                 max: [pos_max_x, pos_max_y]
             },
             tint_color as u32
-        );
+        ));
+        if let Err(err) = result {
+            throw_java_exception(&mut env, &err);
+        }
 
 
 }
 
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawSubTexture<'local>(mut env: JNIEnv<'local>, _class: JClass, 
+        kernel_ptr: jlong,
+        sub_texture_ptr: jlong,
+        uv_min_x: jfloat, uv_min_y: jfloat, uv_max_x: jfloat, uv_max_y: jfloat,
+        pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
+        tint_color: jint,
+        sampler_ptr: jlong) {
+        let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+        let Some(sub_texture) = AtlasSubTexture::from_handle(sub_texture_ptr) else {panic!("invalid sub texture")};
+       
+        let mut ui = kernel.user_interface.borrow_mut();
+        let result = set_draw_sampler(&mut ui, sampler_ptr).and_then(|_| ui.cmd_draw_sub_texture(
+            &sub_texture,
+            &Rect {
+                min: [uv_min_x, uv_min_y],
+                max: [uv_max_x, uv_max_y]
+            },
+            &Rect {
+                min: [pos_min_x, pos_min_y],
+                max: [pos_max_x, pos_max_y]
+            },
+            tint_color as u32
+        ));
+        if let Err(err) = result {
+            throw_java_exception(&mut env, &err);
+        }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawTextureTiled<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        tex_ptr: jlong,
+        layer: jint,
+        pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
+        tile_width: jfloat, tile_height: jfloat,
+        offset_x: jfloat, offset_y: jfloat,
+        tint_color: jint,
+        sampler_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(texture_resource) = TextureResource::from_handle(tex_ptr) else {panic!("invalid tex resource")};
+
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, sampler_ptr).and_then(|_| ui.cmd_draw_texture_tiled(
+        &texture_resource,
+        layer as u32,
+        &Rect {
+            min: [pos_min_x, pos_min_y],
+            max: [pos_max_x, pos_max_y]
+        },
+        [tile_width, tile_height],
+        [offset_x, offset_y],
+        tint_color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawNineSlice<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        tex_ptr: jlong,
+        layer: jint,
+        uv_min_x: jfloat, uv_min_y: jfloat, uv_max_x: jfloat, uv_max_y: jfloat,
+        pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
+        inset_left: jfloat, inset_top: jfloat, inset_right: jfloat, inset_bottom: jfloat,
+        scale: jfloat,
+        tile: jboolean,
+        tint_color: jint,
+        sampler_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(texture_resource) = TextureResource::from_handle(tex_ptr) else {panic!("invalid tex resource")};
+
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, sampler_ptr).and_then(|_| ui.cmd_draw_nine_slice(
+        &texture_resource,
+        layer as u32,
+        &Rect {
+            min: [uv_min_x, uv_min_y],
+            max: [uv_max_x, uv_max_y]
+        },
+        &Rect {
+            min: [pos_min_x, pos_min_y],
+            max: [pos_max_x, pos_max_y]
+        },
+        &NineSlice {
+            insets: [inset_left, inset_top, inset_right, inset_bottom],
+            scale,
+            tile: tile != JNI_FALSE
+        },
+        tint_color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawSubNineSlice<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        sub_texture_ptr: jlong,
+        uv_min_x: jfloat, uv_min_y: jfloat, uv_max_x: jfloat, uv_max_y: jfloat,
+        pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
+        inset_left: jfloat, inset_top: jfloat, inset_right: jfloat, inset_bottom: jfloat,
+        scale: jfloat,
+        tile: jboolean,
+        tint_color: jint,
+        sampler_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(sub_texture) = AtlasSubTexture::from_handle(sub_texture_ptr) else {panic!("invalid sub texture")};
+
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, sampler_ptr).and_then(|_| ui.cmd_draw_sub_nine_slice(
+        &sub_texture,
+        &Rect {
+            min: [uv_min_x, uv_min_y],
+            max: [uv_max_x, uv_max_y]
+        },
+        &Rect {
+            min: [pos_min_x, pos_min_y],
+            max: [pos_max_x, pos_max_y]
+        },
+        &NineSlice {
+            insets: [inset_left, inset_top, inset_right, inset_bottom],
+            scale,
+            tile: tile != JNI_FALSE
+        },
+        tint_color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawText<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        font_ptr: jlong,
+        size: jfloat,
+        pos_x: jfloat, pos_y: jfloat,
+        color: jint,
+        text: JString<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(font) = FontResource::from_handle(font_ptr) else { panic!("invalid font") };
+    let text: String = env.get_string(&text).expect("invalid text").into();
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, 0).and_then(|_| ui.cmd_draw_text(
+        &surface.device,
+        &surface.queue,
+        &font,
+        size,
+        [pos_x, pos_y],
+        color as u32,
+        &text
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawTextLayout<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        font_ptr: jlong,
+        desc: JObject<'local>,
+        pos_x: jfloat, pos_y: jfloat,
+        color: jint,
+        text: JString<'local>) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(font) = FontResource::from_handle(font_ptr) else { panic!("invalid font") };
+    let text: String = env.get_string(&text).expect("invalid text").into();
+    let layout = match text_layout_desc_from_java(&mut env, &desc).and_then(|desc| layout_text(&font, &desc, &text)) {
+        Ok(layout) => layout,
+        Err(err) => {
+            throw_java_exception(&mut env, &err);
+            return;
+        }
+    };
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, 0).and_then(|_| ui.cmd_draw_text_layout(
+        &surface.device,
+        &surface.queue,
+        &layout,
+        [pos_x, pos_y],
+        color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawRect<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        pos_min_x: jfloat, pos_min_y: jfloat, pos_max_x: jfloat, pos_max_y: jfloat,
+        color: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, 0).and_then(|_| ui.cmd_draw_rect(
+        &surface.device,
+        &surface.queue,
+        &Rect {
+            min: [pos_min_x, pos_min_y],
+            max: [pos_max_x, pos_max_y]
+        },
+        color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawLine<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        from_x: jfloat, from_y: jfloat, to_x: jfloat, to_y: jfloat,
+        thickness: jfloat,
+        color: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, 0).and_then(|_| ui.cmd_draw_line(
+        &surface.device,
+        &surface.queue,
+        [from_x, from_y],
+        [to_x, to_y],
+        thickness,
+        color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIDrawPolyline<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        points: JFloatArray<'local>,
+        thickness: jfloat,
+        closed: jboolean,
+        color: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let length = env.get_array_length(&points).expect("invalid point array") as usize;
+    if !length.is_multiple_of(2) {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("points need an x and y each, got {} values", length)));
+        return;
+    }
+    let mut coordinates = vec![0.0; length];
+    env.get_float_array_region(&points, 0, &mut coordinates).expect("invalid point array");
+    let points: Vec<[f32; 2]> = coordinates.chunks_exact(2).map(|point| [point[0], point[1]]).collect();
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    let result = set_draw_sampler(&mut ui, 0).and_then(|_| ui.cmd_draw_polyline(
+        &surface.device,
+        &surface.queue,
+        &points,
+        thickness,
+        closed != JNI_FALSE,
+        color as u32
+    ));
+    if let Err(err) = result {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIBeginTarget<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        tex_ptr: jlong,
+        clear: jboolean,
+        clear_color: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let Some(texture_resource) = TextureResource::from_handle(tex_ptr) else {panic!("invalid tex resource")};
+    let clear = (clear != JNI_FALSE).then(|| clear_color_from_java(clear_color));
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = ui.cmd_begin_target(&surface.device, &texture_resource, clear) {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUIEndTarget<'local>(mut env: JNIEnv<'local>, _class: JClass, kernel_ptr: jlong) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = ui.cmd_end_target() {
+        throw_java_exception(&mut env, &err);
+    }
+}
@@ -0,0 +1,512 @@
+use jni::{sys::{jlong, jint}, objects::{JByteBuffer, JClass, JLongArray}, JNIEnv};
+use super::{jni_font::text_layout_desc, jni_ui::{blend_mode_from_java, clear_color_from_java, set_draw_sampler}};
+use crate::{engine_kernel::EngineKernel, ui::{JavaHandle, NineSlice, UICommandList, UserInterface}, math::rect::Rect, resource::{texture_resource::TextureResource, texture_atlas::AtlasSubTexture, font_resource::FontResource, text_layout::layout_text}, java_util::{throw_java_exception, JNIError, JNIResult}};
+
+// a stream starts with the magic "TUIC" and the format version, followed by commands that are each an opcode and
+// its arguments. everything is little endian. objects are referenced by an index into the handle table submitted
+// with the stream, -1 stands for no sampler.
+// the layout matches UICommandStream.java, bump the version on both sides whenever it changes
+const STREAM_MAGIC: u32 = 0x43495554;
+const STREAM_VERSION: u32 = 2;
+
+const OP_SET_CROP: u32 = 1;
+const OP_CLEAR_CROP: u32 = 2;
+const OP_PUSH_CROP: u32 = 3;
+const OP_POP_CROP: u32 = 4;
+const OP_PUSH_TRANSFORM: u32 = 5;
+const OP_POP_TRANSFORM: u32 = 6;
+const OP_TRANSLATE: u32 = 7;
+const OP_ROTATE: u32 = 8;
+const OP_SCALE: u32 = 9;
+const OP_SET_BLEND_MODE: u32 = 10;
+const OP_DRAW_TEXTURE: u32 = 11;
+const OP_DRAW_SUB_TEXTURE: u32 = 12;
+const OP_DRAW_TEXTURE_TILED: u32 = 13;
+const OP_DRAW_NINE_SLICE: u32 = 14;
+const OP_DRAW_SUB_NINE_SLICE: u32 = 15;
+const OP_DRAW_RECT: u32 = 16;
+const OP_DRAW_LINE: u32 = 17;
+const OP_DRAW_POLYLINE: u32 = 18;
+const OP_DRAW_TEXT: u32 = 19;
+const OP_DRAW_TEXT_LAYOUT: u32 = 20;
+const OP_DRAW_LIST: u32 = 21;
+const OP_BEGIN_TARGET: u32 = 22;
+const OP_END_TARGET: u32 = 23;
+
+// a command as it was read from the stream, handles are checked against the table but not resolved yet
+enum UICommand<'a> {
+    SetCrop(Option<Rect>),
+    PushCrop(Rect),
+    PopCrop,
+    PushTransform,
+    PopTransform,
+    Translate([f32; 2]),
+    Rotate(f32),
+    Scale([f32; 2]),
+    SetBlendMode(i32),
+    DrawTexture { texture: jlong, layer: u32, uv: Rect, pos: Rect, tint_color: u32, sampler: jlong },
+    DrawSubTexture { sub_texture: jlong, uv: Rect, pos: Rect, tint_color: u32, sampler: jlong },
+    DrawTextureTiled { texture: jlong, layer: u32, pos: Rect, tile_size: [f32; 2], tile_offset: [f32; 2], tint_color: u32, sampler: jlong },
+    DrawNineSlice { texture: jlong, layer: u32, uv: Rect, pos: Rect, slice: NineSlice, tint_color: u32, sampler: jlong },
+    DrawSubNineSlice { sub_texture: jlong, uv: Rect, pos: Rect, slice: NineSlice, tint_color: u32, sampler: jlong },
+    DrawRect { pos: Rect, color: u32 },
+    DrawLine { from: [f32; 2], to: [f32; 2], thickness: f32, color: u32 },
+    DrawPolyline { points: Vec<[f32; 2]>, thickness: f32, closed: bool, color: u32 },
+    DrawText { font: jlong, size: f32, pos: [f32; 2], color: u32, text: &'a str },
+    DrawTextLayout { font: jlong, size: f32, max_width: f32, max_lines: i32, wrap: bool, ellipsis: bool, align: i32, pos: [f32; 2], color: u32, text: &'a str },
+    DrawList { list: jlong, offset: [f32; 2], clip: Option<Rect> },
+    BeginTarget { texture: jlong, clear: Option<i32> },
+    EndTarget,
+}
+
+struct StreamReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    handles: &'a [jlong],
+}
+
+impl<'a> StreamReader<'a> {
+    // checks the header, the reader starts at the first command
+    fn new(data: &'a [u8], handles: &'a [jlong]) -> JNIResult<Self> {
+        let mut reader = StreamReader { data, offset: 0, handles };
+        if reader.u32()? != STREAM_MAGIC {
+            return Err(JNIError::IllegalArgument("not a ui command stream".to_string()));
+        }
+        let version = reader.u32()?;
+        if version != STREAM_VERSION {
+            return Err(JNIError::IllegalArgument(format!("unsupported ui command stream version {}, expected {}", version, STREAM_VERSION)));
+        }
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> JNIResult<&'a [u8]> {
+        if self.data.len() - self.offset < len {
+            return Err(JNIError::IllegalArgument(format!("ui command stream truncated at byte {}, {} more bytes needed", self.offset, len)));
+        }
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn read<const N: usize>(&mut self) -> JNIResult<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> JNIResult<u32> {
+        Ok(u32::from_le_bytes(self.read()?))
+    }
+
+    fn i32(&mut self) -> JNIResult<i32> {
+        Ok(i32::from_le_bytes(self.read()?))
+    }
+
+    fn f32(&mut self) -> JNIResult<f32> {
+        Ok(f32::from_le_bytes(self.read()?))
+    }
+
+    fn bool(&mut self) -> JNIResult<bool> {
+        let offset = self.offset;
+        match self.u32()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(JNIError::IllegalArgument(format!("invalid boolean {} in ui command stream at byte {}", value, offset)))
+        }
+    }
+
+    fn vec2(&mut self) -> JNIResult<[f32; 2]> {
+        Ok([self.f32()?, self.f32()?])
+    }
+
+    fn rect(&mut self) -> JNIResult<Rect> {
+        Ok(Rect {
+            min: self.vec2()?,
+            max: self.vec2()?
+        })
+    }
+
+    fn nine_slice(&mut self) -> JNIResult<NineSlice> {
+        Ok(NineSlice {
+            insets: [self.f32()?, self.f32()?, self.f32()?, self.f32()?],
+            scale: self.f32()?,
+            tile: self.bool()?
+        })
+    }
+
+    // a byte length followed by utf-8
+    fn string(&mut self) -> JNIResult<&'a str> {
+        let offset = self.offset;
+        let len = self.u32()? as usize;
+        std::str::from_utf8(self.take(len)?)
+            .map_err(|_| JNIError::IllegalArgument(format!("invalid utf-8 text in ui command stream at byte {}", offset)))
+    }
+
+    // a count of points followed by their coordinates
+    fn points(&mut self) -> JNIResult<Vec<[f32; 2]>> {
+        let offset = self.offset;
+        let count = self.u32()? as usize;
+        let len = count.checked_mul(8)
+            .ok_or_else(|| JNIError::IllegalArgument(format!("invalid point count {} in ui command stream at byte {}", count, offset)))?;
+        // checked against the remaining bytes before allocating
+        let coordinates = self.take(len)?;
+        Ok(coordinates.chunks_exact(8).map(|point| [
+            f32::from_le_bytes(point[0..4].try_into().unwrap()),
+            f32::from_le_bytes(point[4..8].try_into().unwrap())
+        ]).collect())
+    }
+
+    // an index into the handle table
+    fn handle(&mut self, name: &str) -> JNIResult<jlong> {
+        let offset = self.offset;
+        let index = self.i32()?;
+        self.lookup(index, name, offset)
+    }
+
+    // a sampler handle, 0 if the index is -1
+    fn sampler(&mut self) -> JNIResult<jlong> {
+        let offset = self.offset;
+        match self.i32()? {
+            -1 => Ok(0),
+            index => self.lookup(index, "sampler", offset)
+        }
+    }
+
+    fn lookup(&self, index: i32, name: &str, offset: usize) -> JNIResult<jlong> {
+        match usize::try_from(index).ok().and_then(|index| self.handles.get(index)) {
+            Some(0) => Err(JNIError::IllegalArgument(format!("null {} in ui command stream at byte {}", name, offset))),
+            Some(handle) => Ok(*handle),
+            None => Err(JNIError::IllegalArgument(format!("invalid {} index {} in ui command stream at byte {}, {} handles were submitted",
+                name, index, offset, self.handles.len())))
+        }
+    }
+
+    // the next command, None at the end of the stream
+    fn command(&mut self) -> JNIResult<Option<UICommand<'a>>> {
+        if self.offset == self.data.len() {
+            return Ok(None);
+        }
+        let offset = self.offset;
+        let command = match self.u32()? {
+            OP_SET_CROP => UICommand::SetCrop(Some(self.rect()?)),
+            OP_CLEAR_CROP => UICommand::SetCrop(None),
+            OP_PUSH_CROP => UICommand::PushCrop(self.rect()?),
+            OP_POP_CROP => UICommand::PopCrop,
+            OP_PUSH_TRANSFORM => UICommand::PushTransform,
+            OP_POP_TRANSFORM => UICommand::PopTransform,
+            OP_TRANSLATE => UICommand::Translate(self.vec2()?),
+            OP_ROTATE => UICommand::Rotate(self.f32()?),
+            OP_SCALE => UICommand::Scale(self.vec2()?),
+            OP_SET_BLEND_MODE => UICommand::SetBlendMode(self.i32()?),
+            OP_DRAW_TEXTURE => UICommand::DrawTexture {
+                texture: self.handle("texture")?,
+                layer: self.u32()?,
+                uv: self.rect()?,
+                pos: self.rect()?,
+                tint_color: self.u32()?,
+                sampler: self.sampler()?
+            },
+            OP_DRAW_SUB_TEXTURE => UICommand::DrawSubTexture {
+                sub_texture: self.handle("sub texture")?,
+                uv: self.rect()?,
+                pos: self.rect()?,
+                tint_color: self.u32()?,
+                sampler: self.sampler()?
+            },
+            OP_DRAW_TEXTURE_TILED => UICommand::DrawTextureTiled {
+                texture: self.handle("texture")?,
+                layer: self.u32()?,
+                pos: self.rect()?,
+                tile_size: self.vec2()?,
+                tile_offset: self.vec2()?,
+                tint_color: self.u32()?,
+                sampler: self.sampler()?
+            },
+            OP_DRAW_NINE_SLICE => UICommand::DrawNineSlice {
+                texture: self.handle("texture")?,
+                layer: self.u32()?,
+                uv: self.rect()?,
+                pos: self.rect()?,
+                slice: self.nine_slice()?,
+                tint_color: self.u32()?,
+                sampler: self.sampler()?
+            },
+            OP_DRAW_SUB_NINE_SLICE => UICommand::DrawSubNineSlice {
+                sub_texture: self.handle("sub texture")?,
+                uv: self.rect()?,
+                pos: self.rect()?,
+                slice: self.nine_slice()?,
+                tint_color: self.u32()?,
+                sampler: self.sampler()?
+            },
+            OP_DRAW_RECT => UICommand::DrawRect {
+                pos: self.rect()?,
+                color: self.u32()?
+            },
+            OP_DRAW_LINE => UICommand::DrawLine {
+                from: self.vec2()?,
+                to: self.vec2()?,
+                thickness: self.f32()?,
+                color: self.u32()?
+            },
+            OP_DRAW_POLYLINE => UICommand::DrawPolyline {
+                thickness: self.f32()?,
+                closed: self.bool()?,
+                color: self.u32()?,
+                points: self.points()?
+            },
+            OP_DRAW_TEXT => UICommand::DrawText {
+                font: self.handle("font")?,
+                size: self.f32()?,
+                pos: self.vec2()?,
+                color: self.u32()?,
+                text: self.string()?
+            },
+            OP_DRAW_TEXT_LAYOUT => UICommand::DrawTextLayout {
+                font: self.handle("font")?,
+                size: self.f32()?,
+                max_width: self.f32()?,
+                max_lines: self.i32()?,
+                wrap: self.bool()?,
+                ellipsis: self.bool()?,
+                align: self.i32()?,
+                pos: self.vec2()?,
+                color: self.u32()?,
+                text: self.string()?
+            },
+            OP_DRAW_LIST => {
+                let list = self.handle("command list")?;
+                let list_offset = self.vec2()?;
+                let clip = self.bool()?;
+                let clip_rect = self.rect()?;
+                UICommand::DrawList { list, offset: list_offset, clip: clip.then_some(clip_rect) }
+            }
+            OP_BEGIN_TARGET => {
+                let texture = self.handle("texture")?;
+                let clear = self.bool()?;
+                let clear_color = self.i32()?;
+                UICommand::BeginTarget { texture, clear: clear.then_some(clear_color) }
+            }
+            OP_END_TARGET => UICommand::EndTarget,
+            op => return Err(JNIError::IllegalArgument(format!("unknown ui command {} at byte {}", op, offset)))
+        };
+        Ok(Some(command))
+    }
+}
+
+fn resolve<T: JavaHandle<H>, H>(handle: jlong, name: &str) -> JNIResult<H> {
+    T::from_handle(handle).ok_or_else(|| JNIError::IllegalArgument(format!("invalid {} in ui command stream", name)))
+}
+
+fn execute_command(ui: &mut UserInterface, device: &wgpu::Device, queue: &wgpu::Queue, command: UICommand) -> JNIResult<()> {
+    match command {
+        UICommand::SetCrop(crop) => ui.cmd_set_crop(crop),
+        UICommand::PushCrop(crop) => ui.cmd_push_crop(crop),
+        UICommand::PopCrop => ui.cmd_pop_crop()?,
+        UICommand::PushTransform => ui.cmd_push_transform(),
+        UICommand::PopTransform => ui.cmd_pop_transform()?,
+        UICommand::Translate(offset) => ui.cmd_translate(offset),
+        UICommand::Rotate(radians) => ui.cmd_rotate(radians),
+        UICommand::Scale(scale) => ui.cmd_scale(scale),
+        UICommand::SetBlendMode(mode) => ui.cmd_set_blend_mode(blend_mode_from_java(mode)?),
+        UICommand::DrawTexture { texture, layer, uv, pos, tint_color, sampler } => {
+            let texture = resolve::<TextureResource, _>(texture, "texture")?;
+            set_draw_sampler(ui, sampler)?;
+            ui.cmd_draw_texture(&texture, layer, &uv, &pos, tint_color)?;
+        }
+        UICommand::DrawSubTexture { sub_texture, uv, pos, tint_color, sampler } => {
+            let sub_texture = resolve::<AtlasSubTexture, _>(sub_texture, "sub texture")?;
+            set_draw_sampler(ui, sampler)?;
+            ui.cmd_draw_sub_texture(&sub_texture, &uv, &pos, tint_color)?;
+        }
+        UICommand::DrawTextureTiled { texture, layer, pos, tile_size, tile_offset, tint_color, sampler } => {
+            let texture = resolve::<TextureResource, _>(texture, "texture")?;
+            set_draw_sampler(ui, sampler)?;
+            ui.cmd_draw_texture_tiled(&texture, layer, &pos, tile_size, tile_offset, tint_color)?;
+        }
+        UICommand::DrawNineSlice { texture, layer, uv, pos, slice, tint_color, sampler } => {
+            let texture = resolve::<TextureResource, _>(texture, "texture")?;
+            set_draw_sampler(ui, sampler)?;
+            ui.cmd_draw_nine_slice(&texture, layer, &uv, &pos, &slice, tint_color)?;
+        }
+        UICommand::DrawSubNineSlice { sub_texture, uv, pos, slice, tint_color, sampler } => {
+            let sub_texture = resolve::<AtlasSubTexture, _>(sub_texture, "sub texture")?;
+            set_draw_sampler(ui, sampler)?;
+            ui.cmd_draw_sub_nine_slice(&sub_texture, &uv, &pos, &slice, tint_color)?;
+        }
+        UICommand::DrawRect { pos, color } => {
+            set_draw_sampler(ui, 0)?;
+            ui.cmd_draw_rect(device, queue, &pos, color)?;
+        }
+        UICommand::DrawLine { from, to, thickness, color } => {
+            set_draw_sampler(ui, 0)?;
+            ui.cmd_draw_line(device, queue, from, to, thickness, color)?;
+        }
+        UICommand::DrawPolyline { points, thickness, closed, color } => {
+            set_draw_sampler(ui, 0)?;
+            ui.cmd_draw_polyline(device, queue, &points, thickness, closed, color)?;
+        }
+        UICommand::DrawText { font, size, pos, color, text } => {
+            let font = resolve::<FontResource, _>(font, "font")?;
+            set_draw_sampler(ui, 0)?;
+            ui.cmd_draw_text(device, queue, &font, size, pos, color, text)?;
+        }
+        UICommand::DrawTextLayout { font, size, max_width, max_lines, wrap, ellipsis, align, pos, color, text } => {
+            let font = resolve::<FontResource, _>(font, "font")?;
+            let desc = text_layout_desc(size, max_width, max_lines, wrap, ellipsis, align)?;
+            let layout = layout_text(&font, &desc, text)?;
+            set_draw_sampler(ui, 0)?;
+            ui.cmd_draw_text_layout(device, queue, &layout, pos, color)?;
+        }
+        UICommand::DrawList { list, offset, clip } => {
+            let list = resolve::<UICommandList, _>(list, "command list")?;
+            ui.cmd_draw_list(&list, offset, clip)?;
+        }
+        UICommand::BeginTarget { texture, clear } => {
+            let texture = resolve::<TextureResource, _>(texture, "texture")?;
+            ui.cmd_begin_target(device, &texture, clear.map(clear_color_from_java))?;
+        }
+        UICommand::EndTarget => ui.cmd_end_target()?,
+    }
+    Ok(())
+}
+
+// runs the commands in order, the ones before a failing command stay recorded
+fn execute_stream(ui: &mut UserInterface, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8], handles: &[jlong]) -> JNIResult<()> {
+    let mut reader = StreamReader::new(data, handles)?;
+    while let Some(command) = reader.command()? {
+        execute_command(ui, device, queue, command)?;
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub extern "system" fn Java_org_terasology_engine_rust_UIRenderer_00024JNI_cmdUISubmitStream<'local>(mut env: JNIEnv<'local>, _class: JClass,
+        kernel_ptr: jlong,
+        buffer: JByteBuffer<'local>,
+        length: jint,
+        handles: JLongArray<'local>,
+        handle_count: jint) {
+    let Some(kernel) = EngineKernel::from_handle(kernel_ptr) else { panic!("kernel invalid") };
+    let (Ok(address), Ok(capacity)) = (env.get_direct_buffer_address(&buffer), env.get_direct_buffer_capacity(&buffer)) else {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument("ui command streams need a direct buffer".to_string()));
+        return;
+    };
+    if length < 0 || length as usize > capacity {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("invalid ui command stream length {} for a buffer of {} bytes", length, capacity)));
+        return;
+    }
+    let handle_capacity = env.get_array_length(&handles).expect("invalid handle array");
+    if handle_count < 0 || handle_count > handle_capacity {
+        throw_java_exception(&mut env, &JNIError::IllegalArgument(format!("invalid handle count {} for an array of {} handles", handle_count, handle_capacity)));
+        return;
+    }
+    let mut handle_table = vec![0; handle_count as usize];
+    env.get_long_array_region(&handles, 0, &mut handle_table).expect("invalid handle array");
+    // the buffer is only read while java is blocked in this call
+    let data = if length == 0 { &[][..] } else { unsafe { std::slice::from_raw_parts(address, length as usize) } };
+
+    let surface = kernel.window_surface.lock().expect("failed to resolve surface");
+    let mut ui = kernel.user_interface.borrow_mut();
+    if let Err(err) = execute_stream(&mut ui, &surface.device, &surface.queue, data, &handle_table) {
+        throw_java_exception(&mut env, &err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a stream of the current version followed by `words`
+    fn stream(words: &[u32]) -> Vec<u8> {
+        [STREAM_MAGIC, STREAM_VERSION].iter().chain(words).flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn commands<'a>(data: &'a [u8], handles: &'a [jlong]) -> JNIResult<Vec<UICommand<'a>>> {
+        let mut reader = StreamReader::new(data, handles)?;
+        let mut commands = Vec::new();
+        while let Some(command) = reader.command()? {
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+
+    fn draw_texture(texture: i32, sampler: i32) -> Vec<u32> {
+        let mut words = vec![OP_DRAW_TEXTURE, texture as u32, 0];
+        words.extend([0.0f32, 0.0, 1.0, 1.0, 0.0, 0.0, 8.0, 8.0].map(f32::to_bits));
+        words.extend([0xffffffff, sampler as u32]);
+        words
+    }
+
+    #[test]
+    fn reads_commands() {
+        let mut words = vec![OP_PUSH_TRANSFORM, OP_TRANSLATE, 1.5f32.to_bits(), 2.0f32.to_bits(), OP_POP_TRANSFORM];
+        words.extend(draw_texture(1, -1));
+        words.extend(draw_texture(0, 1));
+        let data = stream(&words);
+        let commands = commands(&data, &[10, 20]).unwrap();
+        assert_eq!(commands.len(), 5);
+        assert!(matches!(commands[1], UICommand::Translate([1.5, 2.0])));
+        assert!(matches!(commands[3], UICommand::DrawTexture { texture: 20, sampler: 0, .. }));
+        assert!(matches!(commands[4], UICommand::DrawTexture { texture: 10, sampler: 20, .. }));
+    }
+
+    #[test]
+    fn empty_stream() {
+        assert!(commands(&stream(&[]), &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_headers() {
+        assert!(StreamReader::new(&[], &[]).is_err());
+        assert!(StreamReader::new(&STREAM_MAGIC.to_le_bytes(), &[]).is_err());
+        let bad_magic: Vec<u8> = [0x12345678, STREAM_VERSION].iter().flat_map(|word: &u32| word.to_le_bytes()).collect();
+        assert!(StreamReader::new(&bad_magic, &[]).is_err());
+        let old_version: Vec<u8> = [STREAM_MAGIC, 1].iter().flat_map(|word: &u32| word.to_le_bytes()).collect();
+        assert!(StreamReader::new(&old_version, &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_opcodes() {
+        assert!(commands(&stream(&[OP_POP_CROP, 0]), &[]).is_err());
+        assert!(commands(&stream(&[OP_END_TARGET + 1]), &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_streams() {
+        let data = stream(&draw_texture(0, -1));
+        for len in 9..data.len() {
+            assert!(commands(&data[..len], &[10]).is_err(), "{} bytes", len);
+        }
+        // a stream may only end between commands
+        let mut data = stream(&[OP_POP_CROP]);
+        data.push(0);
+        assert!(commands(&data, &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_handles_outside_the_table() {
+        assert!(commands(&stream(&draw_texture(1, -1)), &[10]).is_err());
+        assert!(commands(&stream(&draw_texture(-1, -1)), &[10]).is_err());
+        assert!(commands(&stream(&draw_texture(0, 1)), &[10]).is_err());
+        assert!(commands(&stream(&draw_texture(0, -2)), &[10]).is_err());
+        assert!(commands(&stream(&draw_texture(0, -1)), &[0]).is_err());
+    }
+
+    #[test]
+    fn rejects_point_counts_past_the_end() {
+        let mut words = vec![OP_DRAW_POLYLINE, 1.0f32.to_bits(), 0, 0xffffffff, 2];
+        words.extend([0.0f32, 0.0, 4.0, 4.0].map(f32::to_bits));
+        let data = stream(&words);
+        assert!(matches!(&commands(&data, &[]).unwrap()[0], UICommand::DrawPolyline { points, .. } if points.len() == 2));
+        words[4] = u32::MAX;
+        assert!(commands(&stream(&words), &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_text() {
+        let mut words = vec![OP_DRAW_TEXT, 0, 12.0f32.to_bits(), 0, 0, 0xffffffff, 4];
+        words.push(u32::from_le_bytes([0xff, 0xfe, 0xfd, 0xfc]));
+        assert!(commands(&stream(&words), &[10]).is_err());
+        words[7] = u32::from_le_bytes(*b"text");
+        assert!(matches!(commands(&stream(&words), &[10]).unwrap()[0], UICommand::DrawText { font: 10, text: "text", .. }));
+    }
+}
@@ -1,5 +1,10 @@
 
 pub mod jni_engine_kernel;
 pub mod jni_ui;
+pub mod jni_ui_stream;
 pub mod jni_resource;
 pub mod jni_texture;
+pub mod jni_atlas;
+pub mod jni_sampler;
+pub mod jni_font;
+pub mod jni_memory;
@@ -2,6 +2,7 @@ mod engine_kernel;
 mod java_util;
 mod window_surface;
 mod ui;
+mod skybox;
 mod resource;
 mod jni;
 mod math;
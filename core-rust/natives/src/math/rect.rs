@@ -1,5 +1,5 @@
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Rect {
     pub min: [f32; 2], 
     pub max: [f32; 2],
@@ -24,7 +24,23 @@ impl Rect {
         return self.min[0] < rect.max[0] && self.max[0] > rect.min[0] &&
                 self.max[1] > rect.min[1] && self.min[1] < rect.max[1];
     }
-    
+
+    // the overlap of both rects, None if they don't overlap
+    pub fn intersection(&self, rect: &Rect) -> Option<Rect> {
+        let result = Rect {
+            min: [self.min[0].max(rect.min[0]), self.min[1].max(rect.min[1])],
+            max: [self.max[0].min(rect.max[0]), self.max[1].min(rect.max[1])]
+        };
+        if result.is_empty() {
+            return None;
+        }
+        Some(result)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.max[0] > self.min[0] && self.max[1] > self.min[1])
+    }
+
     pub fn combine(&self, other: &Rect) -> Rect {
         let mut result = Rect::zero();
         result.min[0] = if self.min[0] < other.min[0] { self.min[0] } else { other.min[0]};
@@ -35,3 +51,63 @@ impl Rect {
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Rect {
+        Rect { min: [min_x, min_y], max: [max_x, max_y] }
+    }
+
+    #[test]
+    fn intersection_of_overlapping_rects() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        let b = rect(5.0, -5.0, 15.0, 5.0);
+        assert_eq!(a.intersection(&b), Some(rect(5.0, 0.0, 10.0, 5.0)));
+        assert_eq!(b.intersection(&a), Some(rect(5.0, 0.0, 10.0, 5.0)));
+    }
+
+    #[test]
+    fn intersection_of_contained_rects() {
+        let outer = rect(0.0, 0.0, 10.0, 10.0);
+        let inner = rect(2.0, 3.0, 4.0, 5.0);
+        assert_eq!(outer.intersection(&inner), Some(inner));
+        assert_eq!(inner.intersection(&outer), Some(inner));
+        assert_eq!(outer.intersection(&outer), Some(outer));
+    }
+
+    #[test]
+    fn intersection_of_disjoint_rects() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection(&rect(20.0, 0.0, 30.0, 10.0)), None);
+        assert_eq!(a.intersection(&rect(0.0, 20.0, 10.0, 30.0)), None);
+        assert_eq!(a.intersection(&rect(-10.0, -10.0, -5.0, -5.0)), None);
+    }
+
+    #[test]
+    fn intersection_of_touching_rects() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        // sharing an edge or a corner leaves no area
+        assert_eq!(a.intersection(&rect(10.0, 0.0, 20.0, 10.0)), None);
+        assert_eq!(a.intersection(&rect(0.0, 10.0, 10.0, 20.0)), None);
+        assert_eq!(a.intersection(&rect(10.0, 10.0, 20.0, 20.0)), None);
+    }
+
+    #[test]
+    fn intersection_with_empty_rects() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection(&rect(5.0, 5.0, 5.0, 5.0)), None);
+        assert_eq!(a.intersection(&rect(8.0, 2.0, 2.0, 8.0)), None);
+    }
+
+    #[test]
+    fn is_empty() {
+        assert!(!rect(0.0, 0.0, 1.0, 1.0).is_empty());
+        assert!(Rect::zero().is_empty());
+        assert!(rect(0.0, 0.0, 0.0, 1.0).is_empty());
+        assert!(rect(0.0, 0.0, 1.0, 0.0).is_empty());
+        assert!(rect(1.0, 1.0, 0.0, 0.0).is_empty());
+        assert!(rect(0.0, 0.0, f32::NAN, 1.0).is_empty());
+    }
+}
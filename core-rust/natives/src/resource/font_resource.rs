@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use ab_glyph::{Font, FontVec, PxScale};
+
+use crate::java_util::{arc_dispose_handle, arc_from_handle, arc_to_handle, JavaHandle, JNIError, JNIResult};
+
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+// held across the cycle check and the write, two fonts made fallbacks of each other at the same time would
+// otherwise both pass the check
+static FALLBACK_LOCK: Mutex<()> = Mutex::new(());
+
+// a TTF or OTF font, glyphs are rasterized on demand by the ui glyph cache
+pub struct FontResource {
+    pub font: FontVec,
+    // identifies the font in the glyph cache, pointers could be reused once the font is dropped
+    pub id: u64,
+    // fonts searched in order for characters this font has no glyph for
+    fallbacks: RwLock<Vec<Arc<FontResource>>>,
+}
+
+impl FontResource {
+    pub fn from_bytes(data: Vec<u8>) -> JNIResult<Self> {
+        let font = FontVec::try_from_vec(data)
+            .map_err(|err| JNIError::IllegalArgument(format!("failed to load font: {}", err)))?;
+        if rustybuzz::Face::from_slice(font.as_slice(), 0).is_none() {
+            return Err(JNIError::IllegalArgument("failed to load font: the font can't be shaped".to_string()));
+        }
+        Ok(Self {
+            font,
+            id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            fallbacks: RwLock::new(Vec::new())
+        })
+    }
+
+    // the scale that makes one em `size` pixels large
+    pub fn px_scale(&self, size: f32) -> PxScale {
+        let units_per_em = self.font.units_per_em().unwrap_or(1.0);
+        PxScale::from(size * self.font.height_unscaled() / units_per_em)
+    }
+
+    pub fn shaper_face(&self) -> rustybuzz::Face<'_> {
+        rustybuzz::Face::from_slice(self.font.as_slice(), 0).expect("font was checked when it was loaded")
+    }
+
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.font.glyph_id(c).0 != 0
+    }
+
+    fn reaches(&self, font: &FontResource) -> bool {
+        self.id == font.id || self.fallbacks.read().unwrap().iter().any(|fallback| fallback.reaches(font))
+    }
+
+    // replaces the fallback fonts, a font can't fall back to itself
+    pub fn set_fallbacks(&self, fallbacks: Vec<Arc<FontResource>>) -> JNIResult<()> {
+        let _guard = FALLBACK_LOCK.lock().unwrap();
+        if fallbacks.iter().any(|fallback| fallback.reaches(self)) {
+            return Err(JNIError::IllegalArgument("fallback fonts can't form a cycle".to_string()));
+        }
+        *self.fallbacks.write().unwrap() = fallbacks;
+        Ok(())
+    }
+
+    // this font followed by its fallbacks and their fallbacks, depth first without duplicates
+    pub fn chain(self: &Arc<Self>) -> Vec<Arc<FontResource>> {
+        fn visit(font: &Arc<FontResource>, chain: &mut Vec<Arc<FontResource>>) {
+            if chain.iter().any(|other| other.id == font.id) {
+                return;
+            }
+            chain.push(font.clone());
+            for fallback in font.fallbacks.read().unwrap().iter() {
+                visit(fallback, chain);
+            }
+        }
+        let mut chain = Vec::new();
+        visit(self, &mut chain);
+        chain
+    }
+}
+
+impl JavaHandle<Arc<FontResource>> for FontResource {
+    fn from_handle(ptr: jni::sys::jlong) -> Option<Arc<FontResource>> {
+        arc_from_handle(ptr)
+    }
+
+    fn to_handle(from: Arc<FontResource>) -> jni::sys::jlong {
+        arc_to_handle(from)
+    }
+
+    fn drop_handle(ptr: jni::sys::jlong) {
+        arc_dispose_handle::<FontResource>(ptr);
+    }
+}
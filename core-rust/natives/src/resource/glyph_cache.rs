@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use ab_glyph::{point, Font, GlyphId};
+
+use crate::java_util::JNIResult;
+use crate::math::rect::Rect;
+use crate::resource::font_resource::FontResource;
+use crate::resource::gpu_memory::GpuMemoryTracker;
+use crate::resource::texture_atlas::{AtlasSubTexture, TextureAtlas, TextureAtlasDesc};
+use crate::resource::texture_resource::TextureResource;
+
+const GLYPH_PAGE_SIZE: u32 = 512;
+const GLYPH_MAX_PAGE_SIZE: u32 = 2048;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: u64,
+    glyph: u16,
+    // bits of the pixel size
+    size: u32,
+}
+
+struct CachedGlyph {
+    // None for glyphs without an outline, like spaces
+    image: Option<AtlasSubTexture>,
+    // top left corner of the image relative to the pen position on the baseline
+    offset: [f32; 2],
+}
+
+// where to draw a glyph, `rect` is relative to the pen position on the baseline
+pub struct GlyphQuad {
+    pub texture: Arc<TextureResource>,
+    pub uv: Rect,
+    pub rect: Rect,
+}
+
+// rasterizes glyphs on demand into a shared atlas. glyphs are stored as white texels with the
+// coverage in alpha, so they can be tinted like any other ui texture.
+pub struct GlyphCache {
+    atlas: Arc<TextureAtlas>,
+    glyphs: HashMap<GlyphKey, CachedGlyph>,
+    fonts: HashMap<u64, Weak<FontResource>>,
+    // a few white texels for untextured shapes, so they batch with text
+    white: Option<AtlasSubTexture>,
+}
+
+impl GlyphCache {
+    pub fn new(memory: &Arc<GpuMemoryTracker>) -> Self {
+        let Ok(atlas) = TextureAtlas::new(TextureAtlasDesc {
+            page_size: GLYPH_PAGE_SIZE,
+            max_page_size: GLYPH_MAX_PAGE_SIZE,
+            // every glyph brings its own transparent border
+            padding: 0,
+            mip_levels: 1,
+            format: wgpu::TextureFormat::Rgba8Unorm
+        }, memory) else {
+            panic!("invalid glyph atlas description");
+        };
+        Self {
+            atlas: Arc::new(atlas),
+            glyphs: HashMap::new(),
+            fonts: HashMap::new(),
+            white: None
+        }
+    }
+
+    // the atlas page holding the white texels and the uv of their center
+    pub fn white_texel(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> JNIResult<(Arc<TextureResource>, [f32; 2])> {
+        if self.white.is_none() {
+            // 3x3 so filtering at the center never reaches a neighbour
+            self.white = Some(self.atlas.insert(device, queue, 3, 3, &[255u8; 3 * 3 * 4])?);
+        }
+        let (texture, uv) = self.white.as_ref().unwrap().resolve();
+        Ok((texture, [(uv.min[0] + uv.max[0]) * 0.5, (uv.min[1] + uv.max[1]) * 0.5]))
+    }
+
+    pub fn glyph(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, font: &Arc<FontResource>, glyph: GlyphId, size: f32) -> JNIResult<Option<GlyphQuad>> {
+        let key = GlyphKey {
+            font: font.id,
+            glyph: glyph.0,
+            size: size.to_bits()
+        };
+        if !self.glyphs.contains_key(&key) {
+            let cached = self.rasterize(device, queue, font, glyph, size)?;
+            self.fonts.entry(font.id).or_insert_with(|| Arc::downgrade(font));
+            self.glyphs.insert(key, cached);
+        }
+        let cached = &self.glyphs[&key];
+        Ok(cached.image.as_ref().map(|image| {
+            let (texture, uv) = image.resolve();
+            let [width, height] = image.size();
+            GlyphQuad {
+                texture,
+                uv,
+                rect: Rect {
+                    min: cached.offset,
+                    max: [cached.offset[0] + width as f32, cached.offset[1] + height as f32]
+                }
+            }
+        }))
+    }
+
+    fn rasterize(&self, device: &wgpu::Device, queue: &wgpu::Queue, font: &FontResource, glyph: GlyphId, size: f32) -> JNIResult<CachedGlyph> {
+        let glyph = glyph.with_scale_and_position(font.px_scale(size), point(0.0, 0.0));
+        let Some(outline) = font.font.outline_glyph(glyph) else {
+            return Ok(CachedGlyph { image: None, offset: [0.0, 0.0] });
+        };
+        let bounds = outline.px_bounds();
+        // one texel of transparent border on every side keeps filtering from bleeding into neighbours
+        let width = bounds.width() as u32 + 2;
+        let height = bounds.height() as u32 + 2;
+        let mut data = [255u8, 255, 255, 0].repeat((width * height) as usize);
+        outline.draw(|x, y, coverage| {
+            let offset = (((y + 1) * width + x + 1) * 4 + 3) as usize;
+            data[offset] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+        let image = self.atlas.insert(device, queue, width, height, &data)?;
+        Ok(CachedGlyph {
+            image: Some(image),
+            offset: [bounds.min.x - 1.0, bounds.min.y - 1.0]
+        })
+    }
+
+    // frees the glyphs of fonts that were dropped
+    pub fn collect_garbage(&mut self) {
+        let before = self.fonts.len();
+        self.fonts.retain(|_, font| font.strong_count() > 0);
+        if self.fonts.len() != before {
+            let fonts = &self.fonts;
+            self.glyphs.retain(|key, _| fonts.contains_key(&key.font));
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub const MEMORY_CATEGORY_COUNT: usize = 5;
+
+// codes match GpuMemory.Category
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum MemoryCategory {
+    Texture = 0,
+    RenderTarget = 1,
+    Atlas = 2,
+    Buffer = 3,
+    UIScratch = 4,
+}
+
+impl MemoryCategory {
+    pub const ALL: [MemoryCategory; MEMORY_CATEGORY_COUNT] = [
+        MemoryCategory::Texture,
+        MemoryCategory::RenderTarget,
+        MemoryCategory::Atlas,
+        MemoryCategory::Buffer,
+        MemoryCategory::UIScratch
+    ];
+
+    pub fn from_code(code: u32) -> Option<MemoryCategory> {
+        MemoryCategory::ALL.get(code as usize).copied()
+    }
+}
+
+#[derive(Clone)]
+pub struct MemoryEntry {
+    pub id: u64,
+    pub category: MemoryCategory,
+    pub size: u64,
+    pub label: String,
+}
+
+// a category that went over its soft budget since the events were last taken
+#[derive(Clone, Copy)]
+pub struct BudgetEvent {
+    pub category: MemoryCategory,
+    pub used: u64,
+    pub budget: u64,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    entries: HashMap<u64, MemoryEntry>,
+    totals: [u64; MEMORY_CATEGORY_COUNT],
+    budgets: [Option<u64>; MEMORY_CATEGORY_COUNT],
+    // set while a category is over budget so every overrun is only reported once
+    over_budget: [bool; MEMORY_CATEGORY_COUNT],
+    events: Vec<BudgetEvent>,
+}
+
+// estimated gpu memory of every texture and buffer the engine allocates. the sizes are what the
+// resources need for their data, drivers add padding and alignment on top of that.
+#[derive(Default)]
+pub struct GpuMemoryTracker {
+    next_id: AtomicU64,
+    state: Mutex<MemoryState>,
+}
+
+// an allocation that is accounted for until it is dropped
+pub struct TrackedMemory {
+    tracker: Arc<GpuMemoryTracker>,
+    id: u64,
+}
+
+impl Drop for TrackedMemory {
+    fn drop(&mut self) {
+        self.tracker.release(self.id);
+    }
+}
+
+impl GpuMemoryTracker {
+    pub fn track(self: &Arc<Self>, category: MemoryCategory, label: impl Into<String>, size: u64) -> TrackedMemory {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.state.lock().expect("failed to lock memory tracker");
+        state.entries.insert(id, MemoryEntry {
+            id,
+            category,
+            size,
+            label: label.into()
+        });
+        let index = category as usize;
+        state.totals[index] += size;
+        if let Some(budget) = state.budgets[index] {
+            if state.totals[index] > budget && !state.over_budget[index] {
+                log::warn!("{:?} memory is over budget: {} of {} bytes used", category, state.totals[index], budget);
+                state.over_budget[index] = true;
+                let event = BudgetEvent { category, used: state.totals[index], budget };
+                state.events.push(event);
+            }
+        }
+        TrackedMemory {
+            tracker: self.clone(),
+            id
+        }
+    }
+
+    pub fn track_texture(self: &Arc<Self>, category: MemoryCategory, label: impl Into<String>, texture: &wgpu::Texture) -> TrackedMemory {
+        self.track(category, label, texture_size_bytes(texture))
+    }
+
+    fn release(&self, id: u64) {
+        let mut state = self.state.lock().expect("failed to lock memory tracker");
+        if let Some(entry) = state.entries.remove(&id) {
+            let index = entry.category as usize;
+            state.totals[index] -= entry.size;
+            if state.budgets[index].is_none_or(|budget| state.totals[index] <= budget) {
+                state.over_budget[index] = false;
+            }
+        }
+    }
+
+    pub fn total(&self, category: MemoryCategory) -> u64 {
+        self.state.lock().expect("failed to lock memory tracker").totals[category as usize]
+    }
+
+    pub fn entries(&self) -> Vec<MemoryEntry> {
+        let state = self.state.lock().expect("failed to lock memory tracker");
+        let mut entries: Vec<MemoryEntry> = state.entries.values().cloned().collect();
+        entries.sort_by_key(|entry| entry.id);
+        entries
+    }
+
+    // None removes the budget. a category that is already over the new budget is reported right away.
+    pub fn set_budget(&self, category: MemoryCategory, budget: Option<u64>) {
+        let mut state = self.state.lock().expect("failed to lock memory tracker");
+        let index = category as usize;
+        state.budgets[index] = budget;
+        state.over_budget[index] = false;
+        if let Some(budget) = budget {
+            if state.totals[index] > budget {
+                state.over_budget[index] = true;
+                let event = BudgetEvent { category, used: state.totals[index], budget };
+                state.events.push(event);
+            }
+        }
+    }
+
+    // the overruns since the last call. allocations happen while the kernel is locked, so evicting is left to
+    // whoever polls the events once the frame is done.
+    pub fn take_budget_events(&self) -> Vec<BudgetEvent> {
+        std::mem::take(&mut self.state.lock().expect("failed to lock memory tracker").events)
+    }
+}
+
+// bytes taken up by every layer and mip level of a texture
+pub fn texture_size_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    // depth formats without a defined layout are counted as 4 bytes per texel
+    let block_bytes = format.block_size(None).unwrap_or(4) as u64;
+    let dimension = texture.dimension();
+    let size = texture.size();
+    let layers = match dimension {
+        wgpu::TextureDimension::D3 => 1,
+        _ => size.depth_or_array_layers as u64
+    };
+    let mut layer_bytes = 0;
+    for mip in 0..texture.mip_level_count() {
+        let extent = size.mip_level_size(mip, dimension);
+        let depth = match dimension {
+            wgpu::TextureDimension::D3 => extent.depth_or_array_layers as u64,
+            _ => 1
+        };
+        layer_bytes += extent.width.div_ceil(block_width) as u64 * extent.height.div_ceil(block_height) as u64 * depth * block_bytes;
+    }
+    layer_bytes * layers * texture.sample_count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_keep_their_labels() {
+        let tracker = Arc::new(GpuMemoryTracker::default());
+        let _first = tracker.track(MemoryCategory::Texture, format!("texture {}", 1), 16);
+        let _second = tracker.track(MemoryCategory::Buffer, "staging", 8);
+        let labels: Vec<String> = tracker.entries().into_iter().map(|entry| entry.label).collect();
+        assert_eq!(labels, ["texture 1", "staging"]);
+    }
+
+    #[test]
+    fn budget_events_once_per_overrun() {
+        let tracker = Arc::new(GpuMemoryTracker::default());
+        tracker.set_budget(MemoryCategory::Texture, Some(100));
+
+        let first = tracker.track(MemoryCategory::Texture, "first", 80);
+        assert!(tracker.take_budget_events().is_empty());
+        let second = tracker.track(MemoryCategory::Texture, "second", 40);
+        let _third = tracker.track(MemoryCategory::Texture, "third", 10);
+        let events = tracker.take_budget_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].category, MemoryCategory::Texture);
+        assert_eq!((events[0].used, events[0].budget), (120, 100));
+
+        // dropping back below the budget allows the next overrun to be reported
+        drop(second);
+        drop(first);
+        let _fourth = tracker.track(MemoryCategory::Texture, "fourth", 100);
+        assert_eq!(tracker.take_budget_events().len(), 1);
+        assert!(tracker.take_budget_events().is_empty());
+    }
+
+    #[test]
+    fn lowering_the_budget_reports_right_away() {
+        let tracker = Arc::new(GpuMemoryTracker::default());
+        let _cached = tracker.track(MemoryCategory::Atlas, "cached", 60);
+        tracker.set_budget(MemoryCategory::Atlas, Some(100));
+        assert!(tracker.take_budget_events().is_empty());
+        tracker.set_budget(MemoryCategory::Atlas, Some(50));
+        assert_eq!(tracker.take_budget_events().len(), 1);
+    }
+}
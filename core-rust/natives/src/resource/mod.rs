@@ -1,2 +1,10 @@
 pub mod mesh_resource;
 pub mod texture_resource;
+pub mod texture_decode;
+pub mod texture_atlas;
+pub mod sampler_resource;
+pub mod gpu_memory;
+pub mod upload_queue;
+pub mod font_resource;
+pub mod glyph_cache;
+pub mod text_layout;
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use crate::java_util::{arc_dispose_handle, arc_from_handle, arc_to_handle, JavaHandle, JNIError, JNIResult};
+
+const MAX_ANISOTROPY: u16 = 16;
+
+pub struct SamplerDesc {
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    pub address_modes: [wgpu::AddressMode; 3],
+    pub anisotropy: u16,
+    pub lod_min_clamp: f32,
+    pub lod_max_clamp: f32,
+    pub compare: Option<wgpu::CompareFunction>,
+}
+
+pub struct SamplerResource {
+    pub sampler: wgpu::Sampler,
+    // comparison samplers can only be bound to depth textures
+    pub comparison: bool,
+}
+
+impl SamplerResource {
+    pub fn new(device: &wgpu::Device, desc: &SamplerDesc) -> JNIResult<Self> {
+        if desc.anisotropy == 0 || desc.anisotropy > MAX_ANISOTROPY {
+            return Err(JNIError::IllegalArgument(format!("anisotropy must be between 1 and {}, got {}", MAX_ANISOTROPY, desc.anisotropy)));
+        }
+        let all_linear = [desc.mag_filter, desc.min_filter, desc.mipmap_filter].iter()
+            .all(|filter| *filter == wgpu::FilterMode::Linear);
+        if desc.anisotropy > 1 && !all_linear {
+            return Err(JNIError::IllegalArgument("anisotropic filtering requires linear mag, min and mipmap filters".to_string()));
+        }
+        if !(desc.lod_min_clamp >= 0.0 && desc.lod_min_clamp <= desc.lod_max_clamp) {
+            return Err(JNIError::IllegalArgument(format!("invalid lod clamp {}..{}", desc.lod_min_clamp, desc.lod_max_clamp)));
+        }
+        Ok(Self {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("sampler resource"),
+                address_mode_u: desc.address_modes[0],
+                address_mode_v: desc.address_modes[1],
+                address_mode_w: desc.address_modes[2],
+                mag_filter: desc.mag_filter,
+                min_filter: desc.min_filter,
+                mipmap_filter: desc.mipmap_filter,
+                lod_min_clamp: desc.lod_min_clamp,
+                lod_max_clamp: desc.lod_max_clamp,
+                compare: desc.compare,
+                anisotropy_clamp: desc.anisotropy,
+                border_color: None
+            }),
+            comparison: desc.compare.is_some()
+        })
+    }
+}
+
+impl JavaHandle<Arc<SamplerResource>> for SamplerResource {
+    fn from_handle(ptr: jni::sys::jlong) -> Option<Arc<SamplerResource>> {
+        arc_from_handle(ptr)
+    }
+
+    fn to_handle(from: Arc<SamplerResource>) -> jni::sys::jlong {
+        arc_to_handle(from)
+    }
+
+    fn drop_handle(ptr: jni::sys::jlong) {
+        arc_dispose_handle::<SamplerResource>(ptr);
+    }
+}
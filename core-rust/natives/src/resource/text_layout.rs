@@ -0,0 +1,560 @@
+use std::ops::Range;
+use std::sync::Arc;
+use ab_glyph::{Font, GlyphId, ScaleFont};
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+use crate::java_util::{JNIError, JNIResult};
+use crate::resource::font_resource::FontResource;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    // left for left to right paragraphs, right for right to left ones
+    Start,
+    End
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextLayoutDesc {
+    // em size in pixels
+    pub size: f32,
+    // width lines are wrapped or truncated at, None leaves lines unbounded
+    pub max_width: Option<f32>,
+    // None keeps every line
+    pub max_lines: Option<u32>,
+    // break lines at `max_width`, otherwise only line breaks in the text start a new line
+    pub wrap: bool,
+    // end lines that were cut short with an ellipsis
+    pub ellipsis: bool,
+    pub align: TextAlign
+}
+
+impl TextLayoutDesc {
+    pub fn new(size: f32) -> Self {
+        Self {
+            size,
+            max_width: None,
+            max_lines: None,
+            wrap: false,
+            ellipsis: false,
+            align: TextAlign::Left
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutGlyph {
+    // index into the fonts of the layout
+    pub font: u16,
+    pub glyph: GlyphId,
+    // pen position on the baseline, relative to the top left corner of the layout. the offset the shaper gave
+    // the glyph, like for marks, is included.
+    pub pos: [f32; 2],
+    pub advance: f32,
+    // offset of the character in utf-16 code units, like the index of a java string
+    pub char_index: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutLine {
+    // first glyph of the line in visual order, the line ends where the next one starts
+    pub glyph_start: usize,
+    // offset in utf-16 code units of the first character of the line
+    pub char_start: u32,
+    pub baseline: f32,
+    // start of the line after alignment
+    pub x: f32,
+    // width without trailing whitespace
+    pub width: f32,
+}
+
+pub struct TextLayout {
+    // the font followed by its fallbacks
+    pub fonts: Vec<Arc<FontResource>>,
+    // em size the layout was made for
+    pub font_size: f32,
+    // distance between the baselines of two lines
+    pub line_advance: f32,
+    pub glyphs: Vec<LayoutGlyph>,
+    pub lines: Vec<LayoutLine>,
+    pub size: [f32; 2],
+}
+
+// a glyph of a paragraph in logical order, positioned on a single endless line
+#[derive(Clone, Copy)]
+struct ShapedGlyph {
+    font: u16,
+    glyph: GlyphId,
+    x: f32,
+    advance: f32,
+    offset: [f32; 2],
+    byte_index: usize,
+    char_index: u32,
+    // end of the cluster, a ligature covers several characters
+    char_end: u32,
+    level: u8,
+    whitespace: bool,
+}
+
+struct ShapedLine {
+    glyphs: Vec<ShapedGlyph>,
+    char_start: u32,
+    // more text followed that didn't fit
+    truncated: bool,
+}
+
+// characters that belong to the cluster before them, they stay in its font as long as it covers them
+fn continues_cluster(c: char) -> bool {
+    c.is_whitespace() || matches!(c,
+        '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}' |
+        '\u{FE20}'..='\u{FE2F}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}' | '\u{200C}' | '\u{200D}')
+}
+
+struct Shaper<'a> {
+    fonts: &'a [Arc<FontResource>],
+    faces: Vec<rustybuzz::Face<'a>>,
+    size: f32,
+}
+
+impl<'a> Shaper<'a> {
+    fn new(fonts: &'a [Arc<FontResource>], size: f32) -> Self {
+        Self {
+            fonts,
+            faces: fonts.iter().map(|font| font.shaper_face()).collect(),
+            size
+        }
+    }
+
+    // the first font of the chain that has a glyph for the character, the primary font if none has one
+    fn select_font(&self, c: char, current: Option<usize>) -> usize {
+        if let Some(current) = current {
+            if continues_cluster(c) && self.fonts[current].has_glyph(c) {
+                return current;
+            }
+        }
+        self.fonts.iter().position(|font| font.has_glyph(c))
+            .or(current)
+            .unwrap_or(0)
+    }
+
+    // shapes a paragraph without line breaks into glyphs in logical order. the text is split into runs of the
+    // same direction and font, `char_start` is the offset of the paragraph in utf-16 code units.
+    fn shape_paragraph(&self, text: &str, char_start: u32) -> (Vec<ShapedGlyph>, u8) {
+        let bidi = BidiInfo::new(text, None);
+        let paragraph_level = bidi.paragraphs.first().map_or(0, |paragraph| paragraph.level.number());
+
+        // utf-16 offset of every byte that starts a character
+        let mut char_offsets = vec![char_start; text.len() + 1];
+        let mut offset = char_start;
+        for (byte_index, c) in text.char_indices() {
+            char_offsets[byte_index] = offset;
+            offset += c.len_utf16() as u32;
+        }
+        char_offsets[text.len()] = offset;
+
+        let mut runs: Vec<(Range<usize>, usize, u8)> = Vec::new();
+        for (byte_index, c) in text.char_indices() {
+            let level = bidi.levels[byte_index].number();
+            let current = runs.last().map(|(_, font, _)| *font);
+            let font = self.select_font(c, current);
+            match runs.last_mut() {
+                Some((range, run_font, run_level)) if *run_font == font && *run_level == level => range.end = byte_index + c.len_utf8(),
+                _ => runs.push((byte_index..byte_index + c.len_utf8(), font, level))
+            }
+        }
+
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut x = 0.0;
+        for (range, font, level) in runs {
+            let run = self.shape_run(text, range, font, level, &char_offsets);
+            for mut glyph in run {
+                glyph.x = x;
+                x += glyph.advance;
+                glyphs.push(glyph);
+            }
+        }
+        (glyphs, paragraph_level)
+    }
+
+    fn shape_run(&self, text: &str, range: Range<usize>, font: usize, level: u8, char_offsets: &[u32]) -> Vec<ShapedGlyph> {
+        let face = &self.faces[font];
+        let scale = self.size / face.units_per_em() as f32;
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(&text[range.clone()]);
+        buffer.set_direction(if level % 2 == 1 { rustybuzz::Direction::RightToLeft } else { rustybuzz::Direction::LeftToRight });
+        let output = rustybuzz::shape(face, &[], buffer);
+
+        let mut glyphs: Vec<ShapedGlyph> = output.glyph_infos().iter().zip(output.glyph_positions())
+            .map(|(info, position)| {
+                let byte_index = range.start + info.cluster as usize;
+                let c = text[byte_index..].chars().next().unwrap();
+                ShapedGlyph {
+                    font: font as u16,
+                    glyph: GlyphId(info.glyph_id as u16),
+                    x: 0.0,
+                    advance: position.x_advance as f32 * scale,
+                    offset: [position.x_offset as f32 * scale, -position.y_offset as f32 * scale],
+                    byte_index,
+                    char_index: char_offsets[byte_index],
+                    char_end: 0,
+                    level,
+                    whitespace: c.is_whitespace()
+                }
+            })
+            .collect();
+        // right to left runs come out in visual order
+        if level % 2 == 1 {
+            glyphs.reverse();
+        }
+        let mut cluster_end = range.end;
+        for index in (0..glyphs.len()).rev() {
+            if index + 1 < glyphs.len() && glyphs[index + 1].byte_index != glyphs[index].byte_index {
+                cluster_end = glyphs[index + 1].byte_index;
+            }
+            glyphs[index].char_end = char_offsets[cluster_end];
+        }
+        // control characters only give context to the shaper
+        glyphs.retain(|glyph| !text[glyph.byte_index..].starts_with(char::is_control));
+        glyphs
+    }
+}
+
+// width of the glyphs when the first one is placed at the start of a line, trailing whitespace doesn't count
+fn line_width(glyphs: &[ShapedGlyph]) -> f32 {
+    let Some(first) = glyphs.first() else { return 0.0 };
+    glyphs.iter().rev()
+        .find(|glyph| !glyph.whitespace)
+        .map_or(0.0, |last| last.x + last.advance - first.x)
+}
+
+// splits a paragraph at the break opportunities closest to `max_width`. words longer than a line are broken
+// between clusters.
+fn wrap_paragraph(text: &str, mut glyphs: Vec<ShapedGlyph>, char_start: u32, max_width: f32) -> Vec<ShapedLine> {
+    let mut breaks = linebreaks(text)
+        .filter(|(_, opportunity)| *opportunity == BreakOpportunity::Allowed)
+        .map(|(index, _)| index)
+        .peekable();
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    let mut last_break = None;
+    let mut index = 0;
+    while index < glyphs.len() {
+        while breaks.next_if(|&position| position <= glyphs[index].byte_index).is_some() {
+            last_break = Some(index);
+        }
+        let glyph = &glyphs[index];
+        let overflows = !glyph.whitespace && glyph.x + glyph.advance - glyphs[line_start].x > max_width;
+        if overflows && index > line_start {
+            let end = match last_break {
+                Some(end) if end > line_start => end,
+                // never split the glyphs of a cluster
+                _ => (line_start + 1..=index).rev()
+                    .find(|&end| glyphs[end].byte_index != glyphs[end - 1].byte_index)
+                    .unwrap_or(index)
+            };
+            lines.push(end);
+            line_start = end;
+            last_break = None;
+            index = end;
+            continue;
+        }
+        index += 1;
+    }
+
+    let mut result = Vec::with_capacity(lines.len() + 1);
+    for end in lines.into_iter().rev() {
+        let rest = glyphs.split_off(end);
+        result.push(ShapedLine {
+            char_start: rest[0].char_index,
+            glyphs: rest,
+            truncated: false
+        });
+    }
+    result.push(ShapedLine {
+        glyphs,
+        char_start,
+        truncated: false
+    });
+    result.reverse();
+    result
+}
+
+// cuts glyphs off the end of the line until the ellipsis fits behind it
+fn truncate_with_ellipsis(shaper: &Shaper, line: &mut ShapedLine, paragraph_level: u8, max_width: f32) {
+    let ellipsis_text = if shaper.fonts.iter().any(|font| font.has_glyph('\u{2026}')) { "\u{2026}" } else { "..." };
+    let (ellipsis, _) = shaper.shape_paragraph(ellipsis_text, 0);
+    append_ellipsis(line, ellipsis, paragraph_level, max_width);
+}
+
+fn append_ellipsis(line: &mut ShapedLine, ellipsis: Vec<ShapedGlyph>, paragraph_level: u8, max_width: f32) {
+    let ellipsis_width = line_width(&ellipsis);
+    let line_x = line.glyphs.first().map_or(0.0, |glyph| glyph.x);
+    // the ellipsis stands in for everything from the first character that was cut off
+    let mut char_index = line.glyphs.last().map_or(line.char_start, |last| last.char_end);
+    while let Some(last) = line.glyphs.last() {
+        if !last.whitespace && last.x + last.advance - line_x + ellipsis_width <= max_width {
+            break;
+        }
+        char_index = last.char_index;
+        line.glyphs.pop();
+    }
+    let x = line.glyphs.last().map_or(line_x, |last| last.x + last.advance);
+    line.glyphs.extend(ellipsis.into_iter().map(|glyph| ShapedGlyph {
+        x: x + glyph.x,
+        char_index,
+        char_end: char_index,
+        level: paragraph_level,
+        ..glyph
+    }));
+}
+
+// drops the lines past `max_lines`, the last one kept is marked as cut short
+fn limit_lines(lines: &mut Vec<(ShapedLine, u8)>, max_lines: usize) {
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        lines.last_mut().unwrap().0.truncated = true;
+    }
+}
+
+// brings the glyphs of a line from logical into visual order, rules L1 and L2 of the unicode bidi algorithm
+fn reorder_line(glyphs: &mut [ShapedGlyph], paragraph_level: u8) {
+    for glyph in glyphs.iter_mut().rev() {
+        if !glyph.whitespace {
+            break;
+        }
+        glyph.level = paragraph_level;
+    }
+    let Some(highest) = glyphs.iter().map(|glyph| glyph.level).max() else { return };
+    let lowest = glyphs.iter().map(|glyph| glyph.level).min().unwrap();
+    let lowest_odd = lowest | 1;
+    for level in (lowest_odd..=highest).rev() {
+        let mut start = 0;
+        while start < glyphs.len() {
+            if glyphs[start].level < level {
+                start += 1;
+                continue;
+            }
+            let end = glyphs[start..].iter().position(|glyph| glyph.level < level).map_or(glyphs.len(), |length| start + length);
+            glyphs[start..end].reverse();
+            start = end;
+        }
+    }
+}
+
+// lays out text in `font`, characters the font has no glyph for are taken from its fallbacks. line metrics
+// come from the primary font.
+pub fn layout_text(font: &Arc<FontResource>, desc: &TextLayoutDesc, text: &str) -> JNIResult<TextLayout> {
+    if !(desc.size > 0.0 && desc.size.is_finite()) {
+        return Err(JNIError::IllegalArgument(format!("invalid font size {}", desc.size)));
+    }
+    if let Some(max_width) = desc.max_width {
+        if !(max_width > 0.0 && max_width.is_finite()) {
+            return Err(JNIError::IllegalArgument(format!("invalid max width {}", max_width)));
+        }
+    }
+    if desc.max_lines == Some(0) {
+        return Err(JNIError::IllegalArgument("max lines has to be at least 1".to_string()));
+    }
+    let fonts = font.chain();
+    let shaper = Shaper::new(&fonts, desc.size);
+    let scaled = font.font.as_scaled(font.px_scale(desc.size));
+
+    // lines with the level of the paragraph they belong to
+    let mut lines: Vec<(ShapedLine, u8)> = Vec::new();
+    let mut char_start = 0;
+    for paragraph in text.split('\n') {
+        let (glyphs, level) = shaper.shape_paragraph(paragraph, char_start);
+        match desc.max_width {
+            Some(max_width) if desc.wrap => lines.extend(wrap_paragraph(paragraph, glyphs, char_start, max_width).into_iter().map(|line| (line, level))),
+            _ => lines.push((ShapedLine { glyphs, char_start, truncated: false }, level))
+        }
+        // the line break itself is one code unit
+        char_start += paragraph.encode_utf16().count() as u32 + 1;
+    }
+    if let Some(max_lines) = desc.max_lines {
+        limit_lines(&mut lines, max_lines as usize);
+    }
+    if let (Some(max_width), true) = (desc.max_width, desc.ellipsis) {
+        for (line, level) in lines.iter_mut() {
+            if line.truncated || line_width(&line.glyphs) > max_width {
+                truncate_with_ellipsis(&shaper, line, *level, max_width);
+            }
+        }
+    }
+
+    let line_advance = scaled.height() + scaled.line_gap();
+    let widths: Vec<f32> = lines.iter().map(|(line, _)| line_width(&line.glyphs)).collect();
+    let widest = widths.iter().copied().fold(0.0, f32::max);
+    // lines are aligned within the max width if there is one
+    let align_width = desc.max_width.unwrap_or(widest);
+    let mut layout = TextLayout {
+        font_size: desc.size,
+        line_advance,
+        glyphs: Vec::with_capacity(lines.iter().map(|(line, _)| line.glyphs.len()).sum()),
+        lines: Vec::with_capacity(lines.len()),
+        size: [widest, scaled.height() + (lines.len() - 1) as f32 * line_advance],
+        fonts: Vec::new()
+    };
+    for (index, ((mut line, level), line_width)) in lines.into_iter().zip(widths).enumerate() {
+        let baseline = scaled.ascent() + index as f32 * line_advance;
+        let rtl = level % 2 == 1;
+        let offset = match (desc.align, rtl) {
+            (TextAlign::Left, _) | (TextAlign::Start, false) | (TextAlign::End, true) => 0.0,
+            (TextAlign::Center, _) => (align_width - line_width) * 0.5,
+            (TextAlign::Right, _) | (TextAlign::Start, true) | (TextAlign::End, false) => align_width - line_width
+        };
+        // trailing whitespace of right to left lines ends up on the left, it hangs outside the line
+        let trailing_whitespace: f32 = line.glyphs.iter().rev().take_while(|glyph| glyph.whitespace).map(|glyph| glyph.advance).sum();
+        reorder_line(&mut line.glyphs, level);
+        layout.lines.push(LayoutLine {
+            glyph_start: layout.glyphs.len(),
+            char_start: line.char_start,
+            baseline,
+            x: offset,
+            width: line_width
+        });
+        let mut pen = offset - if rtl { trailing_whitespace } else { 0.0 };
+        for glyph in line.glyphs {
+            layout.glyphs.push(LayoutGlyph {
+                font: glyph.font,
+                glyph: glyph.glyph,
+                pos: [pen + glyph.offset[0], baseline + glyph.offset[1]],
+                advance: glyph.advance,
+                char_index: glyph.char_index
+            });
+            pen += glyph.advance;
+        }
+    }
+    layout.fonts = fonts;
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADVANCE: f32 = 10.0;
+
+    // one glyph per character, every glyph `ADVANCE` wide. uppercase letters stand in for right to left text.
+    fn shape(text: &str, char_start: u32) -> Vec<ShapedGlyph> {
+        text.char_indices().enumerate().map(|(index, (byte_index, c))| ShapedGlyph {
+            font: 0,
+            glyph: GlyphId(c as u16),
+            x: index as f32 * ADVANCE,
+            advance: ADVANCE,
+            offset: [0.0, 0.0],
+            byte_index,
+            char_index: char_start + index as u32,
+            char_end: char_start + index as u32 + 1,
+            level: if c.is_ascii_uppercase() { 1 } else { 0 },
+            whitespace: c.is_whitespace()
+        }).collect()
+    }
+
+    fn text_of(glyphs: &[ShapedGlyph]) -> String {
+        glyphs.iter().map(|glyph| char::from_u32(glyph.glyph.0 as u32).unwrap()).collect()
+    }
+
+    fn wrap(text: &str, max_width: f32) -> Vec<String> {
+        wrap_paragraph(text, shape(text, 0), 0, max_width).iter().map(|line| text_of(&line.glyphs)).collect()
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(wrap("aaa bbb ccc", 65.0), ["aaa ", "bbb ", "ccc"]);
+        // trailing whitespace may hang past the max width
+        assert_eq!(wrap("aaa bbb", 30.0), ["aaa ", "bbb"]);
+        assert_eq!(wrap("aaa bbb", 70.0), ["aaa bbb"]);
+    }
+
+    #[test]
+    fn breaks_words_longer_than_a_line() {
+        assert_eq!(wrap("abcdefgh", 30.0), ["abc", "def", "gh"]);
+        assert_eq!(wrap("ab cdefgh", 30.0), ["ab ", "cde", "fgh"]);
+        // a single glyph wider than the line still takes up a line of its own
+        assert_eq!(wrap("ab", 5.0), ["a", "b"]);
+    }
+
+    #[test]
+    fn wrapped_lines_start_at_their_first_character() {
+        let lines = wrap_paragraph("aaa bbb", shape("aaa bbb", 12), 12, 35.0);
+        assert_eq!(lines.iter().map(|line| line.char_start).collect::<Vec<_>>(), [12, 16]);
+    }
+
+    #[test]
+    fn line_width_ignores_trailing_whitespace() {
+        assert_eq!(line_width(&shape("ab  ", 0)), 20.0);
+        assert_eq!(line_width(&shape("  ", 0)), 0.0);
+        assert_eq!(line_width(&shape("", 0)), 0.0);
+        // measured from the first glyph of the line
+        assert_eq!(line_width(&shape("ab cd", 0)[3..]), 20.0);
+    }
+
+    #[test]
+    fn limits_lines() {
+        let mut lines: Vec<(ShapedLine, u8)> = ["a", "b", "c"].iter().map(|text| (ShapedLine {
+            glyphs: shape(text, 0),
+            char_start: 0,
+            truncated: false
+        }, 0)).collect();
+        limit_lines(&mut lines, 3);
+        assert!(lines.iter().all(|(line, _)| !line.truncated));
+        limit_lines(&mut lines, 2);
+        assert_eq!(lines.len(), 2);
+        assert!(!lines[0].0.truncated);
+        assert!(lines[1].0.truncated);
+    }
+
+    #[test]
+    fn ellipsis_replaces_what_does_not_fit() {
+        let mut line = ShapedLine { glyphs: shape("abcdef", 4), char_start: 4, truncated: false };
+        append_ellipsis(&mut line, shape("...", 0), 0, 50.0);
+        assert_eq!(text_of(&line.glyphs), "ab...");
+        assert_eq!(line_width(&line.glyphs), 50.0);
+        // the ellipsis stands in for the first character that was cut off
+        assert!(line.glyphs[2..].iter().all(|glyph| glyph.char_index == 6));
+    }
+
+    #[test]
+    fn ellipsis_does_not_follow_whitespace() {
+        let mut line = ShapedLine { glyphs: shape("ab cdef", 0), char_start: 0, truncated: false };
+        append_ellipsis(&mut line, shape("...", 0), 0, 60.0);
+        assert_eq!(text_of(&line.glyphs), "ab...");
+    }
+
+    #[test]
+    fn ellipsis_is_appended_to_lines_that_fit() {
+        // a line cut short by max lines keeps all of its glyphs if there is room
+        let mut line = ShapedLine { glyphs: shape("ab", 0), char_start: 0, truncated: true };
+        append_ellipsis(&mut line, shape("...", 0), 0, 100.0);
+        assert_eq!(text_of(&line.glyphs), "ab...");
+        assert_eq!(line.glyphs[2].char_index, 2);
+    }
+
+    #[test]
+    fn reorders_right_to_left_runs() {
+        let mut glyphs = shape("ab CDE fg", 0);
+        reorder_line(&mut glyphs, 0);
+        assert_eq!(text_of(&glyphs), "ab EDC fg");
+
+        let mut glyphs = shape("ABC de", 0);
+        for glyph in glyphs.iter_mut().filter(|glyph| glyph.whitespace) {
+            glyph.level = 1;
+        }
+        for glyph in glyphs.iter_mut().filter(|glyph| !glyph.whitespace && glyph.level == 0) {
+            glyph.level = 2;
+        }
+        reorder_line(&mut glyphs, 1);
+        assert_eq!(text_of(&glyphs), "de CBA");
+    }
+
+    #[test]
+    fn trailing_whitespace_takes_the_paragraph_level() {
+        let mut glyphs = shape("AB  ", 0);
+        reorder_line(&mut glyphs, 0);
+        assert_eq!(text_of(&glyphs), "BA  ");
+    }
+}
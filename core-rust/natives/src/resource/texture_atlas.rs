@@ -0,0 +1,350 @@
+use std::sync::{Arc, Mutex};
+use etagere::{size2, AllocId, AllocatorOptions, BucketedAtlasAllocator};
+
+use crate::java_util::{arc_dispose_handle, arc_from_handle, arc_to_handle, JavaHandle, JNIError, JNIResult};
+use crate::math::rect::Rect;
+use crate::resource::texture_resource::{TextureRegion, TextureResource};
+use crate::resource::gpu_memory::{GpuMemoryTracker, MemoryCategory};
+
+const ATLAS_BYTES_PER_TEXEL: usize = 4;
+
+pub struct TextureAtlasDesc {
+    pub page_size: u32,
+    // pages double in size until they reach this size, after that new pages are added
+    pub max_page_size: u32,
+    // gutter of replicated edge texels around every image
+    pub padding: u32,
+    pub mip_levels: u32,
+    pub format: wgpu::TextureFormat,
+}
+
+struct AtlasPage {
+    texture: Arc<TextureResource>,
+    allocator: BucketedAtlasAllocator,
+}
+
+// packs many small rgba8 images into shared pages. allocations are aligned to the size of the
+// smallest mip so every image keeps its own gutter down to the last mip level.
+pub struct TextureAtlas {
+    desc: TextureAtlasDesc,
+    pages: Mutex<Vec<AtlasPage>>,
+    memory: Arc<GpuMemoryTracker>,
+}
+
+// an image inside an atlas page, frees its space when dropped
+pub struct AtlasSubTexture {
+    atlas: Arc<TextureAtlas>,
+    page: usize,
+    id: AllocId,
+    // texels covered by the image, excluding the gutter
+    rect: [u32; 4],
+}
+
+impl Drop for AtlasSubTexture {
+    fn drop(&mut self) {
+        let mut pages = self.atlas.pages.lock().expect("failed to lock atlas pages");
+        pages[self.page].allocator.deallocate(self.id);
+    }
+}
+
+impl AtlasSubTexture {
+    pub fn size(&self) -> [u32; 2] {
+        [self.rect[2], self.rect[3]]
+    }
+
+    // the page texture and the uv rect of the image inside of it.
+    // pages can grow, so this has to be resolved again every time the image is drawn.
+    pub fn resolve(&self) -> (Arc<TextureResource>, Rect) {
+        let pages = self.atlas.pages.lock().expect("failed to lock atlas pages");
+        let texture = pages[self.page].texture.clone();
+        let size = texture.texture.size();
+        let uv = Rect {
+            min: [
+                self.rect[0] as f32 / size.width as f32,
+                self.rect[1] as f32 / size.height as f32
+            ],
+            max: [
+                (self.rect[0] + self.rect[2]) as f32 / size.width as f32,
+                (self.rect[1] + self.rect[3]) as f32 / size.height as f32
+            ]
+        };
+        (texture, uv)
+    }
+}
+
+impl TextureAtlas {
+    pub fn new(desc: TextureAtlasDesc, memory: &Arc<GpuMemoryTracker>) -> JNIResult<Self> {
+        if !matches!(desc.format, wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb) {
+            return Err(JNIError::IllegalArgument(format!("atlas format {:?} is not supported, only rgba8 atlases can be packed", desc.format)));
+        }
+        if desc.mip_levels == 0 || desc.mip_levels > 8 {
+            return Err(JNIError::IllegalArgument(format!("atlas mip levels must be between 1 and 8, got {}", desc.mip_levels)));
+        }
+        if desc.page_size < (1 << (desc.mip_levels - 1)) || desc.page_size > desc.max_page_size || desc.max_page_size > u16::MAX as u32 {
+            return Err(JNIError::IllegalArgument(format!("invalid atlas page size {} with max page size {}", desc.page_size, desc.max_page_size)));
+        }
+        if desc.padding > desc.max_page_size {
+            return Err(JNIError::IllegalArgument(format!("atlas padding {} exceeds the max page size {}", desc.padding, desc.max_page_size)));
+        }
+        Ok(Self {
+            desc,
+            pages: Mutex::new(Vec::new()),
+            memory: memory.clone()
+        })
+    }
+
+    fn alignment(&self) -> u32 {
+        1 << (self.desc.mip_levels - 1)
+    }
+
+    fn create_page_texture(&self, device: &wgpu::Device, size: u32) -> Arc<TextureResource> {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 1
+            },
+            mip_level_count: self.desc.mip_levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.desc.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            label: Some("texture atlas page"),
+            view_formats: &[],
+        });
+        Arc::new(TextureResource::with_category(&self.memory, texture, MemoryCategory::Atlas, Some("texture atlas page")))
+    }
+
+    fn create_page(&self, device: &wgpu::Device, size: u32) -> AtlasPage {
+        let alignment = self.alignment() as i32;
+        AtlasPage {
+            texture: self.create_page_texture(device, size),
+            allocator: BucketedAtlasAllocator::with_options(size2(size as i32, size as i32), &AllocatorOptions {
+                alignment: size2(alignment, alignment),
+                ..etagere::DEFAULT_OPTIONS
+            })
+        }
+    }
+
+    // doubles the size of a page and copies the existing images over
+    fn grow_page(&self, device: &wgpu::Device, queue: &wgpu::Queue, page: &mut AtlasPage) {
+        let old_size = page.allocator.size().width as u32;
+        let new_size = (old_size * 2).min(self.desc.max_page_size);
+        let texture = self.create_page_texture(device, new_size);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("grow texture atlas") });
+        for mip in 0..self.desc.mip_levels {
+            encoder.copy_texture_to_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &page.texture.texture,
+                    mip_level: mip,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All
+                },
+                wgpu::ImageCopyTexture {
+                    texture: &texture.texture,
+                    mip_level: mip,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All
+                },
+                page.texture.mip_extent(mip));
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        page.allocator.grow(size2(new_size as i32, new_size as i32));
+        page.texture = texture;
+    }
+
+    // finds space for `width` x `height` texels, growing pages or adding new ones when the atlas is full
+    fn allocate(&self, device: &wgpu::Device, queue: &wgpu::Queue, pages: &mut Vec<AtlasPage>, width: u32, height: u32) -> JNIResult<(usize, etagere::Allocation)> {
+        let size = width.max(height);
+        if size > self.desc.max_page_size {
+            return Err(JNIError::IllegalArgument(format!("image with gutter is {}x{} texels, larger than the max atlas page size {}", width, height, self.desc.max_page_size)));
+        }
+        let requested = size2(width as i32, height as i32);
+        for (index, page) in pages.iter_mut().enumerate() {
+            if let Some(allocation) = page.allocator.allocate(requested) {
+                return Ok((index, allocation));
+            }
+        }
+        for (index, page) in pages.iter_mut().enumerate() {
+            while (page.allocator.size().width as u32) < self.desc.max_page_size {
+                self.grow_page(device, queue, page);
+                if let Some(allocation) = page.allocator.allocate(requested) {
+                    return Ok((index, allocation));
+                }
+            }
+        }
+        let mut page_size = self.desc.page_size;
+        while page_size < size {
+            page_size *= 2;
+        }
+        let mut page = self.create_page(device, page_size.min(self.desc.max_page_size));
+        let allocation = page.allocator.allocate(requested)
+            .ok_or_else(|| JNIError::Generic("failed to allocate space in a new atlas page".to_string()))?;
+        pages.push(page);
+        Ok((pages.len() - 1, allocation))
+    }
+
+    // packs a tightly packed rgba8 image into the atlas
+    pub fn insert(self: &Arc<Self>, device: &wgpu::Device, queue: &wgpu::Queue, width: u32, height: u32, data: &[u8]) -> JNIResult<AtlasSubTexture> {
+        if width == 0 || height == 0 {
+            return Err(JNIError::IllegalArgument("atlas image must not be empty".to_string()));
+        }
+        // larger images can't fit a page, this also keeps the sizes below from overflowing
+        if width > self.desc.max_page_size || height > self.desc.max_page_size {
+            return Err(JNIError::IllegalArgument(format!("image is {}x{} texels, larger than the max atlas page size {}", width, height, self.desc.max_page_size)));
+        }
+        let required = image_size(width, height)
+            .ok_or_else(|| JNIError::IllegalArgument(format!("image of {}x{} texels is too large", width, height)))?;
+        if data.len() < required {
+            return Err(JNIError::IllegalArgument(format!("buffer holds {} bytes but the image requires {}", data.len(), required)));
+        }
+        let padding = self.desc.padding;
+        let padded_width = width + padding * 2;
+        let padded_height = height + padding * 2;
+
+        let mut pages = self.pages.lock().expect("failed to lock atlas pages");
+        let (page_index, allocation) = self.allocate(device, queue, &mut pages, padded_width, padded_height)?;
+        let page = &pages[page_index];
+        let origin = [allocation.rectangle.min.x as u32, allocation.rectangle.min.y as u32];
+
+        let mut mip_width = padded_width;
+        let mut mip_height = padded_height;
+        let mut mip_data = extrude_image(&data[..required], width, height, padding);
+        for mip in 0..self.desc.mip_levels {
+            page.texture.write_region(queue, &TextureRegion {
+                x: origin[0] >> mip,
+                y: origin[1] >> mip,
+                layer: 0,
+                mip,
+                width: mip_width,
+                height: mip_height
+            }, &mip_data, 0)?;
+            if mip + 1 < self.desc.mip_levels {
+                (mip_width, mip_height, mip_data) = downsample_image(&mip_data, mip_width, mip_height);
+            }
+        }
+
+        Ok(AtlasSubTexture {
+            atlas: self.clone(),
+            page: page_index,
+            id: allocation.id,
+            rect: [origin[0] + padding, origin[1] + padding, width, height]
+        })
+    }
+}
+
+// the bytes of a tightly packed image, none if it doesn't fit in memory
+fn image_size(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul(ATLAS_BYTES_PER_TEXEL)
+}
+
+fn texel_offset(x: u32, y: u32, width: u32) -> usize {
+    (y as usize * width as usize + x as usize) * ATLAS_BYTES_PER_TEXEL
+}
+
+// surrounds the image with `padding` texels that repeat its edges
+fn extrude_image(data: &[u8], width: u32, height: u32, padding: u32) -> Vec<u8> {
+    let padded_width = width + padding * 2;
+    let padded_height = height + padding * 2;
+    let mut result = Vec::with_capacity(image_size(padded_width, padded_height).expect("atlas image too large"));
+    for y in 0..padded_height {
+        let source_y = y.saturating_sub(padding).min(height - 1);
+        for x in 0..padded_width {
+            let source_x = x.saturating_sub(padding).min(width - 1);
+            let offset = texel_offset(source_x, source_y, width);
+            result.extend_from_slice(&data[offset..offset + ATLAS_BYTES_PER_TEXEL]);
+        }
+    }
+    result
+}
+
+// box filters the image down to half its size, odd edges are clamped
+fn downsample_image(data: &[u8], width: u32, height: u32) -> (u32, u32, Vec<u8>) {
+    let half_width = width.div_ceil(2);
+    let half_height = height.div_ceil(2);
+    let mut result = Vec::with_capacity(image_size(half_width, half_height).expect("atlas image too large"));
+    for y in 0..half_height {
+        for x in 0..half_width {
+            let mut sum = [0u32; ATLAS_BYTES_PER_TEXEL];
+            for (sample_x, sample_y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let source_x = (x * 2 + sample_x).min(width - 1);
+                let source_y = (y * 2 + sample_y).min(height - 1);
+                let offset = texel_offset(source_x, source_y, width);
+                for (channel, value) in sum.iter_mut().enumerate() {
+                    *value += data[offset + channel] as u32;
+                }
+            }
+            result.extend(sum.iter().map(|value| (value / 4) as u8));
+        }
+    }
+    (half_width, half_height, result)
+}
+
+impl JavaHandle<Arc<TextureAtlas>> for TextureAtlas {
+    fn from_handle(ptr: jni::sys::jlong) -> Option<Arc<TextureAtlas>> {
+        arc_from_handle(ptr)
+    }
+
+    fn to_handle(from: Arc<TextureAtlas>) -> jni::sys::jlong {
+        arc_to_handle(from)
+    }
+
+    fn drop_handle(ptr: jni::sys::jlong) {
+        arc_dispose_handle::<TextureAtlas>(ptr);
+    }
+}
+
+impl JavaHandle<Arc<AtlasSubTexture>> for AtlasSubTexture {
+    fn from_handle(ptr: jni::sys::jlong) -> Option<Arc<AtlasSubTexture>> {
+        arc_from_handle(ptr)
+    }
+
+    fn to_handle(from: Arc<AtlasSubTexture>) -> jni::sys::jlong {
+        arc_to_handle(from)
+    }
+
+    fn drop_handle(ptr: jni::sys::jlong) {
+        arc_dispose_handle::<AtlasSubTexture>(ptr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extrude_repeats_edges() {
+        let data = [1, 1, 1, 1, 2, 2, 2, 2];
+        let extruded = extrude_image(&data, 2, 1, 1);
+        let row: Vec<u8> = [1, 1, 2, 2].iter().flat_map(|value| [*value; 4]).collect();
+        assert_eq!(extruded, [row.clone(), row.clone(), row].concat());
+    }
+
+    #[test]
+    fn extrude_without_padding_copies() {
+        let data: Vec<u8> = (0..16).collect();
+        assert_eq!(extrude_image(&data, 2, 2, 0), data);
+    }
+
+    #[test]
+    fn downsample_averages_texels() {
+        let data = [0, 0, 0, 0, 4, 4, 4, 4, 8, 8, 8, 8, 12, 12, 12, 12];
+        assert_eq!(downsample_image(&data, 2, 2), (1, 1, vec![6, 6, 6, 6]));
+    }
+
+    #[test]
+    fn downsample_clamps_odd_edges() {
+        let data = [0, 0, 0, 0, 8, 8, 8, 8, 20, 20, 20, 20];
+        let (width, height, result) = downsample_image(&data, 3, 1);
+        assert_eq!((width, height), (2, 1));
+        assert_eq!(result, vec![4, 4, 4, 4, 20, 20, 20, 20]);
+    }
+
+    #[test]
+    fn image_size_checks_overflow() {
+        assert_eq!(image_size(3, 2), Some(24));
+        assert_eq!(image_size(u32::MAX, u32::MAX), None);
+    }
+}
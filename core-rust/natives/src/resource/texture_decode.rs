@@ -0,0 +1,464 @@
+use ddsfile::{Caps2, D3DFormat, Dds, DxgiFormat, MiscFlag};
+
+use crate::java_util::{JNIError, JNIResult};
+use crate::resource::texture_resource::TextureFormatExt;
+
+const KTX2_MAGIC: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const DDS_MAGIC: [u8; 4] = *b"DDS ";
+const TGA_HEADER_SIZE: usize = 18;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EncodedContainer {
+    Png,
+    Jpeg,
+    Tga,
+    Dds,
+    Ktx2
+}
+
+pub struct DecodeHints {
+    // treat 8 bit color data without explicit color space as sRGB
+    pub srgb: bool,
+}
+
+// a decoded image with its data laid out the way `create_texture_with_data` expects it,
+// every mip of the first layer followed by every mip of the next layer.
+pub struct DecodedTexture {
+    pub size: wgpu::Extent3d,
+    pub dimension: wgpu::TextureDimension,
+    pub format: wgpu::TextureFormat,
+    pub mip_level_count: u32,
+    // the layers are the faces of one or more cubes
+    pub cube: bool,
+    pub data: Vec<u8>,
+}
+
+pub fn detect_container(bytes: &[u8]) -> JNIResult<EncodedContainer> {
+    if bytes.starts_with(&KTX2_MAGIC) {
+        return Ok(EncodedContainer::Ktx2);
+    }
+    if bytes.starts_with(&DDS_MAGIC) {
+        return Ok(EncodedContainer::Dds);
+    }
+    match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Png) => Ok(EncodedContainer::Png),
+        Ok(image::ImageFormat::Jpeg) => Ok(EncodedContainer::Jpeg),
+        // TGA has no magic number, its header is checked for values a TGA image can have instead
+        _ if is_tga_header(bytes) => Ok(EncodedContainer::Tga),
+        _ => Err(JNIError::IllegalArgument("unknown image container".to_string()))
+    }
+}
+
+fn is_tga_header(bytes: &[u8]) -> bool {
+    if bytes.len() < TGA_HEADER_SIZE {
+        return false;
+    }
+    let color_map_type = bytes[1];
+    let image_type = bytes[2];
+    let width = u16::from_le_bytes([bytes[12], bytes[13]]);
+    let height = u16::from_le_bytes([bytes[14], bytes[15]]);
+    let pixel_depth = bytes[16];
+    let color_mapped = matches!(image_type, 1 | 9);
+    // true color images may still carry an unused color map
+    match (color_map_type, color_mapped) {
+        (0, false) | (1, _) => {},
+        _ => return false
+    }
+    // color mapped, true color and grayscale, each raw or run length encoded
+    matches!(image_type, 1 | 2 | 3 | 9 | 10 | 11) &&
+        matches!(pixel_depth, 8 | 15 | 16 | 24 | 32) &&
+        width > 0 && height > 0
+}
+
+pub fn decode_texture(bytes: &[u8], hints: &DecodeHints) -> JNIResult<DecodedTexture> {
+    let container = detect_container(bytes)?;
+    let mut decoded = match container {
+        EncodedContainer::Png => decode_image(bytes, image::ImageFormat::Png, hints),
+        EncodedContainer::Jpeg => decode_image(bytes, image::ImageFormat::Jpeg, hints),
+        EncodedContainer::Tga => decode_image(bytes, image::ImageFormat::Tga, hints),
+        EncodedContainer::Dds => decode_dds(bytes, hints),
+        EncodedContainer::Ktx2 => decode_ktx2(bytes, hints),
+    }?;
+
+    let expected = texture_data_size(decoded.format, decoded.dimension, decoded.size, decoded.mip_level_count)?;
+    if decoded.data.len() < expected {
+        return Err(JNIError::IllegalArgument(format!("{:?} image holds {} bytes but {} are required", container, decoded.data.len(), expected)));
+    }
+    decoded.data.truncate(expected);
+    Ok(decoded)
+}
+
+// bytes of every mip of every layer, tightly packed in the order wgpu expects initial texture data in
+pub fn texture_data_size(format: wgpu::TextureFormat, dimension: wgpu::TextureDimension, size: wgpu::Extent3d, mip_level_count: u32) -> JNIResult<usize> {
+    let block_bytes = format.bit_size_block() / 8;
+    // depth and stencil can only be written one aspect at a time
+    if block_bytes == 0 || format.is_depth_stencil_format() {
+        return Err(JNIError::IllegalArgument(format!("texture format {:?} can't be uploaded", format)));
+    }
+    let (block_width, block_height) = format.block_size_texels();
+    let layers = match dimension {
+        wgpu::TextureDimension::D3 => 1,
+        _ => size.depth_or_array_layers
+    };
+    let mut layer_size = 0;
+    for mip in 0..mip_level_count {
+        let extent = size.mip_level_size(mip, dimension);
+        let depth = match dimension {
+            wgpu::TextureDimension::D3 => extent.depth_or_array_layers,
+            _ => 1
+        };
+        layer_size += extent.width.div_ceil(block_width) as usize *
+            extent.height.div_ceil(block_height) as usize *
+            depth as usize * block_bytes as usize;
+    }
+    Ok(layer_size * layers as usize)
+}
+
+fn decode_image(bytes: &[u8], format: image::ImageFormat, hints: &DecodeHints) -> JNIResult<DecodedTexture> {
+    let image = image::load_from_memory_with_format(bytes, format)
+        .map_err(|err| JNIError::IllegalArgument(format!("failed to decode {:?} image: {}", format, err)))?
+        .into_rgba8();
+    Ok(DecodedTexture {
+        size: wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1
+        },
+        dimension: wgpu::TextureDimension::D2,
+        format: if hints.srgb { wgpu::TextureFormat::Rgba8UnormSrgb } else { wgpu::TextureFormat::Rgba8Unorm },
+        mip_level_count: 1,
+        cube: false,
+        data: image.into_raw()
+    })
+}
+
+fn dxgi_texture_format(format: DxgiFormat, hints: &DecodeHints) -> Option<wgpu::TextureFormat> {
+    Some(match format {
+        DxgiFormat::R8_UNorm => wgpu::TextureFormat::R8Unorm,
+        DxgiFormat::R8_SNorm => wgpu::TextureFormat::R8Snorm,
+        DxgiFormat::R8_UInt => wgpu::TextureFormat::R8Uint,
+        DxgiFormat::R8_SInt => wgpu::TextureFormat::R8Sint,
+        DxgiFormat::R8G8_UNorm => wgpu::TextureFormat::Rg8Unorm,
+        DxgiFormat::R8G8_SNorm => wgpu::TextureFormat::Rg8Snorm,
+        DxgiFormat::R8G8_UInt => wgpu::TextureFormat::Rg8Uint,
+        DxgiFormat::R8G8_SInt => wgpu::TextureFormat::Rg8Sint,
+        DxgiFormat::R8G8B8A8_UNorm if hints.srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::R8G8B8A8_UNorm => wgpu::TextureFormat::Rgba8Unorm,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        DxgiFormat::R8G8B8A8_SNorm => wgpu::TextureFormat::Rgba8Snorm,
+        DxgiFormat::R8G8B8A8_UInt => wgpu::TextureFormat::Rgba8Uint,
+        DxgiFormat::R8G8B8A8_SInt => wgpu::TextureFormat::Rgba8Sint,
+        DxgiFormat::B8G8R8A8_UNorm if hints.srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::B8G8R8A8_UNorm => wgpu::TextureFormat::Bgra8Unorm,
+        DxgiFormat::B8G8R8A8_UNorm_sRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        DxgiFormat::R16_Float => wgpu::TextureFormat::R16Float,
+        DxgiFormat::R16G16B16A16_Float => wgpu::TextureFormat::Rgba16Float,
+        DxgiFormat::R32_Float => wgpu::TextureFormat::R32Float,
+        DxgiFormat::R11G11B10_Float => wgpu::TextureFormat::Rg11b10Float,
+        DxgiFormat::BC1_UNorm if hints.srgb => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC1_UNorm => wgpu::TextureFormat::Bc1RgbaUnorm,
+        DxgiFormat::BC1_UNorm_sRGB => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm if hints.srgb => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC2_UNorm => wgpu::TextureFormat::Bc2RgbaUnorm,
+        DxgiFormat::BC2_UNorm_sRGB => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm if hints.srgb => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC3_UNorm => wgpu::TextureFormat::Bc3RgbaUnorm,
+        DxgiFormat::BC3_UNorm_sRGB => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        DxgiFormat::BC4_UNorm => wgpu::TextureFormat::Bc4RUnorm,
+        DxgiFormat::BC4_SNorm => wgpu::TextureFormat::Bc4RSnorm,
+        DxgiFormat::BC5_UNorm => wgpu::TextureFormat::Bc5RgUnorm,
+        DxgiFormat::BC5_SNorm => wgpu::TextureFormat::Bc5RgSnorm,
+        DxgiFormat::BC6H_UF16 => wgpu::TextureFormat::Bc6hRgbUfloat,
+        DxgiFormat::BC6H_SF16 => wgpu::TextureFormat::Bc6hRgbFloat,
+        DxgiFormat::BC7_UNorm if hints.srgb => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        DxgiFormat::BC7_UNorm => wgpu::TextureFormat::Bc7RgbaUnorm,
+        DxgiFormat::BC7_UNorm_sRGB => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        // depth images can't be written from a buffer and are not decoded
+        _ => return None
+    })
+}
+
+fn d3d_texture_format(format: D3DFormat, hints: &DecodeHints) -> Option<wgpu::TextureFormat> {
+    Some(match format {
+        D3DFormat::L8 => wgpu::TextureFormat::R8Unorm,
+        D3DFormat::A8B8G8R8 if hints.srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        D3DFormat::A8B8G8R8 => wgpu::TextureFormat::Rgba8Unorm,
+        D3DFormat::A8R8G8B8 if hints.srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        D3DFormat::A8R8G8B8 => wgpu::TextureFormat::Bgra8Unorm,
+        D3DFormat::R16F => wgpu::TextureFormat::R16Float,
+        D3DFormat::A16B16G16R16F => wgpu::TextureFormat::Rgba16Float,
+        D3DFormat::R32F => wgpu::TextureFormat::R32Float,
+        D3DFormat::DXT1 if hints.srgb => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        D3DFormat::DXT1 => wgpu::TextureFormat::Bc1RgbaUnorm,
+        D3DFormat::DXT3 if hints.srgb => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+        D3DFormat::DXT3 => wgpu::TextureFormat::Bc2RgbaUnorm,
+        D3DFormat::DXT5 if hints.srgb => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        D3DFormat::DXT5 => wgpu::TextureFormat::Bc3RgbaUnorm,
+        _ => return None
+    })
+}
+
+fn decode_dds(bytes: &[u8], hints: &DecodeHints) -> JNIResult<DecodedTexture> {
+    let dds = Dds::read(bytes)
+        .map_err(|err| JNIError::IllegalArgument(format!("failed to decode DDS image: {}", err)))?;
+    let format = match (&dds.header10, dds.get_d3d_format()) {
+        (Some(header10), _) => dxgi_texture_format(header10.dxgi_format, hints),
+        (None, Some(d3d_format)) => d3d_texture_format(d3d_format, hints),
+        (None, None) => None
+    }.ok_or_else(|| JNIError::IllegalArgument("unsupported DDS pixel format".to_string()))?;
+
+    let (dimension, depth_or_array_layers, cube) = if dds.header.caps2.contains(Caps2::VOLUME) {
+        (wgpu::TextureDimension::D3, dds.get_depth(), false)
+    } else {
+        let faces = match &dds.header10 {
+            Some(header10) if header10.misc_flag.contains(MiscFlag::TEXTURECUBE) => 6,
+            None if dds.header.caps2.contains(Caps2::CUBEMAP) => 6,
+            _ => 1
+        };
+        let layers = match &dds.header10 {
+            Some(header10) => header10.array_size.max(1),
+            None => 1
+        };
+        (wgpu::TextureDimension::D2, layers * faces, faces == 6)
+    };
+
+    Ok(DecodedTexture {
+        size: wgpu::Extent3d {
+            width: dds.get_width(),
+            height: dds.get_height(),
+            depth_or_array_layers
+        },
+        dimension,
+        format,
+        mip_level_count: dds.get_num_mipmap_levels().max(1),
+        cube,
+        // DDS already stores every layer with its full mip chain back to back
+        data: dds.data
+    })
+}
+
+fn ktx2_texture_format(format: ktx2::Format, hints: &DecodeHints) -> Option<wgpu::TextureFormat> {
+    Some(match format {
+        ktx2::Format::R8_UNORM => wgpu::TextureFormat::R8Unorm,
+        ktx2::Format::R8_SNORM => wgpu::TextureFormat::R8Snorm,
+        ktx2::Format::R8_UINT => wgpu::TextureFormat::R8Uint,
+        ktx2::Format::R8_SINT => wgpu::TextureFormat::R8Sint,
+        ktx2::Format::R8G8_UNORM => wgpu::TextureFormat::Rg8Unorm,
+        ktx2::Format::R8G8_SNORM => wgpu::TextureFormat::Rg8Snorm,
+        ktx2::Format::R8G8_UINT => wgpu::TextureFormat::Rg8Uint,
+        ktx2::Format::R8G8_SINT => wgpu::TextureFormat::Rg8Sint,
+        ktx2::Format::R8G8B8A8_UNORM if hints.srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+        ktx2::Format::R8G8B8A8_UNORM => wgpu::TextureFormat::Rgba8Unorm,
+        ktx2::Format::R8G8B8A8_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        ktx2::Format::R8G8B8A8_SNORM => wgpu::TextureFormat::Rgba8Snorm,
+        ktx2::Format::R8G8B8A8_UINT => wgpu::TextureFormat::Rgba8Uint,
+        ktx2::Format::R8G8B8A8_SINT => wgpu::TextureFormat::Rgba8Sint,
+        ktx2::Format::B8G8R8A8_UNORM if hints.srgb => wgpu::TextureFormat::Bgra8UnormSrgb,
+        ktx2::Format::B8G8R8A8_UNORM => wgpu::TextureFormat::Bgra8Unorm,
+        ktx2::Format::B8G8R8A8_SRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        ktx2::Format::R16_SFLOAT => wgpu::TextureFormat::R16Float,
+        ktx2::Format::R16G16B16A16_SFLOAT => wgpu::TextureFormat::Rgba16Float,
+        ktx2::Format::R32_SFLOAT => wgpu::TextureFormat::R32Float,
+        ktx2::Format::B10G11R11_UFLOAT_PACK32 => wgpu::TextureFormat::Rg11b10Float,
+        ktx2::Format::BC1_RGB_UNORM_BLOCK | ktx2::Format::BC1_RGBA_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        ktx2::Format::BC1_RGB_UNORM_BLOCK | ktx2::Format::BC1_RGBA_UNORM_BLOCK => wgpu::TextureFormat::Bc1RgbaUnorm,
+        ktx2::Format::BC1_RGB_SRGB_BLOCK | ktx2::Format::BC1_RGBA_SRGB_BLOCK => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+        ktx2::Format::BC2_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+        ktx2::Format::BC2_UNORM_BLOCK => wgpu::TextureFormat::Bc2RgbaUnorm,
+        ktx2::Format::BC2_SRGB_BLOCK => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+        ktx2::Format::BC3_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        ktx2::Format::BC3_UNORM_BLOCK => wgpu::TextureFormat::Bc3RgbaUnorm,
+        ktx2::Format::BC3_SRGB_BLOCK => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+        ktx2::Format::BC4_UNORM_BLOCK => wgpu::TextureFormat::Bc4RUnorm,
+        ktx2::Format::BC4_SNORM_BLOCK => wgpu::TextureFormat::Bc4RSnorm,
+        ktx2::Format::BC5_UNORM_BLOCK => wgpu::TextureFormat::Bc5RgUnorm,
+        ktx2::Format::BC5_SNORM_BLOCK => wgpu::TextureFormat::Bc5RgSnorm,
+        ktx2::Format::BC6H_UFLOAT_BLOCK => wgpu::TextureFormat::Bc6hRgbUfloat,
+        ktx2::Format::BC6H_SFLOAT_BLOCK => wgpu::TextureFormat::Bc6hRgbFloat,
+        ktx2::Format::BC7_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        ktx2::Format::BC7_UNORM_BLOCK => wgpu::TextureFormat::Bc7RgbaUnorm,
+        ktx2::Format::BC7_SRGB_BLOCK => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Etc2Rgb8UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8_UNORM_BLOCK => wgpu::TextureFormat::Etc2Rgb8Unorm,
+        ktx2::Format::ETC2_R8G8B8_SRGB_BLOCK => wgpu::TextureFormat::Etc2Rgb8UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A1_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Etc2Rgb8A1UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A1_UNORM_BLOCK => wgpu::TextureFormat::Etc2Rgb8A1Unorm,
+        ktx2::Format::ETC2_R8G8B8A1_SRGB_BLOCK => wgpu::TextureFormat::Etc2Rgb8A1UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK if hints.srgb => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+        ktx2::Format::ETC2_R8G8B8A8_UNORM_BLOCK => wgpu::TextureFormat::Etc2Rgba8Unorm,
+        ktx2::Format::ETC2_R8G8B8A8_SRGB_BLOCK => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+        ktx2::Format::EAC_R11_UNORM_BLOCK => wgpu::TextureFormat::EacR11Unorm,
+        ktx2::Format::EAC_R11_SNORM_BLOCK => wgpu::TextureFormat::EacR11Snorm,
+        ktx2::Format::EAC_R11G11_UNORM_BLOCK => wgpu::TextureFormat::EacRg11Unorm,
+        ktx2::Format::EAC_R11G11_SNORM_BLOCK => wgpu::TextureFormat::EacRg11Snorm,
+        // depth images can't be written from a buffer and are not decoded
+        _ => return None
+    })
+}
+
+fn decode_ktx2(bytes: &[u8], hints: &DecodeHints) -> JNIResult<DecodedTexture> {
+    let reader = ktx2::Reader::new(bytes)
+        .map_err(|err| JNIError::IllegalArgument(format!("failed to decode KTX2 image: {:?}", err)))?;
+    let header = reader.header();
+    if header.supercompression_scheme.is_some() {
+        return Err(JNIError::IllegalArgument("supercompressed KTX2 images are not supported".to_string()));
+    }
+    let format = header.format
+        .and_then(|format| ktx2_texture_format(format, hints))
+        .ok_or_else(|| JNIError::IllegalArgument(format!("unsupported KTX2 format {:?}", header.format)))?;
+
+    let (dimension, depth_or_array_layers) = if header.pixel_depth > 0 {
+        (wgpu::TextureDimension::D3, header.pixel_depth)
+    } else {
+        (wgpu::TextureDimension::D2, header.layer_count.max(1) * header.face_count)
+    };
+    let size = wgpu::Extent3d {
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        depth_or_array_layers
+    };
+    let levels: Vec<&[u8]> = reader.levels().collect();
+
+    // KTX2 stores every layer of a mip level together, reorder them so each layer is followed by its mip chain
+    let data = match dimension {
+        wgpu::TextureDimension::D3 => levels.concat(),
+        _ => {
+            let mut data = Vec::with_capacity(levels.iter().map(|level| level.len()).sum());
+            for layer in 0..depth_or_array_layers as usize {
+                for level in levels.iter() {
+                    let layer_size = level.len() / depth_or_array_layers as usize;
+                    data.extend_from_slice(&level[layer * layer_size..(layer + 1) * layer_size]);
+                }
+            }
+            data
+        }
+    };
+
+    Ok(DecodedTexture {
+        size,
+        dimension,
+        format,
+        mip_level_count: levels.len().max(1) as u32,
+        cube: header.pixel_depth == 0 && header.face_count == 6,
+        data
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tga_header(image_type: u8, pixel_depth: u8) -> Vec<u8> {
+        let mut header = vec![0; TGA_HEADER_SIZE];
+        header[2] = image_type;
+        header[12] = 4;
+        header[14] = 2;
+        header[16] = pixel_depth;
+        header
+    }
+
+    fn data_size(format: wgpu::TextureFormat, dimension: wgpu::TextureDimension, size: [u32; 3], mip_level_count: u32) -> JNIResult<usize> {
+        texture_data_size(format, dimension, wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: size[2]
+        }, mip_level_count)
+    }
+
+    #[test]
+    fn data_size_covers_every_mip() {
+        let d2 = wgpu::TextureDimension::D2;
+        assert_eq!(data_size(wgpu::TextureFormat::Rgba8Unorm, d2, [4, 4, 1], 1).unwrap(), 64);
+        assert_eq!(data_size(wgpu::TextureFormat::Rgba8Unorm, d2, [4, 4, 1], 3).unwrap(), 64 + 16 + 4);
+        assert_eq!(data_size(wgpu::TextureFormat::R16Float, d2, [3, 1, 1], 2).unwrap(), 6 + 2);
+    }
+
+    #[test]
+    fn data_size_counts_layers_and_depth() {
+        assert_eq!(data_size(wgpu::TextureFormat::Rgba8Unorm, wgpu::TextureDimension::D2, [2, 2, 2], 2).unwrap(), (16 + 4) * 2);
+        assert_eq!(data_size(wgpu::TextureFormat::Rgba8Unorm, wgpu::TextureDimension::D3, [4, 4, 4], 2).unwrap(), 256 + 32);
+    }
+
+    #[test]
+    fn data_size_rounds_up_to_whole_blocks() {
+        let d2 = wgpu::TextureDimension::D2;
+        assert_eq!(data_size(wgpu::TextureFormat::Bc1RgbaUnorm, d2, [5, 5, 1], 1).unwrap(), 4 * 8);
+        // the 2x2 and 1x1 mips still take up a whole block
+        assert_eq!(data_size(wgpu::TextureFormat::Bc7RgbaUnorm, d2, [4, 4, 1], 3).unwrap(), 3 * 16);
+    }
+
+    #[test]
+    fn data_size_rejects_formats_without_a_layout() {
+        assert!(data_size(wgpu::TextureFormat::Depth24Plus, wgpu::TextureDimension::D2, [4, 4, 1], 1).is_err());
+        assert!(data_size(wgpu::TextureFormat::Depth16Unorm, wgpu::TextureDimension::D2, [4, 4, 1], 1).is_err());
+        assert!(data_size(wgpu::TextureFormat::Stencil8, wgpu::TextureDimension::D2, [4, 4, 1], 1).is_err());
+    }
+
+    #[test]
+    fn block_info_matches_wgpu() {
+        let formats = [
+            wgpu::TextureFormat::R8Unorm,
+            wgpu::TextureFormat::Rg8Snorm,
+            wgpu::TextureFormat::R16Float,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            wgpu::TextureFormat::Bgra8Unorm,
+            wgpu::TextureFormat::Rg11b10Float,
+            wgpu::TextureFormat::R32Float,
+            wgpu::TextureFormat::Rgba16Float,
+            wgpu::TextureFormat::Rgba32Float,
+            wgpu::TextureFormat::Rg16Float,
+            wgpu::TextureFormat::Rgb10a2Unorm,
+            wgpu::TextureFormat::Rg32Float,
+            wgpu::TextureFormat::Rgba32Uint,
+            wgpu::TextureFormat::Bc1RgbaUnorm,
+            wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            wgpu::TextureFormat::Bc4RUnorm,
+            wgpu::TextureFormat::Bc5RgUnorm,
+            wgpu::TextureFormat::Bc6hRgbUfloat,
+            wgpu::TextureFormat::Bc7RgbaUnorm,
+            wgpu::TextureFormat::Etc2Rgb8Unorm,
+            wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+            wgpu::TextureFormat::EacR11Unorm,
+            wgpu::TextureFormat::EacRg11Snorm,
+        ];
+        for format in formats {
+            assert_eq!(format.bit_size_block(), format.block_size(None).unwrap() * 8, "{:?}", format);
+            assert_eq!(format.block_size_texels(), format.block_dimensions(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn detects_containers_by_magic() {
+        assert_eq!(detect_container(&KTX2_MAGIC).unwrap(), EncodedContainer::Ktx2);
+        assert_eq!(detect_container(b"DDS \0\0\0\0").unwrap(), EncodedContainer::Dds);
+        assert_eq!(detect_container(b"\x89PNG\r\n\x1a\n").unwrap(), EncodedContainer::Png);
+        assert_eq!(detect_container(&[0xFF, 0xD8, 0xFF, 0xE0]).unwrap(), EncodedContainer::Jpeg);
+    }
+
+    #[test]
+    fn detects_tga_by_header() {
+        assert_eq!(detect_container(&tga_header(2, 32)).unwrap(), EncodedContainer::Tga);
+        assert_eq!(detect_container(&tga_header(11, 8)).unwrap(), EncodedContainer::Tga);
+    }
+
+    #[test]
+    fn rejects_unknown_containers() {
+        assert!(detect_container(b"").is_err());
+        assert!(detect_container(b"definitely not an image").is_err());
+        assert!(detect_container(&tga_header(2, 7)).is_err());
+        assert!(detect_container(&tga_header(4, 32)).is_err());
+
+        let mut color_mapped_without_map = tga_header(1, 8);
+        color_mapped_without_map[1] = 0;
+        assert!(detect_container(&color_mapped_without_map).is_err());
+    }
+
+    #[test]
+    fn unknown_containers_fail_with_a_container_error() {
+        let hints = DecodeHints { srgb: false };
+        match decode_texture(&[0x42; 64], &hints) {
+            Err(JNIError::IllegalArgument(message)) => assert_eq!(message, "unknown image container"),
+            _ => panic!("garbage decoded")
+        }
+    }
+}
@@ -1,31 +1,388 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use glam::u32;
 
 
-use crate::java_util::{arc_dispose_handle, arc_from_handle, arc_to_handle, JavaHandle, set_joml_vector2f};
+use crate::java_util::{arc_dispose_handle, arc_from_handle, arc_to_handle, JavaHandle, JNIError, JNIResult};
+use crate::resource::gpu_memory::{GpuMemoryTracker, MemoryCategory, TrackedMemory};
 
 pub struct TextureResource {
     pub texture: wgpu::Texture,
+    // the layers are the faces of one or more cubes
+    cube: bool,
+    _memory: TrackedMemory,
+}
+
+// a rectangle of texels inside a single layer and mip level of a texture.
+// for 3D textures the layer selects the depth slice.
+#[derive(Clone, Copy)]
+pub struct TextureRegion {
+    pub x: u32,
+    pub y: u32,
+    pub layer: u32,
+    pub mip: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// how the rows of a region are laid out in a buffer
+pub struct RegionLayout {
+    // bytes of texel data in a row of blocks
+    pub row_bytes: u32,
+    // bytes between the start of two rows
+    pub bytes_per_row: u32,
+    pub rows: u32,
+    // bytes the region takes up, the last row is not padded
+    pub required: usize,
+}
+
+// a pending copy of a texture region into a mappable buffer
+pub struct TextureReadback {
+    buffer: wgpu::Buffer,
+    row_bytes: u32,
+    padded_row_bytes: u32,
+    rows: u32,
+    status: Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>,
+    _memory: TrackedMemory,
+}
+
+impl TextureReadback {
+    // returns the tightly packed texel data once the buffer is mapped, the device has to be polled for this to progress
+    pub fn try_resolve(&self) -> Option<JNIResult<Vec<u8>>> {
+        let status = self.status.lock().expect("failed to lock readback status").take()?;
+        if let Err(err) = status {
+            return Some(Err(JNIError::Generic(format!("failed to map readback buffer: {}", err))));
+        }
+        let mut result = Vec::with_capacity((self.row_bytes * self.rows) as usize);
+        {
+            let mapped = self.buffer.slice(..).get_mapped_range();
+            for row in mapped.chunks(self.padded_row_bytes as usize).take(self.rows as usize) {
+                result.extend_from_slice(&row[..self.row_bytes as usize]);
+            }
+        }
+        self.buffer.unmap();
+        Some(Ok(result))
+    }
 }
 
 pub trait TextureFormatExt {
+    // size of a single texel block in bits, 0 if the format can't be copied to or from a buffer
     fn bit_size_block(&self) -> u32;
+    // width and height of a texel block in texels
+    fn block_size_texels(&self) -> (u32, u32);
 }
 
 impl TextureFormatExt for wgpu::TextureFormat {
 
     fn bit_size_block(&self) -> u32 {
-       //TODO: incomplete
        match self {
             wgpu::TextureFormat::R8Unorm |
+                wgpu::TextureFormat::R8Snorm |
+                wgpu::TextureFormat::R8Uint |
                 wgpu::TextureFormat::R8Sint |
-                wgpu::TextureFormat::R8Snorm => 8,
+                wgpu::TextureFormat::Stencil8 => 8,
+            wgpu::TextureFormat::Rg8Unorm |
+                wgpu::TextureFormat::Rg8Snorm |
+                wgpu::TextureFormat::Rg8Uint |
+                wgpu::TextureFormat::Rg8Sint => 16,
+            wgpu::TextureFormat::Rg16Unorm |
+                wgpu::TextureFormat::Rg16Snorm |
+                wgpu::TextureFormat::Rg16Uint |
+                wgpu::TextureFormat::Rg16Sint |
+                wgpu::TextureFormat::Rg16Float => 32,
+            wgpu::TextureFormat::R16Unorm |
+                wgpu::TextureFormat::R16Snorm |
+                wgpu::TextureFormat::R16Uint |
+                wgpu::TextureFormat::R16Sint |
+                wgpu::TextureFormat::R16Float |
+                wgpu::TextureFormat::Depth16Unorm => 16,
             wgpu::TextureFormat::Rgba8Unorm |
+                wgpu::TextureFormat::Rgba8Snorm |
                 wgpu::TextureFormat::Rgba8Sint |
                 wgpu::TextureFormat::Rgba8Uint |
-                wgpu::TextureFormat::Rgba8UnormSrgb => 32, 
+                wgpu::TextureFormat::Rgba8UnormSrgb |
+                wgpu::TextureFormat::Bgra8Unorm |
+                wgpu::TextureFormat::Bgra8UnormSrgb => 32,
+            wgpu::TextureFormat::R32Float |
+                wgpu::TextureFormat::R32Uint |
+                wgpu::TextureFormat::R32Sint |
+                wgpu::TextureFormat::Rgb10a2Unorm |
+                wgpu::TextureFormat::Rgb9e5Ufloat |
+                wgpu::TextureFormat::Rg11b10Float => 32,
+            wgpu::TextureFormat::Rgba16Unorm |
+                wgpu::TextureFormat::Rgba16Snorm |
+                wgpu::TextureFormat::Rgba16Uint |
+                wgpu::TextureFormat::Rgba16Sint |
+                wgpu::TextureFormat::Rgba16Float |
+                wgpu::TextureFormat::Rg32Uint |
+                wgpu::TextureFormat::Rg32Sint |
+                wgpu::TextureFormat::Rg32Float => 64,
+            wgpu::TextureFormat::Rgba32Uint |
+                wgpu::TextureFormat::Rgba32Sint |
+                wgpu::TextureFormat::Rgba32Float => 128,
+            // compressed formats are sized per 4x4 block
+            wgpu::TextureFormat::Bc1RgbaUnorm |
+                wgpu::TextureFormat::Bc1RgbaUnormSrgb |
+                wgpu::TextureFormat::Bc4RUnorm |
+                wgpu::TextureFormat::Bc4RSnorm |
+                wgpu::TextureFormat::Etc2Rgb8Unorm |
+                wgpu::TextureFormat::Etc2Rgb8UnormSrgb |
+                wgpu::TextureFormat::Etc2Rgb8A1Unorm |
+                wgpu::TextureFormat::Etc2Rgb8A1UnormSrgb |
+                wgpu::TextureFormat::EacR11Unorm |
+                wgpu::TextureFormat::EacR11Snorm => 64,
+            wgpu::TextureFormat::Bc2RgbaUnorm |
+                wgpu::TextureFormat::Bc2RgbaUnormSrgb |
+                wgpu::TextureFormat::Bc3RgbaUnorm |
+                wgpu::TextureFormat::Bc3RgbaUnormSrgb |
+                wgpu::TextureFormat::Bc5RgUnorm |
+                wgpu::TextureFormat::Bc5RgSnorm |
+                wgpu::TextureFormat::Bc6hRgbUfloat |
+                wgpu::TextureFormat::Bc6hRgbFloat |
+                wgpu::TextureFormat::Bc7RgbaUnorm |
+                wgpu::TextureFormat::Bc7RgbaUnormSrgb |
+                wgpu::TextureFormat::Etc2Rgba8Unorm |
+                wgpu::TextureFormat::Etc2Rgba8UnormSrgb |
+                wgpu::TextureFormat::EacRg11Unorm |
+                wgpu::TextureFormat::EacRg11Snorm => 128,
+            // the layout of these is up to the driver or they can't be written from a buffer
+            wgpu::TextureFormat::Depth32Float |
+                wgpu::TextureFormat::Depth24Plus |
+                wgpu::TextureFormat::Depth24PlusStencil8 |
+                wgpu::TextureFormat::Depth32FloatStencil8 => 0,
             _ => 0
-        } 
+        }
+    }
+
+    fn block_size_texels(&self) -> (u32, u32) {
+        self.block_dimensions()
+    }
+}
+
+impl TextureResource {
+    // textures that can be rendered to are accounted as render targets
+    pub fn new(memory: &Arc<GpuMemoryTracker>, texture: wgpu::Texture, label: Option<&str>) -> Self {
+        let category = match texture.usage().contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            true => MemoryCategory::RenderTarget,
+            false => MemoryCategory::Texture
+        };
+        Self::with_category(memory, texture, category, label)
+    }
+
+    // unlabeled textures are listed by their size and format
+    pub fn with_category(memory: &Arc<GpuMemoryTracker>, texture: wgpu::Texture, category: MemoryCategory, label: Option<&str>) -> Self {
+        let label = match label {
+            Some(label) => label.to_string(),
+            None => {
+                let size = texture.size();
+                format!("texture {}x{}x{} {:?}", size.width, size.height, size.depth_or_array_layers, texture.format())
+            }
+        };
+        Self {
+            _memory: memory.track_texture(category, label, &texture),
+            cube: false,
+            texture
+        }
+    }
+
+    // every six layers form a cube, in the order +x, -x, +y, -y, +z, -z
+    pub fn with_cube_faces(mut self) -> Self {
+        self.cube = true;
+        self
+    }
+
+    pub fn is_cube(&self) -> bool {
+        self.cube
+    }
+
+    // the view dimension that covers the whole texture
+    pub fn view_dimension(&self) -> wgpu::TextureViewDimension {
+        match self.texture.dimension() {
+            wgpu::TextureDimension::D1 => wgpu::TextureViewDimension::D1,
+            wgpu::TextureDimension::D2 if self.cube && self.texture.depth_or_array_layers() > 6 => wgpu::TextureViewDimension::CubeArray,
+            wgpu::TextureDimension::D2 if self.cube => wgpu::TextureViewDimension::Cube,
+            wgpu::TextureDimension::D2 if self.texture.depth_or_array_layers() > 1 => wgpu::TextureViewDimension::D2Array,
+            wgpu::TextureDimension::D2 => wgpu::TextureViewDimension::D2,
+            wgpu::TextureDimension::D3 => wgpu::TextureViewDimension::D3,
+        }
+    }
+
+    pub fn mip_extent(&self, mip: u32) -> wgpu::Extent3d {
+        self.texture.size().mip_level_size(mip, self.texture.dimension())
+    }
+
+    // number of bytes a tightly packed row of blocks covering `width` texels takes up
+    pub fn row_bytes(&self, width: u32) -> JNIResult<u32> {
+        let format = self.texture.format();
+        let block_bytes = format.bit_size_block() / 8;
+        if block_bytes == 0 {
+            return Err(JNIError::IllegalArgument(format!("texture format {:?} does not support buffer copies", format)));
+        }
+        let (block_width, _) = format.block_size_texels();
+        Ok(width.div_ceil(block_width) * block_bytes)
+    }
+
+    pub fn validate_region(&self, region: &TextureRegion) -> JNIResult<()> {
+        if region.mip >= self.texture.mip_level_count() {
+            return Err(JNIError::IllegalArgument(format!("mip {} out of range, texture has {} mip levels", region.mip, self.texture.mip_level_count())));
+        }
+        let extent = self.mip_extent(region.mip);
+        if region.layer >= extent.depth_or_array_layers {
+            return Err(JNIError::IllegalArgument(format!("layer {} out of range, mip {} has {} layers", region.layer, region.mip, extent.depth_or_array_layers)));
+        }
+        if region.width == 0 || region.height == 0 {
+            return Err(JNIError::IllegalArgument("region must not be empty".to_string()));
+        }
+        if region.x.checked_add(region.width).is_none_or(|end| end > extent.width) ||
+            region.y.checked_add(region.height).is_none_or(|end| end > extent.height) {
+            return Err(JNIError::IllegalArgument(format!("region {}x{} at ({}, {}) exceeds mip {} size {}x{}",
+                region.width, region.height, region.x, region.y, region.mip, extent.width, extent.height)));
+        }
+        let (block_width, block_height) = self.texture.format().block_size_texels();
+        let aligned_x = region.x.is_multiple_of(block_width) && (region.width.is_multiple_of(block_width) || region.x + region.width == extent.width);
+        let aligned_y = region.y.is_multiple_of(block_height) && (region.height.is_multiple_of(block_height) || region.y + region.height == extent.height);
+        if !aligned_x || !aligned_y {
+            return Err(JNIError::IllegalArgument(format!("region is not aligned to the {}x{} block size of the format", block_width, block_height)));
+        }
+        Ok(())
+    }
+
+    // validates `region` and the size of its data, returns the layout of the rows in the data.
+    // `stride` is the number of bytes between rows in `data`, 0 means the rows are tightly packed.
+    pub fn region_layout(&self, region: &TextureRegion, data_len: usize, stride: u32) -> JNIResult<RegionLayout> {
+        self.validate_region(region)?;
+        let row_bytes = self.row_bytes(region.width)?;
+        let bytes_per_row = if stride == 0 { row_bytes } else { stride };
+        if bytes_per_row < row_bytes {
+            return Err(JNIError::IllegalArgument(format!("stride {} is smaller than a row of the region ({} bytes)", bytes_per_row, row_bytes)));
+        }
+        let (_, block_height) = self.texture.format().block_size_texels();
+        let rows = region.height.div_ceil(block_height);
+        let required = bytes_per_row as u64 * (rows - 1) as u64 + row_bytes as u64;
+        if (data_len as u64) < required {
+            return Err(JNIError::IllegalArgument(format!("buffer holds {} bytes but the region requires {}", data_len, required)));
+        }
+        Ok(RegionLayout {
+            row_bytes,
+            bytes_per_row,
+            rows,
+            required: required as usize
+        })
+    }
+
+    // writes `data` into `region`. `stride` is the number of bytes between rows in `data`,
+    // 0 means the rows are tightly packed.
+    pub fn write_region(&self, queue: &wgpu::Queue, region: &TextureRegion, data: &[u8], stride: u32) -> JNIResult<()> {
+        let RegionLayout { bytes_per_row, required, .. } = self.region_layout(region, data.len(), stride)?;
+
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: region.mip,
+                origin: wgpu::Origin3d {
+                    x: region.x,
+                    y: region.y,
+                    z: region.layer
+                },
+                aspect: wgpu::TextureAspect::All
+            },
+            &data[..required],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: region.width,
+                height: region.height,
+                depth_or_array_layers: 1
+            },
+        );
+        Ok(())
+    }
+
+    // copies `region` into a buffer that is mapped once the gpu is done with the copy.
+    // the texture has to be created with COPY_SRC usage.
+    pub fn read_region(&self, device: &wgpu::Device, queue: &wgpu::Queue, memory: &Arc<GpuMemoryTracker>, region: &TextureRegion) -> JNIResult<TextureReadback> {
+        if !self.texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            return Err(JNIError::IllegalArgument("texture was not created with COPY_SRC usage".to_string()));
+        }
+        self.validate_region(region)?;
+        let row_bytes = self.row_bytes(region.width)?;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let (_, block_height) = self.texture.format().block_size_texels();
+        let rows = region.height.div_ceil(block_height);
+
+        let buffer_size = padded_row_bytes as u64 * rows as u64;
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("texture readback"),
+            size: buffer_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("texture readback") });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: region.mip,
+                origin: wgpu::Origin3d {
+                    x: region.x,
+                    y: region.y,
+                    z: region.layer
+                },
+                aspect: wgpu::TextureAspect::All
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None
+                }
+            },
+            wgpu::Extent3d {
+                width: region.width,
+                height: region.height,
+                depth_or_array_layers: 1
+            });
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let status = Arc::new(Mutex::new(None));
+        let callback_status = status.clone();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            *callback_status.lock().expect("failed to lock readback status") = Some(result);
+        });
+        Ok(TextureReadback {
+            buffer,
+            row_bytes,
+            padded_row_bytes,
+            rows,
+            status,
+            _memory: memory.track(MemoryCategory::Buffer, "texture readback", buffer_size)
+        })
+    }
+
+    // writes tightly packed data for every layer of the first mip level
+    pub fn write_all(&self, queue: &wgpu::Queue, data: &[u8]) -> JNIResult<()> {
+        let extent = self.mip_extent(0);
+        let row_bytes = self.row_bytes(extent.width)?;
+        let (_, block_height) = self.texture.format().block_size_texels();
+        let layer_bytes = row_bytes as usize * extent.height.div_ceil(block_height) as usize;
+        let required = layer_bytes * extent.depth_or_array_layers as usize;
+        if data.len() < required {
+            return Err(JNIError::IllegalArgument(format!("buffer holds {} bytes but the texture requires {}", data.len(), required)));
+        }
+        for layer in 0..extent.depth_or_array_layers {
+            let start = layer as usize * layer_bytes;
+            self.write_region(queue, &TextureRegion {
+                x: 0,
+                y: 0,
+                layer,
+                mip: 0,
+                width: extent.width,
+                height: extent.height
+            }, &data[start..start + layer_bytes], 0)?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::java_util::JNIResult;
+use crate::resource::gpu_memory::{GpuMemoryTracker, MemoryCategory, TrackedMemory};
+use crate::resource::texture_resource::{RegionLayout, TextureRegion, TextureResource};
+
+const STAGING_CHUNK_SIZE: u64 = 1 << 20;
+const DEFAULT_FRAME_BUDGET: u64 = 16 << 20;
+
+struct StagingChunk {
+    buffer: Arc<wgpu::Buffer>,
+    offset: u64,
+    _memory: TrackedMemory,
+}
+
+// a ring of mapped staging buffers. chunks are written while mapped, unmapped before the frame is submitted
+// and mapped again once the gpu is done copying out of them.
+struct StagingBelt {
+    active: Vec<StagingChunk>,
+    closed: Vec<StagingChunk>,
+    free: Arc<Mutex<Vec<StagingChunk>>>,
+    memory: Arc<GpuMemoryTracker>,
+}
+
+impl StagingBelt {
+    // returns `size` bytes of mapped staging memory at an offset aligned to `alignment`
+    fn allocate(&mut self, device: &wgpu::Device, size: u64, alignment: u64) -> (Arc<wgpu::Buffer>, u64) {
+        let fits = |chunk: &StagingChunk| chunk.offset.next_multiple_of(alignment) + size <= chunk.buffer.size();
+        let index = match self.active.iter().position(fits) {
+            Some(index) => index,
+            None => {
+                let mut free = self.free.lock().expect("failed to lock staging chunks");
+                let chunk = match free.iter().position(fits) {
+                    Some(index) => free.swap_remove(index),
+                    None => {
+                        let chunk_size = size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT).max(STAGING_CHUNK_SIZE);
+                        StagingChunk {
+                            buffer: Arc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                                label: Some("upload staging"),
+                                size: chunk_size,
+                                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                                mapped_at_creation: true
+                            })),
+                            offset: 0,
+                            _memory: self.memory.track(MemoryCategory::Buffer, "upload staging", chunk_size)
+                        }
+                    }
+                };
+                self.active.push(chunk);
+                self.active.len() - 1
+            }
+        };
+        let chunk = &mut self.active[index];
+        let offset = chunk.offset.next_multiple_of(alignment);
+        chunk.offset = offset + size;
+        (chunk.buffer.clone(), offset)
+    }
+
+    // unmaps every chunk written this frame, has to happen before the copies are submitted
+    fn finish(&mut self) {
+        for chunk in self.active.drain(..) {
+            chunk.buffer.unmap();
+            self.closed.push(chunk);
+        }
+    }
+
+    // maps the submitted chunks again so they can be reused once the gpu is done with them
+    fn recall(&mut self) {
+        for mut chunk in self.closed.drain(..) {
+            chunk.offset = 0;
+            let buffer = chunk.buffer.clone();
+            let free = self.free.clone();
+            buffer.slice(..).map_async(wgpu::MapMode::Write, move |result| {
+                if result.is_ok() {
+                    free.lock().expect("failed to lock staging chunks").push(chunk);
+                }
+            });
+        }
+    }
+}
+
+struct UploadCopy {
+    texture: Arc<TextureResource>,
+    region: TextureRegion,
+    row_bytes: u32,
+    rows: u32,
+    // the tightly packed rows of the region
+    data: Vec<u8>,
+}
+
+impl UploadCopy {
+    fn staging_size(&self) -> u64 {
+        self.row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) as u64 * (self.rows - 1) as u64 + self.row_bytes as u64
+    }
+}
+
+struct PendingUpload {
+    id: u64,
+    copies: Vec<UploadCopy>,
+}
+
+// batches texture writes and records them into the frame encoder, at most `frame_budget` bytes per frame.
+// uploads are completed in the order they were queued.
+//
+// wgpu runs `queue.write_texture` and `queue.write_buffer` before every command of the next submit, so
+// those land before the copies flushed from here. that is what per frame data like ui geometry and
+// uniforms needs, they are consumed by the same frame. a direct write to a texture that still has
+// queued uploads would be overwritten by them though, so those are queued behind them instead.
+pub struct UploadQueue {
+    pending: VecDeque<PendingUpload>,
+    belt: StagingBelt,
+    next_id: u64,
+    frame_budget: u64,
+    // uploads recorded into the current frame
+    flushed: Vec<u64>,
+    // uploads the gpu has finished
+    completed: Arc<Mutex<Vec<u64>>>,
+}
+
+impl UploadQueue {
+    pub fn new(memory: &Arc<GpuMemoryTracker>) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            belt: StagingBelt {
+                active: Vec::new(),
+                closed: Vec::new(),
+                free: Arc::new(Mutex::new(Vec::new())),
+                memory: memory.clone()
+            },
+            next_id: 0,
+            frame_budget: DEFAULT_FRAME_BUDGET,
+            flushed: Vec::new(),
+            completed: Arc::new(Mutex::new(Vec::new()))
+        }
+    }
+
+    // the first upload of a frame is always flushed, even if it is larger than the budget
+    pub fn set_frame_budget(&mut self, bytes: u64) {
+        self.frame_budget = bytes;
+    }
+
+    fn push(&mut self, copies: Vec<UploadCopy>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.push_back(PendingUpload { id, copies });
+        id
+    }
+
+    fn texture_copy(texture: &Arc<TextureResource>, region: TextureRegion, layout: &RegionLayout, data: &[u8]) -> UploadCopy {
+        let mut packed = Vec::with_capacity(layout.row_bytes as usize * layout.rows as usize);
+        for row in 0..layout.rows as usize {
+            let start = row * layout.bytes_per_row as usize;
+            packed.extend_from_slice(&data[start..start + layout.row_bytes as usize]);
+        }
+        UploadCopy {
+            texture: texture.clone(),
+            region,
+            row_bytes: layout.row_bytes,
+            rows: layout.rows,
+            data: packed
+        }
+    }
+
+    // copies `data` right away, so the caller can reuse it as soon as this returns
+    pub fn write_texture(&mut self, texture: &Arc<TextureResource>, region: &TextureRegion, data: &[u8], stride: u32) -> JNIResult<u64> {
+        let layout = texture.region_layout(region, data.len(), stride)?;
+        let copy = Self::texture_copy(texture, *region, &layout, data);
+        Ok(self.push(vec![copy]))
+    }
+
+    // tightly packed data for every layer of the first mip level
+    pub fn write_texture_all(&mut self, texture: &Arc<TextureResource>, data: &[u8]) -> JNIResult<u64> {
+        let extent = texture.mip_extent(0);
+        let mut copies = Vec::with_capacity(extent.depth_or_array_layers as usize);
+        let mut start = 0;
+        for layer in 0..extent.depth_or_array_layers {
+            let region = TextureRegion {
+                x: 0,
+                y: 0,
+                layer,
+                mip: 0,
+                width: extent.width,
+                height: extent.height
+            };
+            let layout = texture.region_layout(&region, data.len() - start, 0)?;
+            copies.push(Self::texture_copy(texture, region, &layout, &data[start..]));
+            start += layout.required;
+        }
+        Ok(self.push(copies))
+    }
+
+    // whether uploads to the texture are still waiting to be flushed
+    pub fn has_pending(&self, texture: &Arc<TextureResource>) -> bool {
+        self.pending.iter()
+            .flat_map(|upload| upload.copies.iter())
+            .any(|copy| Arc::ptr_eq(&copy.texture, texture))
+    }
+
+    // records the copies of as many pending uploads as the frame budget allows
+    pub fn flush(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        let mut spent = 0;
+        while let Some(upload) = self.pending.front() {
+            let size: u64 = upload.copies.iter().map(|copy| copy.staging_size()).sum();
+            if spent > 0 && spent + size > self.frame_budget {
+                break;
+            }
+            spent += size;
+            let upload = self.pending.pop_front().unwrap();
+            for copy in upload.copies.iter() {
+                self.record_copy(device, encoder, copy);
+            }
+            self.flushed.push(upload.id);
+        }
+        self.belt.finish();
+    }
+
+    fn record_copy(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder, copy: &UploadCopy) {
+        let padded_row_bytes = copy.row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let (staging, offset) = self.belt.allocate(device, copy.staging_size(), wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64);
+        {
+            let mut mapped = staging.slice(offset..offset + copy.staging_size()).get_mapped_range_mut();
+            for (row, data) in copy.data.chunks(copy.row_bytes as usize).enumerate() {
+                let start = row * padded_row_bytes as usize;
+                mapped[start..start + data.len()].copy_from_slice(data);
+            }
+        }
+        encoder.copy_buffer_to_texture(
+            wgpu::ImageCopyBuffer {
+                buffer: &staging,
+                layout: wgpu::ImageDataLayout {
+                    offset,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(copy.rows)
+                }
+            },
+            wgpu::ImageCopyTexture {
+                texture: &copy.texture.texture,
+                mip_level: copy.region.mip,
+                origin: wgpu::Origin3d {
+                    x: copy.region.x,
+                    y: copy.region.y,
+                    z: copy.region.layer
+                },
+                aspect: wgpu::TextureAspect::All
+            },
+            wgpu::Extent3d {
+                width: copy.region.width,
+                height: copy.region.height,
+                depth_or_array_layers: 1
+            });
+    }
+
+    // call once the frame encoder has been submitted
+    pub fn after_submit(&mut self, queue: &wgpu::Queue) {
+        self.belt.recall();
+        if self.flushed.is_empty() {
+            return;
+        }
+        let flushed = std::mem::take(&mut self.flushed);
+        let completed = self.completed.clone();
+        queue.on_submitted_work_done(move || {
+            completed.lock().expect("failed to lock completed uploads").extend(flushed);
+        });
+    }
+
+    // uploads the gpu has finished since the last call, the device has to be polled for this to progress
+    pub fn take_completed(&self) -> Vec<u64> {
+        std::mem::take(&mut self.completed.lock().expect("failed to lock completed uploads"))
+    }
+}
@@ -0,0 +1,249 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use bytemuck::{Pod, Zeroable};
+
+use crate::resource::texture_resource::TextureResource;
+use crate::resource::gpu_memory::{GpuMemoryTracker, MemoryCategory, TrackedMemory};
+use crate::java_util::{JNIError, JNIResult};
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SkyboxUniform {
+    // columns of a mat3x3, padded to 16 bytes each
+    rotation: [[f32; 4]; 3],
+    tint: [f32; 4],
+    view_scale: [f32; 2],
+    layer: i32,
+    _padding: u32,
+}
+
+struct SkyboxDraw {
+    view: wgpu::TextureView,
+    dimension: wgpu::TextureViewDimension,
+    uniform: SkyboxUniform,
+    // keeps the cubemap alive until the frame is dispatched
+    _cubemap: Arc<TextureResource>,
+}
+
+// draws a cubemap behind everything else, before the ui is drawn on top
+pub struct SkyboxPass {
+    surface_format: wgpu::TextureFormat,
+    shader: wgpu::ShaderModule,
+    cube_bind_group_layout: wgpu::BindGroupLayout,
+    cube_array_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layouts: HashMap<wgpu::TextureViewDimension, wgpu::PipelineLayout>,
+    pipelines: HashMap<wgpu::TextureViewDimension, wgpu::RenderPipeline>,
+    uniform: wgpu::Buffer,
+    _uniform_memory: TrackedMemory,
+    sampler: wgpu::Sampler,
+    draw: Option<SkyboxDraw>,
+}
+
+fn skybox_bind_group_layout(device: &wgpu::Device, dimension: wgpu::TextureViewDimension) -> wgpu::BindGroupLayout {
+    let binding = match dimension {
+        wgpu::TextureViewDimension::CubeArray => 3,
+        _ => 2
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("skybox bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(mem::size_of::<SkyboxUniform>() as u64)
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    multisampled: false,
+                    view_dimension: dimension,
+                },
+                count: None,
+            }
+        ]
+    })
+}
+
+impl SkyboxPass {
+    pub fn new(device: &wgpu::Device, surface: &wgpu::SurfaceConfiguration, memory: &Arc<GpuMemoryTracker>) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("skybox shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("skybox.wgsl"))),
+        });
+        let uniform_size = mem::size_of::<SkyboxUniform>() as u64;
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("skybox uniform"),
+            size: uniform_size,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        Self {
+            surface_format: surface.format,
+            shader,
+            cube_bind_group_layout: skybox_bind_group_layout(device, wgpu::TextureViewDimension::Cube),
+            cube_array_bind_group_layout: skybox_bind_group_layout(device, wgpu::TextureViewDimension::CubeArray),
+            pipeline_layouts: HashMap::new(),
+            pipelines: HashMap::new(),
+            uniform,
+            _uniform_memory: memory.track(MemoryCategory::Buffer, "skybox uniform", uniform_size),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("skybox sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            draw: None
+        }
+    }
+
+    pub fn cmd_prepare(&mut self) {
+        self.draw = None;
+    }
+
+    // `rotation` turns camera space into world space, the camera looks down -z with y up.
+    // `fov_y` is the vertical field of view in radians and `cube` selects the cube of a cube array.
+    pub fn cmd_draw(&mut self, cubemap: &Arc<TextureResource>, cube: u32, rotation: glam::Quat, fov_y: f32, tint_color: u32) -> JNIResult<()> {
+        if !cubemap.is_cube() {
+            return Err(JNIError::IllegalArgument("the skybox needs a texture created with the cube dimension".to_string()));
+        }
+        let format = cubemap.texture.format();
+        if format.sample_type(None) != Some(wgpu::TextureSampleType::Float { filterable: true }) {
+            return Err(JNIError::IllegalArgument(format!("cubemaps of format {:?} can't be filtered", format)));
+        }
+        let cubes = cubemap.texture.depth_or_array_layers() / 6;
+        if cube >= cubes {
+            return Err(JNIError::IllegalArgument(format!("cube {} out of range, texture has {} cubes", cube, cubes)));
+        }
+        if !(fov_y > 0.0 && fov_y < std::f32::consts::PI) {
+            return Err(JNIError::IllegalArgument(format!("field of view {} has to be between 0 and pi", fov_y)));
+        }
+        let dimension = cubemap.view_dimension();
+        let rotation = glam::Mat3::from_quat(rotation.normalize());
+        let c: [u8; 4] = bytemuck::cast(tint_color);
+        self.draw = Some(SkyboxDraw {
+            view: cubemap.texture.create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(dimension),
+                ..Default::default()
+            }),
+            dimension,
+            uniform: SkyboxUniform {
+                rotation: [rotation.x_axis.extend(0.0).into(), rotation.y_axis.extend(0.0).into(), rotation.z_axis.extend(0.0).into()],
+                tint: [c[3] as f32 / 255.0, c[2] as f32 / 255.0, c[1] as f32 / 255.0, c[0] as f32 / 255.0],
+                // the horizontal scale is filled in once the surface size is known
+                view_scale: [0.0, (fov_y * 0.5).tan()],
+                layer: cube as i32,
+                _padding: 0
+            },
+            _cubemap: cubemap.clone()
+        });
+        Ok(())
+    }
+
+    fn prepare_pipeline(&mut self, device: &wgpu::Device, dimension: wgpu::TextureViewDimension) {
+        if self.pipelines.contains_key(&dimension) {
+            return;
+        }
+        let (bind_group_layout, fragment_entry) = match dimension {
+            wgpu::TextureViewDimension::CubeArray => (&self.cube_array_bind_group_layout, "fs_cube_array"),
+            _ => (&self.cube_bind_group_layout, "fs_cube")
+        };
+        let layout = self.pipeline_layouts.entry(dimension).or_insert_with(|| device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("skybox pipeline layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        }));
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("skybox pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: &self.shader,
+                entry_point: "vs_main",
+                buffers: &[]
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &self.shader,
+                entry_point: fragment_entry,
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: self.surface_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            depth_stencil: None
+        });
+        self.pipelines.insert(dimension, pipeline);
+    }
+
+    // clears the frame and draws the skybox, does nothing if no skybox was requested this frame
+    pub fn cmd_dispatch(
+        &mut self,
+        view: &wgpu::TextureView,
+        size: wgpu::Extent3d,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder
+    ) {
+        let Some(mut draw) = self.draw.take() else { return };
+        draw.uniform.view_scale[0] = draw.uniform.view_scale[1] * size.width as f32 / size.height.max(1) as f32;
+        queue.write_buffer(&self.uniform, 0, bytemuck::bytes_of(&draw.uniform));
+        self.prepare_pipeline(device, draw.dimension);
+
+        let (bind_group_layout, binding) = match draw.dimension {
+            wgpu::TextureViewDimension::CubeArray => (&self.cube_array_bind_group_layout, 3),
+            _ => (&self.cube_bind_group_layout, 2)
+        };
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("skybox bind group"),
+            layout: bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniform.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler)
+                },
+                wgpu::BindGroupEntry {
+                    binding,
+                    resource: wgpu::BindingResource::TextureView(&draw.view)
+                }
+            ]
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("skybox pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true
+                }
+            })],
+            depth_stencil_attachment: None
+        });
+        pass.set_pipeline(&self.pipelines[&draw.dimension]);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,47 @@
+struct SkyboxUniform {
+    // camera to world rotation
+    rotation: mat3x3<f32>,
+    tint: vec4<f32>,
+    // tangent of half the horizontal and vertical field of view
+    view_scale: vec2<f32>,
+    // cube sampled from a cube array
+    layer: i32,
+}
+
+@group(0) @binding(0)
+var<uniform> u_sky: SkyboxUniform;
+@group(0) @binding(1)
+var u_sampler: sampler;
+@group(0) @binding(2)
+var u_cube: texture_cube<f32>;
+@group(0) @binding(3)
+var u_cube_array: texture_cube_array<f32>;
+
+struct VertexOutput {
+    @builtin(position) vertex: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+// a single triangle covering the screen
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let ndc = vec2<f32>(f32(in_vertex_index & 1u) * 4.0 - 1.0, f32(in_vertex_index >> 1u) * 4.0 - 1.0);
+    var result: VertexOutput;
+    result.vertex = vec4<f32>(ndc, 1.0, 1.0);
+    result.ndc = ndc;
+    return result;
+}
+
+fn view_direction(ndc: vec2<f32>) -> vec3<f32> {
+    return u_sky.rotation * normalize(vec3<f32>(ndc * u_sky.view_scale, -1.0));
+}
+
+@fragment
+fn fs_cube(@location(0) ndc: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(u_cube, u_sampler, view_direction(ndc)) * u_sky.tint;
+}
+
+@fragment
+fn fs_cube_array(@location(0) ndc: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(u_cube_array, u_sampler, view_direction(ndc), u_sky.layer) * u_sky.tint;
+}
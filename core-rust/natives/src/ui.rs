@@ -4,19 +4,39 @@ use crate::math::rect::Rect;
 use jni::sys::jlong;
 use std::{borrow::Cow, mem};
 use std::cell::RefCell;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
 use bytemuck::{Pod, Zeroable};
-use std::rc::Rc;
+use glam::{Affine2, Vec2};
 use std::default::Default;
+use wgpu::util::DeviceExt;
 
 use crate::resource::texture_resource::TextureResource;
+use crate::resource::texture_atlas::AtlasSubTexture;
+use crate::resource::sampler_resource::SamplerResource;
+use crate::resource::font_resource::FontResource;
+use crate::resource::glyph_cache::GlyphCache;
+use crate::resource::text_layout::{layout_text, TextLayout, TextLayoutDesc};
+use crate::resource::gpu_memory::{GpuMemoryTracker, MemoryCategory, TrackedMemory};
+use crate::java_util::{JNIError, JNIResult};
 
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct PositionTexCoord {
     pos: [f32; 2],
     uv: [f32; 2],
-    color: u32 
+    color: u32,
+    // slot in the texture binding array, ignored when textures are bound one at a time
+    texture: u32,
+}
+
+// per draw data, selected through the instance index
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct UIInstance {
+    offset: [f32; 2],
+    // added to the texture slot of the vertices, replayed lists don't know their slot when they are recorded
+    texture: u32,
 }
 
 #[repr(C)]
@@ -34,76 +54,527 @@ pub struct GuiTexturePerFrameUniform {
     pub view_transform: [[f32; 4]; 4],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct GuiTexturePushConstants {
+    pub texture_style: u32,
+    pub slice_depth: f32,
+}
+
+const QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+// tint colors come in RGBA order from java, vertices store them byte by byte
+fn vertex_color(tint_color: u32) -> u32 {
+    let c: [u8; 4] = bytemuck::cast(tint_color);
+    bytemuck::cast([c[3], c[2], c[1], c[0]])
+}
+
+// how an image is cut into corners, edges and a center for a frame of any size
+pub struct NineSlice {
+    // left, top, right and bottom border in texels
+    pub insets: [f32; 4],
+    // size of a texel of the border on screen
+    pub scale: f32,
+    // repeat the edges and the center instead of stretching them
+    pub tile: bool,
+}
+
+// the spans of the three slices along one axis as (position, uv) pairs, the middle slice can consist of several tiles
+fn nine_slice_axis(pos: [f32; 2], uv: [f32; 2], insets: [f32; 2], texture_size: f32, slice: &NineSlice) -> [Vec<([f32; 2], [f32; 2])>; 3] {
+    // more tiles than this stretch instead
+    const MAX_TILES: f32 = 256.0;
+    let length = pos[1] - pos[0];
+    let mut border = [insets[0] * slice.scale, insets[1] * slice.scale];
+    // borders that don't fit are shrunk evenly
+    if border[0] + border[1] > length {
+        let shrink = if border[0] + border[1] > 0.0 { length.max(0.0) / (border[0] + border[1]) } else { 0.0 };
+        border = [border[0] * shrink, border[1] * shrink];
+    }
+    // uv may run backwards for flipped images
+    let direction = if uv[1] < uv[0] { -1.0 } else { 1.0 };
+    let positions = [pos[0], pos[0] + border[0], pos[1] - border[1], pos[1]];
+    let uvs = [uv[0], uv[0] + direction * insets[0] / texture_size, uv[1] - direction * insets[1] / texture_size, uv[1]];
+    let span = |index: usize| ([positions[index], positions[index + 1]], [uvs[index], uvs[index + 1]]);
+    let empty = |index: usize| positions[index + 1] - positions[index] <= 0.0;
+
+    let middle = if empty(1) {
+        Vec::new()
+    } else {
+        let tile = (uvs[2] - uvs[1]).abs() * texture_size * slice.scale;
+        let middle_length = positions[2] - positions[1];
+        if slice.tile && tile > 0.0 && middle_length / tile <= MAX_TILES {
+            let count = (middle_length / tile).ceil() as usize;
+            (0..count).map(|index| {
+                let start = positions[1] + index as f32 * tile;
+                let end = (start + tile).min(positions[2]);
+                // the last tile is cut off, not squeezed
+                let fraction = (end - start) / tile;
+                ([start, end], [uvs[1], uvs[1] + (uvs[2] - uvs[1]) * fraction])
+            }).collect()
+        } else {
+            vec![span(1)]
+        }
+    };
+    [
+        if empty(0) { Vec::new() } else { vec![span(0)] },
+        middle,
+        if empty(2) { Vec::new() } else { vec![span(2)] }
+    ]
+}
+
+// splits a half extent into the solid part and the outer edge of a one pixel wide fade, which ends on the pixel
+// centers next to the edge. shapes thinner than a pixel fade out instead of shrinking further.
+fn feather(half: f32) -> (f32, f32, f32) {
+    ((half - 0.5).max(0.0), half + 0.5, (half * 2.0).min(1.0))
+}
+
+fn scale_alpha(color: u32, scale: f32) -> u32 {
+    let mut c: [u8; 4] = bytemuck::cast(color);
+    c[3] = (c[3] as f32 * scale).round() as u8;
+    bytemuck::cast(c)
+}
+
 const VERTEX_BUFFER_INITIAL_SIZE: u64= 1024;
 const INDEX_BUFFER_INITIAL_SIZE: u64 = 1024;
+const INSTANCE_BUFFER_INITIAL_SIZE: u64 = 256;
 const RESERVED_TEXTURE_VIEW: usize = 32;
+const RESERVED_SAMPLERS: usize = 8;
+// upper bound of the texture binding array, the device limit may be lower
+const MAX_TEXTURE_ARRAY_SIZE: u32 = 64;
 
 #[derive(Clone)]
 pub struct TextureDrawGroup {
-    cursor_index: u32,
+    // range in the index arena of the frame
+    index_start: u32,
     index_count: u32,
     texture_index: usize,
+    // the binding array holding the texture, None if the texture is bound on its own
+    texture_set: Option<usize>,
+    sampler_index: usize,
+    blend: UIBlendMode,
+    // the command list holding the geometry, None for the geometry of the frame
+    geometry: Option<Arc<UICommandList>>,
+    instance: u32,
 
-    vertex_offset_start: u64,
-    vertex_offset_end: u64,
-    vertex_buffer: std::rc::Rc<wgpu::Buffer>,
-    
-    index_offset_start: u64,
-    index_offset_end: u64,
-    index_buffer: std::rc::Rc<wgpu::Buffer>,
+    crop: Option<Rect>
+}
 
-    vertex_shadow_data: Vec<u8>,
-    index_shadow_data: Vec<u8>,
+// a draw of a recorded command list, the texture and sampler are resolved again every replay
+struct RecordedDraw {
+    texture: Arc<TextureResource>,
+    layer: u32,
+    dimension: wgpu::TextureViewDimension,
+    sampler: Arc<SamplerResource>,
+    blend: UIBlendMode,
+    crop: Option<Rect>,
+    index_start: u32,
+    index_count: u32,
+}
 
-    crop: Option<Rect>
+// draws collected between cmd_begin_list and cmd_end_list
+#[derive(Default)]
+struct UIRecording {
+    vertices: Vec<PositionTexCoord>,
+    indices: Vec<u32>,
+    draws: Vec<RecordedDraw>,
+    fonts: Vec<Arc<FontResource>>,
+    sub_textures: Vec<Arc<AtlasSubTexture>>,
+    // state of the frame, the list starts without crop and transform
+    frame_state: UIDrawState,
+}
+
+// draws recorded once and replayed in later frames, the geometry stays on the gpu.
+// lists without crops of their own are replayed from a render bundle, bundles can't set scissor rects so the
+// other lists are encoded like any other draw group.
+pub struct UICommandList {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    // the offset of the replay, read by the render bundle
+    instance_buffer: wgpu::Buffer,
+    draws: Vec<RecordedDraw>,
+    // encoded on the first replay and kept while the pass it is drawn in stays compatible
+    bundle: Mutex<Option<UIListBundle>>,
+    // keeps the glyphs of recorded text in the glyph cache
+    _fonts: Vec<Arc<FontResource>>,
+    // atlas images free their space when dropped, the list samples it until it is dropped itself
+    _sub_textures: Vec<Arc<AtlasSubTexture>>,
+    _memory: TrackedMemory,
+}
+
+impl UICommandList {
+    // every draw is bounded by the crop of the replay alone
+    fn bundled(&self) -> bool {
+        !self.draws.is_empty() && self.draws.iter().all(|draw| draw.crop.is_none())
+    }
+}
+
+// what a render bundle bakes in besides the list itself
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct UIBundleKey {
+    format: wgpu::TextureFormat,
+    write_alpha: bool,
+    // the frame uniform buffer and the offset of the pass in it
+    uniform_generation: u64,
+    uniform_offset: u32,
+}
+
+struct UIListBundle {
+    key: UIBundleKey,
+    bundle: Arc<wgpu::RenderBundle>,
+}
+
+impl JavaHandle<Arc<UICommandList>> for UICommandList {
+    fn from_handle(ptr: jlong) -> Option<Arc<UICommandList>> {
+        arc_from_handle(ptr)
+    }
+
+    fn to_handle(from: Arc<UICommandList>) -> jlong {
+        arc_to_handle(from)
+    }
+
+    fn drop_handle(ptr: jlong) {
+        arc_dispose_handle::<UICommandList>(ptr);
+    }
 }
 
+// a gpu buffer kept across frames, it only grows when a frame needs more than any frame before
+struct UIGeometryBuffer {
+    buffer: Option<wgpu::Buffer>,
+    memory: Option<TrackedMemory>,
+    initial_size: u64,
+    usage: wgpu::BufferUsages,
+    label: &'static str,
+}
+
+impl UIGeometryBuffer {
+    fn new(initial_size: u64, usage: wgpu::BufferUsages, label: &'static str) -> Self {
+        Self {
+            buffer: None,
+            memory: None,
+            initial_size,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            label
+        }
+    }
+
+    // writes `data` to the start of the buffer, growing it to the next power of two first if it doesn't fit
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, memory: &Arc<GpuMemoryTracker>, data: &[u8]) {
+        let required = data.len() as u64;
+        if self.buffer.as_ref().is_none_or(|buffer| buffer.size() < required) {
+            let size = self.buffer.as_ref().map_or(self.initial_size, |buffer| buffer.size() * 2)
+                .max(required.next_power_of_two());
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size,
+                usage: self.usage,
+                mapped_at_creation: false
+            }));
+            self.memory = Some(memory.track(MemoryCategory::UIScratch, self.label, size));
+        }
+        if !data.is_empty() {
+            queue.write_buffer(self.buffer.as_ref().unwrap(), 0, data);
+        }
+    }
+}
+
+
+// a texture the ui samples from. 2D textures are viewed one array layer at a time,
+// 3D textures are viewed whole and the slice is selected in the shader.
+pub struct UITextureView {
+    resource: Arc<TextureResource>,
+    layer: u32,
+    view: wgpu::TextureView,
+    dimension: wgpu::TextureViewDimension,
+}
+
+impl UITextureView {
+    fn slice_depth(&self) -> f32 {
+        (self.layer as f32 + 0.5) / self.resource.texture.depth_or_array_layers() as f32
+    }
+}
+
+// 2D textures are bound in sets of `capacity` views and picked per vertex, so draws of different
+// textures can share a draw call. only created when the device supports texture binding arrays.
+struct UITextureArray {
+    capacity: usize,
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    // bound to the slots no texture is drawn from
+    placeholder: wgpu::TextureView,
+}
+
+impl UITextureArray {
+    fn new(device: &wgpu::Device, sampler_layout: &wgpu::BindGroupLayout, per_frame_size: u64) -> Option<UITextureArray> {
+        let required = wgpu::Features::TEXTURE_BINDING_ARRAY | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING;
+        if !device.features().contains(required) {
+            return None;
+        }
+        let capacity = device.limits().max_sampled_textures_per_shader_stage.min(MAX_TEXTURE_ARRAY_SIZE);
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(per_frame_size)
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: std::num::NonZeroU32::new(capacity),
+                }
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[sampler_layout, &bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("gui_texture_array.wgsl"))),
+        });
+        let placeholder = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("ui placeholder texture"),
+            size: wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }).create_view(&wgpu::TextureViewDescriptor::default());
+        Some(UITextureArray {
+            capacity: capacity as usize,
+            bind_group_layout,
+            pipeline_layout,
+            shader,
+            placeholder
+        })
+    }
+}
+
+// a render-attachment texture the ui draws into before the screen pass
+struct UIRenderTarget {
+    resource: Arc<TextureResource>,
+    view: wgpu::TextureView,
+    clear: Option<wgpu::Color>,
+}
+
+// draws that end up in the same attachment, the screen pass has no target
+struct UIPass {
+    target: Option<UIRenderTarget>,
+    draw_groups: Vec<UIDrawGroup>,
+}
+
+impl UIPass {
+    fn bounds(&self, quad: &Rect) -> Rect {
+        match &self.target {
+            Some(target) => {
+                let size = target.resource.texture.size();
+                Rect {
+                    min: [0.0, 0.0],
+                    max: [size.width as f32, size.height as f32]
+                }
+            },
+            None => *quad
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum UIPipelineVariant {
+    Texture2D,
+    Texture3D,
+    TextureArray,
+}
+
+// how a draw is combined with what was drawn before it
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UIBlendMode {
+    #[default]
+    Alpha,
+    // for textures whose color is already multiplied by alpha
+    Premultiplied,
+    Additive,
+    Multiply,
+    // overwrites the color and alpha below, the color is multiplied by alpha
+    Replace,
+}
+
+impl UIBlendMode {
+    // the shaders output premultiplied color, additive and multiply keep the coverage of the target
+    fn blend_state(self) -> Option<wgpu::BlendState> {
+        let component = |src_factor, dst_factor| wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let over = component(wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha);
+        let keep = component(wgpu::BlendFactor::Zero, wgpu::BlendFactor::One);
+        match self {
+            UIBlendMode::Alpha | UIBlendMode::Premultiplied => Some(wgpu::BlendState { color: over, alpha: over }),
+            UIBlendMode::Additive => Some(wgpu::BlendState {
+                color: component(wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+                alpha: keep
+            }),
+            UIBlendMode::Multiply => Some(wgpu::BlendState {
+                color: component(wgpu::BlendFactor::Dst, wgpu::BlendFactor::OneMinusSrcAlpha),
+                alpha: keep
+            }),
+            UIBlendMode::Replace => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct UIPipelineKey {
+    format: wgpu::TextureFormat,
+    variant: UIPipelineVariant,
+    blend: UIBlendMode,
+    // render targets keep the coverage in alpha so they can be composited later
+    write_alpha: bool,
+}
+
+// a command list replayed from its render bundle
+pub struct BundleDrawGroup {
+    list: Arc<UICommandList>,
+    offset: [f32; 2],
+    crop: Option<Rect>,
+}
 
 pub enum UIDrawGroup {
     Texture(TextureDrawGroup),
+    Bundle(BundleDrawGroup),
 }
 
 impl UIDrawGroup {
     pub fn get_scissor_rect(&self) -> Option<Rect> {
         match self {
             UIDrawGroup::Texture(tex) => tex.crop,
-            _ => None
+            UIDrawGroup::Bundle(bundle) => bundle.crop,
+        }
+    }
+}
+
+// crop and transform applied to the following draws
+#[derive(Default)]
+struct UIDrawState {
+    // in pixels of the attachment
+    crop: Option<Rect>,
+    // crops replaced by cmd_push_crop, restored by cmd_pop_crop
+    crop_stack: Vec<Option<Rect>>,
+    transform: Affine2,
+    // transforms saved by cmd_push_transform, restored by cmd_pop_transform
+    transform_stack: Vec<Affine2>,
+}
+
+impl UIDrawState {
+    // inside a push_crop scope the crop can't grow past the crop the scope was pushed into
+    fn set_crop(&mut self, rect: Option<Rect>) {
+        let rect = rect.map(|rect| transform_rect(&self.transform, &rect));
+        self.crop = match self.crop_stack.last().copied().flatten() {
+            Some(outer) => Some(rect.map_or(outer, |rect| outer.intersection(&rect).unwrap_or(Rect::zero()))),
+            None => rect
+        };
+    }
+
+    fn push_crop(&mut self, rect: Rect) {
+        let rect = transform_rect(&self.transform, &rect);
+        self.crop_stack.push(self.crop);
+        self.crop = Some(match self.crop {
+            Some(crop) => crop.intersection(&rect).unwrap_or(Rect::zero()),
+            None => rect
+        });
+    }
+
+    fn pop_crop(&mut self) -> JNIResult<()> {
+        match self.crop_stack.pop() {
+            Some(crop) => {
+                self.crop = crop;
+                Ok(())
+            },
+            None => Err(JNIError::IllegalState("no ui crop to pop".to_string()))
         }
     }
 }
 
-#[repr(u32)]
-pub enum UITextureSamplerTypes  {
-    DefaultTextureSampler = 0, 
-    TileTextureSampler,
-    MaxSamplerTypes
+// the pixels covered by `rect` under `transform`, the bounding box if the transform rotates
+fn transform_rect(transform: &Affine2, rect: &Rect) -> Rect {
+    let corners = [rect.min, [rect.max[0], rect.min[1]], rect.max, [rect.min[0], rect.max[1]]]
+        .map(|corner| transform.transform_point2(Vec2::from(corner)));
+    let min = corners.iter().fold(Vec2::INFINITY, |min, corner| min.min(*corner));
+    let max = corners.iter().fold(Vec2::NEG_INFINITY, |max, corner| max.max(*corner));
+    Rect {
+        min: min.to_array(),
+        max: max.to_array()
+    }
 }
 
 pub struct UserInterface {
-    crop: Option<Rect>, 
+    state: UIDrawState,
+    // state of the screen pass while a render target is recorded
+    screen_state: Option<UIDrawState>,
+    surface_format: wgpu::TextureFormat,
 
     frame_uniform: wgpu::Buffer,
-    immediate_vertex_buffer: Option<Rc<wgpu::Buffer>>,
-    immediate_index_buffer: Option<Rc<wgpu::Buffer>>,
-    vertex_buffer_offset: u64,
-    index_buffer_offset: u64,
+    // bumped whenever the frame uniform is replaced, render bundles bind it
+    frame_uniform_generation: u64,
+    // geometry of every draw this frame, uploaded at once in cmd_dispatch
+    vertices: Vec<PositionTexCoord>,
+    indices: Vec<u32>,
+    vertex_buffer: UIGeometryBuffer,
+    index_buffer: UIGeometryBuffer,
+    // the first instance belongs to the geometry of the frame
+    instances: Vec<UIInstance>,
+    instance_buffer: UIGeometryBuffer,
+    // draws go into a command list instead of the frame while this is set
+    recording: Option<UIRecording>,
+    // lists replayed from their bundle this frame and the offset their instance buffer holds
+    bundled_lists: Vec<(Arc<UICommandList>, [f32; 2])>,
+    // atlas images drawn this frame, kept until the frame was submitted
+    sub_textures: Vec<Arc<AtlasSubTexture>>,
+    memory: Arc<GpuMemoryTracker>,
+    frame_uniform_memory: TrackedMemory,
 
     gui_texture_bind_group_layout: wgpu::BindGroupLayout,
-    gui_texture_const_group: wgpu::BindGroup,
-    gui_texture_pipeline: wgpu::RenderPipeline,
+    gui_texture_3d_bind_group_layout: wgpu::BindGroupLayout,
+    gui_sampler_bind_group_layout: wgpu::BindGroupLayout,
+    gui_texture_shader: wgpu::ShaderModule,
+    gui_pipeline_layout: wgpu::PipelineLayout,
+    gui_3d_pipeline_layout: wgpu::PipelineLayout,
+    pipelines: HashMap<UIPipelineKey, wgpu::RenderPipeline>,
+    texture_array: Option<UITextureArray>,
     
-    tile_sampler: wgpu::Sampler,
-    default_sampler: wgpu::Sampler,
+    tile_sampler: Arc<SamplerResource>,
+    default_sampler: Arc<SamplerResource>,
+    // sampler used by the following draws, None selects the default sampler
+    sampler: Option<Arc<SamplerResource>>,
+    blend: UIBlendMode,
     
-    draw_groups: Vec<UIDrawGroup>,
+    passes: Vec<UIPass>,
+    current_pass: usize,
 
-    textures: smallvec::SmallVec<[(Arc<TextureResource>, wgpu::TextureView); RESERVED_TEXTURE_VIEW]>,
+    textures: smallvec::SmallVec<[UITextureView; RESERVED_TEXTURE_VIEW]>,
+    samplers: smallvec::SmallVec<[Arc<SamplerResource>; RESERVED_SAMPLERS]>,
 
+    glyph_cache: GlyphCache,
 }
 
 impl Drop for UserInterface {
     fn drop(&mut self) {
-        self.draw_groups.clear();
+        self.passes.clear();
     }
 }
 
@@ -124,11 +595,26 @@ impl JavaHandle<Arc<RefCell<UserInterface>>> for UserInterface {
 
 impl UserInterface {
     pub fn cmd_prepare(&mut self) {
-        self.draw_groups.clear();
-        self.cmd_set_crop(None); 
+        self.passes.clear();
+        self.passes.push(UIPass {
+            target: None,
+            draw_groups: Vec::new()
+        });
+        self.current_pass = 0;
+        self.state = UIDrawState::default();
+        self.screen_state = None;
+        self.recording = None;
+        self.bundled_lists.clear();
+        self.sub_textures.clear();
         self.textures.clear();
-        self.vertex_buffer_offset = 0;
-        self.index_buffer_offset = 0;
+        self.samplers.clear();
+        self.sampler = None;
+        self.blend = UIBlendMode::Alpha;
+        self.vertices.clear();
+        self.indices.clear();
+        self.instances.clear();
+        self.instances.push(UIInstance::zeroed());
+        self.glyph_cache.collect_garbage();
     }
 
     pub fn cmd_dispatch(&mut self, 
@@ -136,15 +622,42 @@ impl UserInterface {
         view: &wgpu::TextureView, 
         device: &wgpu::Device, 
         queue: &wgpu::Queue, 
-        encoder: &mut wgpu::CommandEncoder) {
+        encoder: &mut wgpu::CommandEncoder) -> JNIResult<()> {
 
+        // the frame is still drawn, the error is raised once it was submitted
+        let states = || std::iter::once(&self.state).chain(self.screen_state.as_ref());
+        let unpopped_crops: usize = states().map(|state| state.crop_stack.len()).sum();
+        let unpopped_transforms: usize = states().map(|state| state.transform_stack.len()).sum();
+        let result = if self.recording.is_some() {
+            Err(JNIError::IllegalState("a ui command list was recorded but never ended".to_string()))
+        } else if unpopped_crops > 0 {
+            Err(JNIError::IllegalState(format!("{} ui crops were pushed but never popped", unpopped_crops)))
+        } else if unpopped_transforms > 0 {
+            Err(JNIError::IllegalState(format!("{} ui transforms were pushed but never popped", unpopped_transforms)))
+        } else {
+            Ok(())
+        };
 
-        {
+        // every pass has its own view transform, selected with a dynamic offset into the frame uniform
+        let per_frame_size = mem::size_of::<GuiTexturePerFrameUniform>() as u64;
+        let uniform_stride = per_frame_size.next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+        if self.frame_uniform.size() < uniform_stride * self.passes.len() as u64 {
+            self.frame_uniform = device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: uniform_stride * self.passes.len() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.frame_uniform_memory = self.memory.track(MemoryCategory::UIScratch, "ui frame uniform", self.frame_uniform.size());
+            self.frame_uniform_generation += 1;
+        }
+        for (pass_index, pass) in self.passes.iter().enumerate() {
             // Create and update the transform matrix for the current frame.
             // This is required to adapt to vulkan coordinates.
-            let size = quad.size();
-            let offset_x = quad.min[0] / size[0];
-            let offset_y = quad.min[1] / size[1];
+            let bounds = pass.bounds(quad);
+            let size = bounds.size();
+            let offset_x = bounds.min[0] / size[0];
+            let offset_y = bounds.min[1] / size[1];
             let per_frame = GuiTexturePerFrameUniform  {
                 view_transform: [
                     [2.0 / size[0]        , 0.0                 , 0.0, 0.0],
@@ -153,76 +666,427 @@ impl UserInterface {
                     [-1.0 - offset_x * 2.0, 1.0 + offset_y * 2.0, 0.0, 1.0],
                 ]
             }; 
-            queue.write_buffer(&self.frame_uniform, 0, bytemuck::bytes_of(&per_frame));
+            queue.write_buffer(&self.frame_uniform, pass_index as u64 * uniform_stride, bytemuck::bytes_of(&per_frame));
         } 
 
+        let mut pipeline_keys: Vec<UIPipelineKey> = Vec::new();
+        for pass in self.passes.iter() {
+            for group in pass.draw_groups.iter() {
+                let keys = match group {
+                    UIDrawGroup::Texture(tex) => vec![self.pipeline_key(pass, self.group_variant(tex), tex.blend)],
+                    UIDrawGroup::Bundle(bundle) => bundle.list.draws.iter()
+                        .map(|draw| self.pipeline_key(pass, bundle_variant(draw.dimension), draw.blend))
+                        .collect()
+                };
+                for key in keys {
+                    if !pipeline_keys.contains(&key) {
+                        pipeline_keys.push(key);
+                    }
+                }
+            }
+        }
+        for key in pipeline_keys {
+            self.prepare_pipeline(device, key);
+        }
+
+        self.vertex_buffer.upload(device, queue, &self.memory, bytemuck::cast_slice(&self.vertices));
+        self.index_buffer.upload(device, queue, &self.memory, bytemuck::cast_slice(&self.indices));
+        let vertex_buffer = self.vertex_buffer.buffer.as_ref().unwrap();
+        let index_buffer = self.index_buffer.buffer.as_ref().unwrap();
+        self.instance_buffer.upload(device, queue, &self.memory, bytemuck::cast_slice(&self.instances));
+        let instance_buffer = self.instance_buffer.buffer.as_ref().unwrap();
+
+        let mut bundles: HashMap<(usize, usize), Arc<wgpu::RenderBundle>> = HashMap::new();
+        for (pass_index, pass) in self.passes.iter().enumerate() {
+            for (group_index, group) in pass.draw_groups.iter().enumerate() {
+                if let UIDrawGroup::Bundle(group) = group {
+                    queue.write_buffer(&group.list.instance_buffer, 0, bytemuck::bytes_of(&UIInstance { offset: group.offset, texture: 0 }));
+                    let uniform_offset = (pass_index as u64 * uniform_stride) as u32;
+                    bundles.insert((pass_index, group_index), self.list_bundle(device, pass, &group.list, uniform_offset));
+                }
+            }
+        }
 
         encoder.push_debug_group("ui pass");
-        let mut texture_bind_groups: smallvec::SmallVec<[wgpu::BindGroup; RESERVED_TEXTURE_VIEW]> = smallvec::SmallVec::new();
-        for texture  in self.textures.iter() {
-            texture_bind_groups.push(
+        let uniform_binding = wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.frame_uniform,
+            offset: 0,
+            size: wgpu::BufferSize::new(per_frame_size)
+        });
+        let texture_set_bind_groups: Vec<wgpu::BindGroup> = match &self.texture_array {
+            Some(array) => self.textures.chunks(array.capacity).map(|set| {
+                let views: Vec<&wgpu::TextureView> = (0..array.capacity).map(|slot| match set.get(slot) {
+                    Some(texture) if texture.dimension == wgpu::TextureViewDimension::D2 => &texture.view,
+                    _ => &array.placeholder
+                }).collect();
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform_binding.clone(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 3,
+                            resource: wgpu::BindingResource::TextureViewArray(&views)
+                        }
+                    ],
+                    layout: &array.bind_group_layout,
+                    label: Some("gui_texture_array_g1"),
+                })
+            }).collect(),
+            None => Vec::new()
+        };
+        let mut texture_bind_groups: smallvec::SmallVec<[Option<wgpu::BindGroup>; RESERVED_TEXTURE_VIEW]> = smallvec::SmallVec::new();
+        for (texture_index, texture) in self.textures.iter().enumerate() {
+            if self.texture_set(texture_index).is_some() {
+                texture_bind_groups.push(None);
+                continue;
+            }
+            let (texture_binding, layout) = match texture.dimension {
+                wgpu::TextureViewDimension::D3 => (2, &self.gui_texture_3d_bind_group_layout),
+                _ => (1, &self.gui_texture_bind_group_layout)
+            };
+            texture_bind_groups.push(Some(
                 device.create_bind_group(&wgpu::BindGroupDescriptor {
                     entries: &[
                         wgpu::BindGroupEntry {
                             binding: 0,
-                            resource: self.frame_uniform.as_entire_binding(),
+                            resource: uniform_binding.clone(),
                         },
                         wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: wgpu::BindingResource::TextureView(&texture.1)
+                            binding: texture_binding,
+                            resource: wgpu::BindingResource::TextureView(&texture.view)
                         }
                     ],
-                    layout: &self.gui_texture_bind_group_layout,
+                    layout,
                     label: Some("gui_texture_g1"),
                 })
-            );
+            ));
         }
 
-       {
+        let sampler_bind_groups: smallvec::SmallVec<[wgpu::BindGroup; RESERVED_SAMPLERS]> = self.samplers.iter().map(|sampler| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Sampler(&sampler.sampler),
+                    }
+                ],
+                layout: &self.gui_sampler_bind_group_layout,
+                label: Some("gui_texture_g0"),
+            })
+        }).collect();
+
+        // render targets are drawn in the order they were recorded so the screen pass can sample all of them
+        for pass_index in (1..self.passes.len()).chain(std::iter::once(0)) {
+            let pass = &self.passes[pass_index];
+            let bounds = pass.bounds(quad);
+            let (attachment, load) = match &pass.target {
+                Some(target) => (&target.view, target.clear.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear)),
+                None => (view, wgpu::LoadOp::Load)
+            };
 
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
+                    view: attachment,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
+                        load,
                         store: true
                     },
                 })],
                 depth_stencil_attachment: None 
             });
-        
-            rpass.set_bind_group(0, &self.gui_texture_const_group, &[]);
+            rpass.set_vertex_buffer(1, instance_buffer.slice(..));
+            let mut bound_geometry = None;
 
-            for group in self.draw_groups.iter() {
-                match group.get_scissor_rect() {
-                    Some(rect) => rpass.set_scissor_rect(rect.min[0] as u32, rect.min[1] as u32, rect.size()[0] as u32, rect.size()[1] as u32),
-                    _ => rpass.set_scissor_rect(quad.min[0] as u32, quad.min[1] as u32, quad.size()[0] as u32, quad.size()[1] as u32)
-                }
+            for (group_index, group) in pass.draw_groups.iter().enumerate() {
+                let Some(scissor) = scissor_rect(group.get_scissor_rect(), &bounds) else {
+                    continue;
+                };
+                rpass.set_scissor_rect(scissor[0], scissor[1], scissor[2], scissor[3]);
 
                 match group {
                     UIDrawGroup::Texture(ref tex) => {
-                        queue.write_buffer(&tex.vertex_buffer.as_ref(), tex.vertex_offset_start, &tex.vertex_shadow_data);
-                        queue.write_buffer(&tex.index_buffer.as_ref(), tex.index_offset_start, &tex.index_shadow_data);
-                       // update_bind_group(&mut texture_bind_groups, tex.texture_index);
-
-                        rpass.set_bind_group(1, &texture_bind_groups[tex.texture_index], &[]);
-                        rpass.set_pipeline(&self.gui_texture_pipeline);
-                        rpass.set_index_buffer(tex.index_buffer.slice(tex.index_offset_start..tex.index_offset_end), wgpu::IndexFormat::Uint32);
-                        rpass.set_vertex_buffer(0, tex.vertex_buffer.slice(tex.vertex_offset_start..tex.vertex_offset_end));
-                        rpass.draw_indexed(0..tex.index_count, 0, 0..1);
+                        let pipeline = &self.pipelines[&self.pipeline_key(pass, self.group_variant(tex), tex.blend)];
+                        rpass.set_bind_group(0, &sampler_bind_groups[tex.sampler_index], &[]);
+                        let texture_bind_group = match tex.texture_set {
+                            Some(set) => &texture_set_bind_groups[set],
+                            None => texture_bind_groups[tex.texture_index].as_ref().unwrap()
+                        };
+                        rpass.set_bind_group(1, texture_bind_group, &[(pass_index as u64 * uniform_stride) as u32]);
+                        rpass.set_pipeline(pipeline);
+                        let texture = &self.textures[tex.texture_index];
+                        if texture.dimension == wgpu::TextureViewDimension::D3 {
+                            rpass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&GuiTexturePushConstants {
+                                texture_style: 0,
+                                slice_depth: texture.slice_depth()
+                            }));
+                        }
+                        let geometry = tex.geometry.as_ref().map(Arc::as_ptr);
+                        if bound_geometry != Some(geometry) {
+                            let (vertices, indices) = match &tex.geometry {
+                                Some(list) => (&list.vertex_buffer, &list.index_buffer),
+                                None => (vertex_buffer, index_buffer)
+                            };
+                            rpass.set_vertex_buffer(0, vertices.slice(..));
+                            rpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                            bound_geometry = Some(geometry);
+                        }
+                        rpass.draw_indexed(tex.index_start..tex.index_start + tex.index_count, 0, tex.instance..tex.instance + 1);
+                    }
+                    UIDrawGroup::Bundle(_) => {
+                        rpass.execute_bundles(std::iter::once(bundles[&(pass_index, group_index)].as_ref()));
+                        // the bundle leaves the pass without pipeline, bind groups and buffers
+                        rpass.set_vertex_buffer(1, instance_buffer.slice(..));
+                        bound_geometry = None;
                     }
                 }
 
             }
         }
         encoder.pop_debug_group();
+        result
+    }
+
+    fn group_variant(&self, tex: &TextureDrawGroup) -> UIPipelineVariant {
+        match (tex.texture_set, self.textures[tex.texture_index].dimension) {
+            (Some(_), _) => UIPipelineVariant::TextureArray,
+            (None, wgpu::TextureViewDimension::D3) => UIPipelineVariant::Texture3D,
+            (None, _) => UIPipelineVariant::Texture2D
+        }
+    }
+
+    fn pipeline_key(&self, pass: &UIPass, variant: UIPipelineVariant, blend: UIBlendMode) -> UIPipelineKey {
+        UIPipelineKey {
+            format: pass.target.as_ref().map_or(self.surface_format, |target| target.resource.texture.format()),
+            variant,
+            blend,
+            write_alpha: pass.target.is_some()
+        }
+    }
+
+    // the render bundle of a list drawn in `pass`, encoded again if the last one was made for another pass
+    fn list_bundle(&self, device: &wgpu::Device, pass: &UIPass, list: &UICommandList, uniform_offset: u32) -> Arc<wgpu::RenderBundle> {
+        let format = pass.target.as_ref().map_or(self.surface_format, |target| target.resource.texture.format());
+        let key = UIBundleKey {
+            format,
+            write_alpha: pass.target.is_some(),
+            uniform_generation: self.frame_uniform_generation,
+            uniform_offset
+        };
+        let mut cached = list.bundle.lock().unwrap();
+        if let Some(cached) = cached.as_ref().filter(|cached| cached.key == key) {
+            return cached.bundle.clone();
+        }
+
+        let uniform_binding = wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.frame_uniform,
+            offset: 0,
+            size: wgpu::BufferSize::new(mem::size_of::<GuiTexturePerFrameUniform>() as u64)
+        });
+        // the bundle references these, they have to outlive the encoder
+        let bind_groups: Vec<(wgpu::BindGroup, wgpu::BindGroup)> = list.draws.iter().map(|draw| {
+            let sampler = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Sampler(&draw.sampler.sampler),
+                    }
+                ],
+                layout: &self.gui_sampler_bind_group_layout,
+                label: Some("gui_texture_g0"),
+            });
+            let (_, view) = create_ui_texture_view(&draw.texture, draw.layer).expect("recorded textures were checked");
+            let (texture_binding, layout) = match draw.dimension {
+                wgpu::TextureViewDimension::D3 => (2, &self.gui_texture_3d_bind_group_layout),
+                _ => (1, &self.gui_texture_bind_group_layout)
+            };
+            let texture = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_binding.clone(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: texture_binding,
+                        resource: wgpu::BindingResource::TextureView(&view)
+                    }
+                ],
+                layout,
+                label: Some("gui_texture_g1"),
+            });
+            (sampler, texture)
+        }).collect();
+
+        let mut encoder = device.create_render_bundle_encoder(&wgpu::RenderBundleEncoderDescriptor {
+            label: Some("ui command list bundle"),
+            color_formats: &[Some(format)],
+            depth_stencil: None,
+            sample_count: 1,
+            multiview: None
+        });
+        encoder.set_vertex_buffer(0, list.vertex_buffer.slice(..));
+        encoder.set_vertex_buffer(1, list.instance_buffer.slice(..));
+        encoder.set_index_buffer(list.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for (draw, (sampler, texture)) in list.draws.iter().zip(bind_groups.iter()) {
+            encoder.set_pipeline(&self.pipelines[&self.pipeline_key(pass, bundle_variant(draw.dimension), draw.blend)]);
+            encoder.set_bind_group(0, sampler, &[]);
+            encoder.set_bind_group(1, texture, &[uniform_offset]);
+            if draw.dimension == wgpu::TextureViewDimension::D3 {
+                let depth = draw.texture.texture.depth_or_array_layers();
+                encoder.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, bytemuck::bytes_of(&GuiTexturePushConstants {
+                    texture_style: 0,
+                    slice_depth: (draw.layer as f32 + 0.5) / depth as f32
+                }));
+            }
+            encoder.draw_indexed(draw.index_start..draw.index_start + draw.index_count, 0, 0..1);
+        }
+        let bundle = Arc::new(encoder.finish(&wgpu::RenderBundleDescriptor {
+            label: Some("ui command list bundle")
+        }));
+        *cached = Some(UIListBundle {
+            key,
+            bundle: bundle.clone()
+        });
+        bundle
+    }
+
+    fn prepare_pipeline(&mut self, device: &wgpu::Device, key: UIPipelineKey) {
+        if self.pipelines.contains_key(&key) {
+            return;
+        }
+        let premultiplied = key.blend == UIBlendMode::Premultiplied;
+        let (shader, layout, fragment_entry) = match key.variant {
+            UIPipelineVariant::Texture2D => (&self.gui_texture_shader, &self.gui_pipeline_layout,
+                if premultiplied { "fs_main_premultiplied" } else { "fs_main" }),
+            UIPipelineVariant::Texture3D => (&self.gui_texture_shader, &self.gui_3d_pipeline_layout,
+                if premultiplied { "fs_main_3d_premultiplied" } else { "fs_main_3d" }),
+            UIPipelineVariant::TextureArray => {
+                let array = self.texture_array.as_ref().expect("texture sets are only assigned with a texture array");
+                (&array.shader, &array.pipeline_layout, if premultiplied { "fs_main_array_premultiplied" } else { "fs_main_array" })
+            }
+        };
+        let pipeline = create_gui_texture_pipeline(device, shader, layout, fragment_entry, key.format, key.blend, key.write_alpha);
+        self.pipelines.insert(key, pipeline);
+    }
+
+    // starts recording draws into a render-attachment texture, the draws are dispatched before the screen
+    // so later draws in the same frame can sample the texture. `clear` replaces the previous contents.
+    pub fn cmd_begin_target(&mut self, device: &wgpu::Device, target: &Arc<TextureResource>, clear: Option<wgpu::Color>) -> JNIResult<()> {
+        if self.current_pass != 0 {
+            return Err(JNIError::IllegalArgument("ui render targets can't be nested".to_string()));
+        }
+        if self.recording.is_some() {
+            return Err(JNIError::IllegalState("ui render targets can't be recorded into a command list".to_string()));
+        }
+        let texture = &target.texture;
+        if !texture.usage().contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            return Err(JNIError::IllegalArgument("texture was not created with RENDER_ATTACHMENT usage".to_string()));
+        }
+        if texture.dimension() != wgpu::TextureDimension::D2 {
+            return Err(JNIError::IllegalArgument("only 2D textures can be used as ui render targets".to_string()));
+        }
+        let features = texture.format().guaranteed_format_features(device.features());
+        if !features.flags.contains(wgpu::TextureFormatFeatureFlags::BLENDABLE) {
+            return Err(JNIError::IllegalArgument(format!("texture format {:?} can't be blended into", texture.format())));
+        }
+        self.passes.push(UIPass {
+            target: Some(UIRenderTarget {
+                resource: target.clone(),
+                view: texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_mip_level: 0,
+                    mip_level_count: Some(1),
+                    base_array_layer: 0,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                }),
+                clear
+            }),
+            draw_groups: Vec::new()
+        });
+        self.current_pass = self.passes.len() - 1;
+        self.screen_state = Some(mem::take(&mut self.state));
+        Ok(())
+    }
+
+    // returns to drawing on the screen
+    pub fn cmd_end_target(&mut self) -> JNIResult<()> {
+        if self.current_pass == 0 {
+            return Err(JNIError::IllegalArgument("no ui render target to end".to_string()));
+        }
+        if self.recording.is_some() {
+            return Err(JNIError::IllegalState("ui render targets can't be recorded into a command list".to_string()));
+        }
+        if !self.state.crop_stack.is_empty() || !self.state.transform_stack.is_empty() {
+            return Err(JNIError::IllegalState("ui crops and transforms pushed into a render target have to be popped before it ends".to_string()));
+        }
+        self.current_pass = 0;
+        self.state = self.screen_state.take().unwrap_or_default();
+        Ok(())
     }
     
+    // replaces the current crop, inside a cmd_push_crop scope it stays within the crop the scope was pushed into.
+    // crops are given in the current transform, the scissor covers the bounding box of rects that end up rotated.
     pub fn cmd_set_crop(&mut self, rect: Option<Rect>) {
-        self.crop = rect;
+        self.state.set_crop(rect);
+    }
+
+    // crops the following draws to the part of `rect` inside the current crop
+    pub fn cmd_push_crop(&mut self, rect: Rect) {
+        self.state.push_crop(rect);
+    }
+
+    // restores the crop from before the matching cmd_push_crop
+    pub fn cmd_pop_crop(&mut self) -> JNIResult<()> {
+        self.state.pop_crop()
     }
+
+    // saves the current transform until the matching cmd_pop_transform
+    pub fn cmd_push_transform(&mut self) {
+        self.state.transform_stack.push(self.state.transform);
+    }
+
+    pub fn cmd_pop_transform(&mut self) -> JNIResult<()> {
+        match self.state.transform_stack.pop() {
+            Some(transform) => {
+                self.state.transform = transform;
+                Ok(())
+            },
+            None => Err(JNIError::IllegalState("no ui transform to pop".to_string()))
+        }
+    }
+
+    // translate, rotate and scale apply before the current transform, like moving into a child's space
+    pub fn cmd_translate(&mut self, offset: [f32; 2]) {
+        self.state.transform = self.state.transform * Affine2::from_translation(Vec2::from(offset));
+    }
+
+    // positive angles turn clockwise on screen, y points down
+    pub fn cmd_rotate(&mut self, radians: f32) {
+        self.state.transform = self.state.transform * Affine2::from_angle(radians);
+    }
+
+    pub fn cmd_scale(&mut self, scale: [f32; 2]) {
+        self.state.transform = self.state.transform * Affine2::from_scale(Vec2::from(scale));
+    }
+
+    // selects the sampler for the following draws, None selects the default linear sampler
+    pub fn cmd_set_sampler(&mut self, sampler: Option<Arc<SamplerResource>>) -> JNIResult<()> {
+        if sampler.as_ref().is_some_and(|sampler| sampler.comparison) {
+            return Err(JNIError::IllegalArgument("comparison samplers can't be used to draw ui textures".to_string()));
+        }
+        self.sampler = sampler;
+        Ok(())
+    }
+
+    // selects the blend mode for the following draws until the end of the frame
+    pub fn cmd_set_blend_mode(&mut self, blend: UIBlendMode) {
+        self.blend = blend;
+    }
+
     fn evaluate_draw_group(&mut self, new_group: UIDrawGroup) -> bool {
         fn test_bound_rects(rects: &[Rect], test: &Rect) -> bool {
             for rec in rects.iter() {
@@ -233,140 +1097,560 @@ impl UserInterface {
             return false
         }
 
-        let group_valid = match self.draw_groups.last() {
+        let draw_groups = &mut self.passes[self.current_pass].draw_groups;
+        let group_valid = match draw_groups.last() {
             Some(current_draw_group) => {
                 match (current_draw_group, &new_group) {
                     (UIDrawGroup::Texture(current), UIDrawGroup::Texture(new_group)) => {
-                        current.texture_index == new_group.texture_index
+                        (current.texture_index == new_group.texture_index || (current.texture_set.is_some() && current.texture_set == new_group.texture_set))
+                        && current.sampler_index == new_group.sampler_index
+                        && current.blend == new_group.blend
                         && current.crop == new_group.crop
-                        && Rc::ptr_eq(&current.vertex_buffer, &new_group.vertex_buffer)
-                        && Rc::ptr_eq(&current.index_buffer, &new_group.index_buffer)
+                        && current.geometry.is_none() && new_group.geometry.is_none()
                     }
+                    _ => false
                 }
             },
             None => false
         };
 
         if !group_valid {
-            self.draw_groups.push(new_group);
+            draw_groups.push(new_group);
             return true
         }
         return false
     }
 
 
-    fn request_buffer_immediate(&mut self, device: &wgpu::Device, request_vertex_buffer_size: u64, request_index_buffer_size: u64) -> (u64, u64){
-        let mut vb_last_offset = self.vertex_buffer_offset;
-        let mut ib_last_offset = self.index_buffer_offset; 
-
-        if self.immediate_vertex_buffer.is_none() ||  (request_vertex_buffer_size + self.vertex_buffer_offset) > self.immediate_vertex_buffer.as_ref().unwrap().size() {
-            let size = self.immediate_vertex_buffer.as_ref().map_or_else(|| VERTEX_BUFFER_INITIAL_SIZE, |d| d.size() * 2) as wgpu::BufferAddress ;
-            self.immediate_vertex_buffer = Some(Rc::new(device.create_buffer(
-                &wgpu::BufferDescriptor  {
-                    label: Some("Unit Square Vertex Buffer"),
-                    size ,
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false 
-                })));
-            vb_last_offset = 0; 
-            self.vertex_buffer_offset = 0;
-        }
-        if self.immediate_index_buffer.is_none() ||  (request_index_buffer_size + self.index_buffer_offset) > self.immediate_index_buffer.as_ref().unwrap().size() {
-            let size = self.immediate_index_buffer.as_ref().map_or_else(|| INDEX_BUFFER_INITIAL_SIZE, |d| d.size() * 2) as wgpu::BufferAddress;
-            self.immediate_index_buffer = Some(Rc::new(device.create_buffer(
-                &wgpu::BufferDescriptor  {
-                    label: Some("Unit Square Vertex Buffer"),
-                    size,
-                    usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false
-                })));
-            ib_last_offset = 0;
-            self.index_buffer_offset = 0;
-        }
-        self.vertex_buffer_offset += request_vertex_buffer_size;
-        self.index_buffer_offset += request_index_buffer_size;
-        (vb_last_offset, ib_last_offset)
-    }
-
-    fn resolve_texture_index(&mut self, tex: &Arc<TextureResource>) -> usize {
+    fn resolve_texture_index(&mut self, tex: &Arc<TextureResource>, layer: u32) -> JNIResult<usize> {
+        if self.passes[self.current_pass].target.as_ref().is_some_and(|target| Arc::ptr_eq(&target.resource, tex)) {
+            return Err(JNIError::IllegalArgument("a render target can't be sampled while it is drawn into".to_string()));
+        }
        for (pos, e) in self.textures.iter().enumerate() {
-            if Arc::ptr_eq(&e.0, &tex) {
-                return pos;
+            if Arc::ptr_eq(&e.resource, tex) && e.layer == layer {
+                return Ok(pos);
             }
         }
-        self.textures.push((tex.clone(), tex.texture.create_view(&wgpu::TextureViewDescriptor::default())));
-        return self.textures.len() - 1;
+        let (dimension, view) = create_ui_texture_view(tex, layer)?;
+        self.textures.push(UITextureView {
+            resource: tex.clone(),
+            layer,
+            view,
+            dimension
+        });
+        Ok(self.textures.len() - 1)
     }
 
-    pub fn cmd_draw_texture(&mut self, queue: &wgpu::Queue, device: &wgpu::Device, tex: &Arc<TextureResource>, uv: &Rect, pos: &Rect, tint_color: u32) {
-        const NUM_VERTS: usize = 4;
-        const NUM_INDCIES: usize = 6;
-        let tex_index = self.resolve_texture_index(tex);
+    // the texture array set a texture is bound in, 3D textures are always bound on their own
+    fn texture_set(&self, texture_index: usize) -> Option<usize> {
+        match (&self.texture_array, self.textures[texture_index].dimension) {
+            (Some(array), wgpu::TextureViewDimension::D2) => Some(texture_index / array.capacity),
+            _ => None
+        }
+    }
+
+    // the sampler selected for the following draws
+    fn current_sampler(&self) -> &Arc<SamplerResource> {
+        self.sampler.as_ref().unwrap_or(&self.default_sampler)
+    }
+
+    fn resolve_sampler_index(&mut self, sampler: &Arc<SamplerResource>) -> usize {
+        if let Some(pos) = self.samplers.iter().position(|e| Arc::ptr_eq(e, sampler)) {
+            return pos;
+        }
+        self.samplers.push(sampler.clone());
+        self.samplers.len() - 1
+    }
+
+    // appends triangles sampling `tex` to the current draw group, `indices` count from the first of `vertices`
+    fn push_geometry(&mut self, tex: &Arc<TextureResource>, layer: u32, vertices: &[PositionTexCoord], indices: &[u32]) -> JNIResult<()> {
+        // nothing is visible inside an empty crop
+        if self.state.crop.is_some_and(|crop| crop.is_empty()) {
+            return Ok(());
+        }
+        if let Some(recording) = &mut self.recording {
+            let dimension = ui_texture_dimension(tex, layer)?;
+            let sampler = self.sampler.as_ref().unwrap_or(&self.default_sampler).clone();
+            let blend = self.blend;
+            let crop = self.state.crop;
+            let transform = self.state.transform;
+            let merge = recording.draws.last().is_some_and(|draw| Arc::ptr_eq(&draw.texture, tex) && draw.layer == layer
+                && Arc::ptr_eq(&draw.sampler, &sampler) && draw.blend == blend && draw.crop == crop);
+            if !merge {
+                recording.draws.push(RecordedDraw {
+                    texture: tex.clone(),
+                    layer,
+                    dimension,
+                    sampler,
+                    blend,
+                    crop,
+                    index_start: recording.indices.len() as u32,
+                    index_count: 0
+                });
+            }
+            recording.draws.last_mut().unwrap().index_count += indices.len() as u32;
+            let base = recording.vertices.len() as u32;
+            recording.vertices.extend(transform_vertices(transform, 0, vertices));
+            recording.indices.extend(indices.iter().map(|index| base + index));
+            return Ok(());
+        }
+        let tex_index = self.resolve_texture_index(tex, layer)?;
+        let texture_set = self.texture_set(tex_index);
+        let slot = self.texture_array.as_ref().map_or(0, |array| (tex_index % array.capacity) as u32);
+        let sampler = self.current_sampler().clone();
+        let sampler_index = self.resolve_sampler_index(&sampler);
 
-        let request_vertex_buffer_size = (std::mem::size_of::<PositionTexCoord>() * NUM_VERTS) as u64;
-        let request_index_buffer_size = (std::mem::size_of::<u32>() * NUM_INDCIES) as u64;
-        let (vb_buffer_start_offset, ib_buffer_start_offset) = self.request_buffer_immediate(device, request_vertex_buffer_size, request_index_buffer_size);
         let _is_new_group = self.evaluate_draw_group(UIDrawGroup::Texture(TextureDrawGroup {
-            vertex_buffer: self.immediate_vertex_buffer.as_ref().unwrap().clone(),
-            index_buffer: self.immediate_index_buffer.as_ref().unwrap().clone(),
-            vertex_offset_start: vb_buffer_start_offset,
-            vertex_offset_end: vb_buffer_start_offset,
-            index_offset_start: ib_buffer_start_offset,
-            index_offset_end: ib_buffer_start_offset,
-            crop: self.crop,
-            vertex_shadow_data: vec![],
-            index_shadow_data: vec![],
+            crop: self.state.crop,
+            index_start: self.indices.len() as u32,
             index_count: 0,
             texture_index: tex_index,
-            cursor_index: 0
+            texture_set,
+            sampler_index,
+            blend: self.blend,
+            geometry: None,
+            instance: 0,
         }));
-        let UIDrawGroup::Texture(ref mut current_group) = self.draw_groups.last_mut().unwrap();
+        // the group was just pushed or merged into, it always draws textures
+        if let Some(UIDrawGroup::Texture(current_group)) = self.passes[self.current_pass].draw_groups.last_mut() {
+            current_group.index_count += indices.len() as u32;
+        }
 
-        let c: [u8; 4] = bytemuck::cast(tint_color); 
-        let vertex_data: &[PositionTexCoord; NUM_VERTS] = &[
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(transform_vertices(self.state.transform, slot, vertices));
+        self.indices.extend(indices.iter().map(|index| base + index));
+        Ok(())
+    }
+
+    // the following draws are recorded into a command list instead of the frame, starting without crop and transform
+    pub fn cmd_begin_list(&mut self) -> JNIResult<()> {
+        if self.recording.is_some() {
+            return Err(JNIError::IllegalState("ui command lists can't be recorded inside each other".to_string()));
+        }
+        self.recording = Some(UIRecording {
+            frame_state: mem::take(&mut self.state),
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    // uploads the recorded draws, the crop and transform of the frame are restored
+    pub fn cmd_end_list(&mut self, device: &wgpu::Device) -> JNIResult<Arc<UICommandList>> {
+        if self.recording.is_none() {
+            return Err(JNIError::IllegalState("no ui command list is recorded".to_string()));
+        }
+        if !self.state.crop_stack.is_empty() || !self.state.transform_stack.is_empty() {
+            return Err(JNIError::IllegalState("ui crops and transforms pushed into a command list have to be popped before it ends".to_string()));
+        }
+        let recording = self.recording.take().unwrap();
+        self.state = recording.frame_state;
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ui command list vertex buffer"),
+            contents: bytemuck::cast_slice(&recording.vertices),
+            usage: wgpu::BufferUsages::VERTEX
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ui command list index buffer"),
+            contents: bytemuck::cast_slice(&recording.indices),
+            usage: wgpu::BufferUsages::INDEX
+        });
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("ui command list instance buffer"),
+            size: mem::size_of::<UIInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let memory = self.memory.track(MemoryCategory::Buffer, "ui command list",
+            vertex_buffer.size() + index_buffer.size() + instance_buffer.size());
+        Ok(Arc::new(UICommandList {
+            vertex_buffer,
+            index_buffer,
+            instance_buffer,
+            draws: recording.draws,
+            bundle: Mutex::new(None),
+            _fonts: recording.fonts,
+            _sub_textures: recording.sub_textures,
+            _memory: memory
+        }))
+    }
+
+    // replays a command list moved by `offset` pixels. the current transform doesn't apply,
+    // the current crop and `clip` (in pixels) crop the crops recorded in the list.
+    pub fn cmd_draw_list(&mut self, list: &Arc<UICommandList>, offset: [f32; 2], clip: Option<Rect>) -> JNIResult<()> {
+        if self.recording.is_some() {
+            return Err(JNIError::IllegalState("ui command lists can't be replayed while a command list is recorded".to_string()));
+        }
+        if self.can_bundle(list, offset) {
+            let mut crop = None;
+            for bound in [clip, self.state.crop].into_iter().flatten() {
+                crop = Some(match crop {
+                    Some(crop) => bound.intersection(&crop).unwrap_or(Rect::zero()),
+                    None => bound
+                });
+            }
+            if crop.is_some_and(|crop| crop.is_empty()) {
+                return Ok(());
+            }
+            if !self.bundled_lists.iter().any(|(bundled, _)| Arc::ptr_eq(bundled, list)) {
+                self.bundled_lists.push((list.clone(), offset));
+            }
+            self.passes[self.current_pass].draw_groups.push(UIDrawGroup::Bundle(BundleDrawGroup {
+                list: list.clone(),
+                offset,
+                crop
+            }));
+            return Ok(());
+        }
+        let mut instance = None;
+        for draw in list.draws.iter() {
+            let mut crop = draw.crop.map(|crop| Rect {
+                min: [crop.min[0] + offset[0], crop.min[1] + offset[1]],
+                max: [crop.max[0] + offset[0], crop.max[1] + offset[1]]
+            });
+            for bound in [clip, self.state.crop].into_iter().flatten() {
+                crop = Some(match crop {
+                    Some(crop) => crop.intersection(&bound).unwrap_or(Rect::zero()),
+                    None => bound
+                });
+            }
+            if crop.is_some_and(|crop| crop.is_empty()) {
+                continue;
+            }
+            let tex_index = self.resolve_texture_index(&draw.texture, draw.layer)?;
+            let slot = self.texture_array.as_ref().map_or(0, |array| (tex_index % array.capacity) as u32);
+            let sampler_index = self.resolve_sampler_index(&draw.sampler);
+            // draws sampling the same slot share an instance
+            let instance = match instance {
+                Some((instance, instance_slot)) if instance_slot == slot => instance,
+                _ => {
+                    self.instances.push(UIInstance { offset, texture: slot });
+                    let index = self.instances.len() as u32 - 1;
+                    instance = Some((index, slot));
+                    index
+                }
+            };
+            let texture_set = self.texture_set(tex_index);
+            self.passes[self.current_pass].draw_groups.push(UIDrawGroup::Texture(TextureDrawGroup {
+                crop,
+                index_start: draw.index_start,
+                index_count: draw.index_count,
+                texture_index: tex_index,
+                texture_set,
+                sampler_index,
+                blend: draw.blend,
+                geometry: Some(list.clone()),
+                instance
+            }));
+        }
+        Ok(())
+    }
+
+    // lists without crops are replayed from their bundle. the offset is read from the instance buffer of the list,
+    // so a list replayed again at another offset in the same frame is encoded like a cropped one.
+    fn can_bundle(&self, list: &Arc<UICommandList>, offset: [f32; 2]) -> bool {
+        if !list.bundled() {
+            return false;
+        }
+        let target = self.passes[self.current_pass].target.as_ref();
+        if list.draws.iter().any(|draw| target.is_some_and(|target| Arc::ptr_eq(&target.resource, &draw.texture))) {
+            return false;
+        }
+        self.bundled_lists.iter()
+            .find(|(bundled, _)| Arc::ptr_eq(bundled, list))
+            .is_none_or(|(_, bundled_offset)| *bundled_offset == offset)
+    }
+
+    // draws a layer of a 2D texture, or a depth slice of a 3D texture
+    pub fn cmd_draw_texture(&mut self, tex: &Arc<TextureResource>, layer: u32, uv: &Rect, pos: &Rect, tint_color: u32) -> JNIResult<()> {
+        let color = vertex_color(tint_color);
+        let vertex_data = [
             PositionTexCoord {
                 pos: [pos.min[0], pos.min[1]],
                 uv: uv.min,
-                color: bytemuck::cast([c[3], c[2], c[1], c[0]]) 
+                color,
+                texture: 0
             },
             PositionTexCoord {
                 pos: [pos.max[0], pos.min[1]],
-                uv: [uv.max[0], uv.min[1]], 
-                color: bytemuck::cast([c[3], c[2], c[1], c[0]]) 
+                uv: [uv.max[0], uv.min[1]],
+                color,
+                texture: 0
             },
             PositionTexCoord {
                 pos: [pos.max[0],pos.max[1]],
                 uv: uv.max,
-                color: bytemuck::cast([c[3], c[2], c[1], c[0]]) 
+                color,
+                texture: 0
             },
             PositionTexCoord {
                 pos: [pos.min[0], pos.max[1]],
-                uv: [uv.min[0], uv.max[1]],   
-                color: bytemuck::cast([c[3], c[2], c[1], c[0]]) 
+                uv: [uv.min[0], uv.max[1]],
+                color,
+                texture: 0
             }
         ];
-       
-        let cursor = current_group.cursor_index;
-        let index_data: &[u32; NUM_INDCIES] = &[
-            cursor + 0, cursor + 1, cursor + 2,
-            cursor + 0, cursor + 2, cursor + 3
+        self.push_geometry(tex, layer, &vertex_data, &QUAD_INDICES)
+    }
+
+    // repeats a texture across `pos` in a single quad. `tile_size` is the size of one repetition and `offset` shifts
+    // the pattern, both in pixels. without a selected sampler the built in repeating sampler is used.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cmd_draw_texture_tiled(&mut self, tex: &Arc<TextureResource>, layer: u32, pos: &Rect, tile_size: [f32; 2], offset: [f32; 2], tint_color: u32) -> JNIResult<()> {
+        if tile_size.iter().any(|size| !(*size > 0.0 && size.is_finite())) {
+            return Err(JNIError::IllegalArgument(format!("invalid tile size {:?}", tile_size)));
+        }
+        let [width, height] = pos.size();
+        let uv = Rect {
+            min: [-offset[0] / tile_size[0], -offset[1] / tile_size[1]],
+            max: [(width - offset[0]) / tile_size[0], (height - offset[1]) / tile_size[1]]
+        };
+        let selected = self.sampler.clone();
+        if selected.is_none() {
+            self.sampler = Some(self.tile_sampler.clone());
+        }
+        let result = self.cmd_draw_texture(tex, layer, &uv, pos, tint_color);
+        self.sampler = selected;
+        result
+    }
+
+    // a bordered frame from a single image. the corners keep their size, the edges stretch along one axis and the
+    // center along both, or repeat if the slice tiles.
+    #[allow(clippy::too_many_arguments)]
+    pub fn cmd_draw_nine_slice(&mut self, tex: &Arc<TextureResource>, layer: u32, uv: &Rect, pos: &Rect, slice: &NineSlice, tint_color: u32) -> JNIResult<()> {
+        if slice.insets.iter().any(|inset| !(*inset >= 0.0 && inset.is_finite())) {
+            return Err(JNIError::IllegalArgument(format!("invalid nine slice insets {:?}", slice.insets)));
+        }
+        if !(slice.scale > 0.0 && slice.scale.is_finite()) {
+            return Err(JNIError::IllegalArgument(format!("invalid nine slice scale {}", slice.scale)));
+        }
+        let size = tex.texture.size();
+        let [left, top, right, bottom] = slice.insets;
+        let columns = nine_slice_axis([pos.min[0], pos.max[0]], [uv.min[0], uv.max[0]], [left, right], size.width as f32, slice);
+        let rows = nine_slice_axis([pos.min[1], pos.max[1]], [uv.min[1], uv.max[1]], [top, bottom], size.height as f32, slice);
+
+        let color = vertex_color(tint_color);
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (row_pos, row_uv) in rows.iter().flatten() {
+            for (column_pos, column_uv) in columns.iter().flatten() {
+                let first = vertices.len() as u32;
+                vertices.extend_from_slice(&[
+                    PositionTexCoord { pos: [column_pos[0], row_pos[0]], uv: [column_uv[0], row_uv[0]], color, texture: 0 },
+                    PositionTexCoord { pos: [column_pos[1], row_pos[0]], uv: [column_uv[1], row_uv[0]], color, texture: 0 },
+                    PositionTexCoord { pos: [column_pos[1], row_pos[1]], uv: [column_uv[1], row_uv[1]], color, texture: 0 },
+                    PositionTexCoord { pos: [column_pos[0], row_pos[1]], uv: [column_uv[0], row_uv[1]], color, texture: 0 },
+                ]);
+                indices.extend(QUAD_INDICES.iter().map(|index| first + index));
+            }
+        }
+        if vertices.is_empty() {
+            return Ok(());
+        }
+        self.push_geometry(tex, layer, &vertices, &indices)
+    }
+
+    // a nine slice of an image packed into a texture atlas, `uv` is relative to the image
+    pub fn cmd_draw_sub_nine_slice(&mut self, sub_texture: &Arc<AtlasSubTexture>, uv: &Rect, pos: &Rect, slice: &NineSlice, tint_color: u32) -> JNIResult<()> {
+        self.keep_sub_texture(sub_texture);
+        let (texture, page_uv) = sub_texture.resolve();
+        let page_size = page_uv.size();
+        let uv = Rect {
+            min: [page_uv.min[0] + uv.min[0] * page_size[0], page_uv.min[1] + uv.min[1] * page_size[1]],
+            max: [page_uv.min[0] + uv.max[0] * page_size[0], page_uv.min[1] + uv.max[1] * page_size[1]]
+        };
+        self.cmd_draw_nine_slice(&texture, 0, &uv, pos, slice, tint_color)
+    }
+
+    // a filled rect, the edges are anti-aliased so rects on fractional positions don't shimmer
+    pub fn cmd_draw_rect(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, pos: &Rect, color: u32) -> JNIResult<()> {
+        let [width, height] = pos.size();
+        if !(width > 0.0 && height > 0.0) {
+            return Ok(());
+        }
+        let center = [(pos.min[0] + pos.max[0]) * 0.5, (pos.min[1] + pos.max[1]) * 0.5];
+        self.push_feathered_quad(device, queue, center, [1.0, 0.0], [width * 0.5, height * 0.5], color)
+    }
+
+    // a line with butt caps, centered on the segment from `from` to `to`
+    #[allow(clippy::too_many_arguments)]
+    pub fn cmd_draw_line(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, from: [f32; 2], to: [f32; 2], thickness: f32, color: u32) -> JNIResult<()> {
+        if !(thickness > 0.0 && thickness.is_finite()) {
+            return Err(JNIError::IllegalArgument(format!("invalid line thickness {}", thickness)));
+        }
+        let delta = [to[0] - from[0], to[1] - from[1]];
+        let length = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt();
+        if length <= f32::EPSILON {
+            return Ok(());
+        }
+        let center = [(from[0] + to[0]) * 0.5, (from[1] + to[1]) * 0.5];
+        self.push_feathered_quad(device, queue, center, [delta[0] / length, delta[1] / length], [length * 0.5, thickness * 0.5], color)
+    }
+
+    // connected lines with mitered joins, `closed` also connects the last point to the first
+    #[allow(clippy::too_many_arguments)]
+    pub fn cmd_draw_polyline(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, points: &[[f32; 2]], thickness: f32, closed: bool, color: u32) -> JNIResult<()> {
+        // sharper joins are cut off at this many times the thickness
+        const MITER_LIMIT: f32 = 4.0;
+        if !(thickness > 0.0 && thickness.is_finite()) {
+            return Err(JNIError::IllegalArgument(format!("invalid line thickness {}", thickness)));
+        }
+        let mut points = points.to_vec();
+        points.dedup_by(|b, a| (a[0] - b[0]).abs() <= f32::EPSILON && (a[1] - b[1]).abs() <= f32::EPSILON);
+        let closed = closed && points.len() > 2;
+        if closed && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 2 {
+            return Ok(());
+        }
+        let (texture, uv) = self.glyph_cache.white_texel(device, queue)?;
+        let (inner, outer, coverage) = feather(thickness * 0.5);
+        let color = vertex_color(color);
+        let core_color = scale_alpha(color, coverage);
+        let edge_color = scale_alpha(color, 0.0);
+
+        let count = points.len();
+        let normal = |from: [f32; 2], to: [f32; 2]| {
+            let delta = [to[0] - from[0], to[1] - from[1]];
+            let length = (delta[0] * delta[0] + delta[1] * delta[1]).sqrt();
+            [-delta[1] / length, delta[0] / length]
+        };
+        let mut vertices = Vec::with_capacity(count * 4);
+        for index in 0..count {
+            let previous = (index > 0 || closed).then(|| normal(points[(index + count - 1) % count], points[index]));
+            let next = (index + 1 < count || closed).then(|| normal(points[index], points[(index + 1) % count]));
+            let offset = match (previous, next) {
+                (Some(previous), Some(next)) => {
+                    let sum = [previous[0] + next[0], previous[1] + next[1]];
+                    let length = (sum[0] * sum[0] + sum[1] * sum[1]).sqrt();
+                    if length <= f32::EPSILON {
+                        // the line turns back on itself
+                        next
+                    } else {
+                        let miter = [sum[0] / length, sum[1] / length];
+                        let scale = 1.0 / (miter[0] * next[0] + miter[1] * next[1]).max(1.0 / MITER_LIMIT);
+                        [miter[0] * scale, miter[1] * scale]
+                    }
+                },
+                (Some(normal), None) | (None, Some(normal)) => normal,
+                (None, None) => unreachable!()
+            };
+            let point = points[index];
+            for (distance, color) in [(-outer, edge_color), (-inner, core_color), (inner, core_color), (outer, edge_color)] {
+                vertices.push(PositionTexCoord {
+                    pos: [point[0] + offset[0] * distance, point[1] + offset[1] * distance],
+                    uv,
+                    color,
+                    texture: 0
+                });
+            }
+        }
+        let segments = if closed { count } else { count - 1 };
+        let mut indices = Vec::with_capacity(segments * 18);
+        for segment in 0..segments {
+            let a = (segment * 4) as u32;
+            let b = (((segment + 1) % count) * 4) as u32;
+            // the left feather, the core and the right feather
+            for strip in 0..3 {
+                indices.extend_from_slice(&[a + strip, a + strip + 1, b + strip + 1, a + strip, b + strip + 1, b + strip]);
+            }
+        }
+        self.push_geometry(&texture, 0, &vertices, &indices)
+    }
+
+    // a quad around `center` spanning `half` along `axis` and its normal, with a one pixel wide fading edge
+    fn push_feathered_quad(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, center: [f32; 2], axis: [f32; 2], half: [f32; 2], color: u32) -> JNIResult<()> {
+        let (texture, uv) = self.glyph_cache.white_texel(device, queue)?;
+        let (inner_x, outer_x, coverage_x) = feather(half[0]);
+        let (inner_y, outer_y, coverage_y) = feather(half[1]);
+        let color = vertex_color(color);
+        let core_color = scale_alpha(color, coverage_x * coverage_y);
+        let edge_color = scale_alpha(color, 0.0);
+        let normal = [-axis[1], axis[0]];
+        let corner = |x: f32, y: f32, color: u32| PositionTexCoord {
+            pos: [center[0] + axis[0] * x + normal[0] * y, center[1] + axis[1] * x + normal[1] * y],
+            uv,
+            color,
+            texture: 0
+        };
+        let vertices = [
+            corner(-inner_x, -inner_y, core_color),
+            corner(inner_x, -inner_y, core_color),
+            corner(inner_x, inner_y, core_color),
+            corner(-inner_x, inner_y, core_color),
+            corner(-outer_x, -outer_y, edge_color),
+            corner(outer_x, -outer_y, edge_color),
+            corner(outer_x, outer_y, edge_color),
+            corner(-outer_x, outer_y, edge_color),
         ];
-        current_group.index_count += 6;
-        current_group.cursor_index += 4;
-        
-        //self.immediate_vertex_buffer.as_ref().unwrap().slice(current_group.vertex_offset_end..(current_group.vertex_offset_end + request_vertex_buffer_size + 1)).get_mapped_range_mut().copy_from_slice(bytemuck::cast_slice(vertex_data));
-        //queue.write_buffer(&self.immediate_vertex_buffer.as_ref().unwrap(), current_group.vertex_offset_end, bytemuck::cast_slice(vertex_data));
-        //queue.write_buffer(&self.immediate_index_buffer.as_ref().unwrap(), current_group.index_offset_end, bytemuck::cast_slice(index_data));
-        current_group.vertex_shadow_data.extend_from_slice(bytemuck::cast_slice(vertex_data));
-        current_group.index_shadow_data.extend_from_slice(bytemuck::cast_slice(index_data));
-        current_group.vertex_offset_end += request_vertex_buffer_size;
-        current_group.index_offset_end += request_index_buffer_size;
+        let mut indices = Vec::with_capacity(30);
+        indices.extend_from_slice(&QUAD_INDICES);
+        for edge in 0..4 {
+            let next = (edge + 1) % 4;
+            indices.extend_from_slice(&[edge, next, next + 4, edge, next + 4, edge + 4]);
+        }
+        self.push_geometry(&texture, 0, &vertices, &indices)
+    }
+
+    // the uv rect of an atlas image must not be handed out again while a draw still samples it
+    fn keep_sub_texture(&mut self, sub_texture: &Arc<AtlasSubTexture>) {
+        let kept = match &mut self.recording {
+            Some(recording) => &mut recording.sub_textures,
+            None => &mut self.sub_textures
+        };
+        if !kept.last().is_some_and(|last| Arc::ptr_eq(last, sub_texture)) {
+            kept.push(sub_texture.clone());
+        }
+    }
+
+    // draws an image packed into a texture atlas, `uv` is relative to the image
+    pub fn cmd_draw_sub_texture(&mut self, sub_texture: &Arc<AtlasSubTexture>, uv: &Rect, pos: &Rect, tint_color: u32) -> JNIResult<()> {
+        self.keep_sub_texture(sub_texture);
+        let (texture, page_uv) = sub_texture.resolve();
+        let page_size = page_uv.size();
+        let uv = Rect {
+            min: [page_uv.min[0] + uv.min[0] * page_size[0], page_uv.min[1] + uv.min[1] * page_size[1]],
+            max: [page_uv.min[0] + uv.max[0] * page_size[0], page_uv.min[1] + uv.max[1] * page_size[1]]
+        };
+        self.cmd_draw_texture(&texture, 0, &uv, pos, tint_color)
+    }
 
+    // draws text with its first line starting at the top left corner `pos`, `size` is the em size in pixels
+    #[allow(clippy::too_many_arguments)]
+    pub fn cmd_draw_text(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, font: &Arc<FontResource>, size: f32, pos: [f32; 2], color: u32, text: &str) -> JNIResult<()> {
+        let layout = layout_text(font, &TextLayoutDesc::new(size), text)?;
+        self.cmd_draw_text_layout(device, queue, &layout, pos, color)
+    }
+
+    // draws a layout with its top left corner at `pos`. glyphs come from the glyph atlas, so text batches with
+    // other draws into the same atlas page.
+    pub fn cmd_draw_text_layout(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &TextLayout, pos: [f32; 2], color: u32) -> JNIResult<()> {
+        if let Some(recording) = &mut self.recording {
+            for font in &layout.fonts {
+                if !recording.fonts.iter().any(|recorded| Arc::ptr_eq(recorded, font)) {
+                    recording.fonts.push(font.clone());
+                }
+            }
+        }
+        for glyph in &layout.glyphs {
+            let font = &layout.fonts[glyph.font as usize];
+            if let Some(quad) = self.glyph_cache.glyph(device, queue, font, glyph.glyph, layout.font_size)? {
+                // snap to whole pixels, glyphs are rasterized at the origin
+                let origin = [(pos[0] + glyph.pos[0]).round(), (pos[1] + glyph.pos[1]).round()];
+                let rect = Rect {
+                    min: [origin[0] + quad.rect.min[0], origin[1] + quad.rect.min[1]],
+                    max: [origin[0] + quad.rect.max[0], origin[1] + quad.rect.max[1]]
+                };
+                self.cmd_draw_texture(&quad.texture, 0, &quad.uv, &rect, color)?;
+            }
+        }
+        Ok(())
     }
 
     pub fn new(
         device: &wgpu::Device,
-        surface: &wgpu::SurfaceConfiguration
+        surface: &wgpu::SurfaceConfiguration,
+        memory: &Arc<GpuMemoryTracker>
     ) -> UserInterface {
         let gui_per_frame_size = mem::size_of::<GuiTexturePerFrameUniform>() as wgpu::BufferAddress;
 
@@ -375,22 +1659,30 @@ impl UserInterface {
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("gui_texture.wgsl"))),
         });
         
-        let tile_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("wrap sampler"),
-            address_mode_u: wgpu::AddressMode::Repeat,
-            address_mode_v: wgpu::AddressMode::Repeat,
-            address_mode_w: wgpu::AddressMode::Repeat,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
+        let tile_sampler = Arc::new(SamplerResource {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("wrap sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                ..Default::default()
+            }),
+            comparison: false
         });
-        let default_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("default sampler"),
-            ..Default::default()
+        let default_sampler = Arc::new(SamplerResource {
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("default sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            comparison: false
         });
 
-        let gui_texture_const_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { 
+        let gui_sampler_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor { 
             label: None,
             entries: &[
                 wgpu::BindGroupLayoutEntry {
@@ -398,12 +1690,6 @@ impl UserInterface {
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
                 }
             ]
         });
@@ -416,7 +1702,7 @@ impl UserInterface {
                     visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
+                        has_dynamic_offset: true,
                         min_binding_size: wgpu::BufferSize::new(gui_per_frame_size)
                     },
                     count: None,
@@ -433,67 +1719,47 @@ impl UserInterface {
                 }
             ],
         });
-        let gui_texture_const_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        let gui_texture_3d_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
             entries: &[
-                wgpu::BindGroupEntry {
+                wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    resource: wgpu::BindingResource::Sampler(&default_sampler),
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(gui_per_frame_size)
+                    },
+                    count: None,
                 },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&tile_sampler),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D3,
+                    },
+                    count: None,
                 }
             ],
-            layout: &gui_texture_const_bind_group_layout,
-            label: Some("texture bind group const"),
         });
-        
         let gui_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&gui_texture_const_bind_group_layout, &gui_texture_bind_group_layout],
+            bind_group_layouts: &[&gui_sampler_bind_group_layout, &gui_texture_bind_group_layout],
             push_constant_ranges: &[],
         });
-
-        let gui_texture_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("gui texture pipeline"),
-            layout: Some(&gui_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &gui_texture_shader ,
-                entry_point: "vs_main",
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: 20,
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4],
-                    },
-                ]
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &gui_texture_shader ,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface.format.into(),
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::Zero,
-                            dst_factor: wgpu::BlendFactor::One,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                    }),
-                    write_mask: wgpu::ColorWrites::RED | wgpu::ColorWrites::GREEN | wgpu::ColorWrites::BLUE
-                })],
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
-            depth_stencil: None 
+        let gui_3d_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&gui_sampler_bind_group_layout, &gui_texture_3d_bind_group_layout],
+            push_constant_ranges: &[wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..mem::size_of::<GuiTexturePushConstants>() as u32
+            }],
         });
 
+        let texture_array = UITextureArray::new(device, &gui_sampler_bind_group_layout, gui_per_frame_size);
+
         let frame_uniform = device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
             size: gui_per_frame_size,
@@ -503,22 +1769,261 @@ impl UserInterface {
 
 
         UserInterface {
-            crop: None,
-            gui_texture_const_group,
+            state: UIDrawState::default(),
+            screen_state: None,
+            surface_format: surface.format,
+            gui_sampler_bind_group_layout,
             frame_uniform,
-            immediate_vertex_buffer: None,
-            immediate_index_buffer: None,
-            vertex_buffer_offset: 0,
-            index_buffer_offset: 0,
-            gui_texture_pipeline,
+            frame_uniform_generation: 0,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            vertex_buffer: UIGeometryBuffer::new(VERTEX_BUFFER_INITIAL_SIZE, wgpu::BufferUsages::VERTEX, "ui vertex buffer"),
+            index_buffer: UIGeometryBuffer::new(INDEX_BUFFER_INITIAL_SIZE, wgpu::BufferUsages::INDEX, "ui index buffer"),
+            instances: vec![UIInstance::zeroed()],
+            instance_buffer: UIGeometryBuffer::new(INSTANCE_BUFFER_INITIAL_SIZE, wgpu::BufferUsages::VERTEX, "ui instance buffer"),
+            recording: None,
+            bundled_lists: Vec::new(),
+            sub_textures: Vec::new(),
+            memory: memory.clone(),
+            frame_uniform_memory: memory.track(MemoryCategory::UIScratch, "ui frame uniform", gui_per_frame_size),
+            gui_texture_shader,
+            gui_pipeline_layout,
+            gui_3d_pipeline_layout,
+            pipelines: HashMap::new(),
+            texture_array,
             tile_sampler,
             default_sampler,
+            sampler: None,
+            blend: UIBlendMode::Alpha,
             gui_texture_bind_group_layout,
+            gui_texture_3d_bind_group_layout,
             textures: smallvec::SmallVec::new(),
-            draw_groups: Vec::new(),
+            samplers: smallvec::SmallVec::new(),
+            passes: vec![UIPass {
+                target: None,
+                draw_groups: Vec::new()
+            }],
+            current_pass: 0,
+            glyph_cache: GlyphCache::new(memory),
         }
     }
 
 }
 
+// the dimension of the view the ui samples `layer` of `tex` through, fails for textures the ui can't draw
+fn ui_texture_dimension(tex: &TextureResource, layer: u32) -> JNIResult<wgpu::TextureViewDimension> {
+    let format = tex.texture.format();
+    // the ui samples through a filtering sampler, which rules out depth, integer and 32 bit float formats
+    if format.sample_type(None) != Some(wgpu::TextureSampleType::Float { filterable: true }) {
+        return Err(JNIError::IllegalArgument(format!("textures of format {:?} can't be drawn by the ui", format)));
+    }
+    let layers = tex.texture.depth_or_array_layers();
+    if layer >= layers {
+        return Err(JNIError::IllegalArgument(format!("layer {} out of range, texture has {} layers", layer, layers)));
+    }
+    match tex.view_dimension() {
+        // faces of a cubemap are drawn like any other layer
+        wgpu::TextureViewDimension::D2 | wgpu::TextureViewDimension::D2Array |
+            wgpu::TextureViewDimension::Cube | wgpu::TextureViewDimension::CubeArray => Ok(wgpu::TextureViewDimension::D2),
+        wgpu::TextureViewDimension::D3 => Ok(wgpu::TextureViewDimension::D3),
+        dimension => Err(JNIError::IllegalArgument(format!("{:?} textures can't be drawn by the ui", dimension)))
+    }
+}
+
+// the view the ui samples `layer` of `tex` through
+fn create_ui_texture_view(tex: &TextureResource, layer: u32) -> JNIResult<(wgpu::TextureViewDimension, wgpu::TextureView)> {
+    let dimension = ui_texture_dimension(tex, layer)?;
+    let view = match dimension {
+        wgpu::TextureViewDimension::D3 => tex.texture.create_view(&wgpu::TextureViewDescriptor::default()),
+        _ => tex.texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        })
+    };
+    Ok((dimension, view))
+}
+
+// bundles bind every texture on its own, also when the device supports texture arrays
+fn bundle_variant(dimension: wgpu::TextureViewDimension) -> UIPipelineVariant {
+    match dimension {
+        wgpu::TextureViewDimension::D3 => UIPipelineVariant::Texture3D,
+        _ => UIPipelineVariant::Texture2D
+    }
+}
+
+// `vertices` moved by `transform`, sampling from `slot` of the texture binding array
+fn transform_vertices(transform: Affine2, slot: u32, vertices: &[PositionTexCoord]) -> impl Iterator<Item = PositionTexCoord> + '_ {
+    vertices.iter().map(move |vertex| PositionTexCoord {
+        pos: transform.transform_point2(Vec2::from(vertex.pos)).to_array(),
+        texture: slot,
+        ..*vertex
+    })
+}
+
+// the crop clamped to the attachment in texels, None if nothing is left to draw
+fn scissor_rect(crop: Option<Rect>, bounds: &Rect) -> Option<[u32; 4]> {
+    let rect = crop.unwrap_or(*bounds);
+    let min_x = rect.min[0].max(bounds.min[0]).max(0.0) as u32;
+    let min_y = rect.min[1].max(bounds.min[1]).max(0.0) as u32;
+    let max_x = rect.max[0].min(bounds.max[0]).max(0.0) as u32;
+    let max_y = rect.max[1].min(bounds.max[1]).max(0.0) as u32;
+    if max_x <= min_x || max_y <= min_y {
+        return None;
+    }
+    Some([min_x, min_y, max_x - min_x, max_y - min_y])
+}
+
+fn create_gui_texture_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    layout: &wgpu::PipelineLayout,
+    fragment_entry: &str,
+    format: wgpu::TextureFormat,
+    blend: UIBlendMode,
+    write_alpha: bool
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("gui texture pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[
+                wgpu::VertexBufferLayout {
+                    array_stride: mem::size_of::<PositionTexCoord>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4, 3 => Uint32],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: mem::size_of::<UIInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![4 => Float32x2, 5 => Uint32],
+                },
+            ]
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: blend.blend_state(),
+                write_mask: if write_alpha { wgpu::ColorWrites::ALL } else { wgpu::ColorWrites::COLOR }
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        depth_stencil: None 
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slice(tile: bool) -> NineSlice {
+        NineSlice {
+            insets: [0.0; 4],
+            scale: 1.0,
+            tile
+        }
+    }
+
+    #[test]
+    fn nine_slice_axis_stretches() {
+        let [start, middle, end] = nine_slice_axis([0.0, 100.0], [0.0, 1.0], [10.0, 10.0], 40.0, &slice(false));
+        assert_eq!(start, [([0.0, 10.0], [0.0, 0.25])]);
+        assert_eq!(middle, [([10.0, 90.0], [0.25, 0.75])]);
+        assert_eq!(end, [([90.0, 100.0], [0.75, 1.0])]);
+    }
+
+    #[test]
+    fn nine_slice_axis_tiles() {
+        let [_, middle, _] = nine_slice_axis([0.0, 90.0], [0.0, 1.0], [10.0, 10.0], 40.0, &slice(true));
+        // the last tile is cut off
+        assert_eq!(middle, [
+            ([10.0, 30.0], [0.25, 0.75]),
+            ([30.0, 50.0], [0.25, 0.75]),
+            ([50.0, 70.0], [0.25, 0.75]),
+            ([70.0, 80.0], [0.25, 0.5])
+        ]);
+    }
+
+    #[test]
+    fn nine_slice_axis_flipped_uv() {
+        let [start, middle, end] = nine_slice_axis([0.0, 100.0], [1.0, 0.0], [10.0, 10.0], 40.0, &slice(false));
+        assert_eq!(start, [([0.0, 10.0], [1.0, 0.75])]);
+        assert_eq!(middle, [([10.0, 90.0], [0.75, 0.25])]);
+        assert_eq!(end, [([90.0, 100.0], [0.25, 0.0])]);
+    }
+
+    #[test]
+    fn nine_slice_axis_shrinks_insets_that_exceed_the_size() {
+        let [start, middle, end] = nine_slice_axis([0.0, 10.0], [0.0, 1.0], [10.0, 30.0], 40.0, &slice(false));
+        // the borders keep their ratio and the center disappears
+        assert_eq!(start, [([0.0, 2.5], [0.0, 0.25])]);
+        assert!(middle.is_empty());
+        assert_eq!(end, [([2.5, 10.0], [0.25, 1.0])]);
+
+        let scaled = NineSlice { scale: 2.0, ..slice(true) };
+        let [start, middle, end] = nine_slice_axis([0.0, 30.0], [0.0, 1.0], [10.0, 10.0], 40.0, &scaled);
+        assert_eq!(start, [([0.0, 15.0], [0.0, 0.25])]);
+        assert!(middle.is_empty());
+        assert_eq!(end, [([15.0, 30.0], [0.75, 1.0])]);
+    }
+
+    #[test]
+    fn nine_slice_axis_empty_size() {
+        for pos in [[5.0, 5.0], [10.0, 0.0]] {
+            for insets in [[0.0, 0.0], [10.0, 10.0]] {
+                let spans = nine_slice_axis(pos, [0.0, 1.0], insets, 40.0, &slice(true));
+                assert!(spans.iter().all(|span| span.is_empty()), "{:?} {:?}", pos, insets);
+            }
+        }
+    }
+
+    fn rect(min: [f32; 2], max: [f32; 2]) -> Rect {
+        Rect { min, max }
+    }
+
+    #[test]
+    fn crop_stack_intersects_pushed_crops() {
+        let mut state = UIDrawState::default();
+        state.push_crop(rect([0.0, 0.0], [100.0, 100.0]));
+        state.push_crop(rect([50.0, 50.0], [150.0, 150.0]));
+        assert_eq!(state.crop, Some(rect([50.0, 50.0], [100.0, 100.0])));
+        state.push_crop(rect([200.0, 200.0], [300.0, 300.0]));
+        assert_eq!(state.crop, Some(Rect::zero()));
 
+        state.pop_crop().unwrap();
+        state.pop_crop().unwrap();
+        assert_eq!(state.crop, Some(rect([0.0, 0.0], [100.0, 100.0])));
+        state.pop_crop().unwrap();
+        assert_eq!(state.crop, None);
+        assert!(state.pop_crop().is_err());
+    }
+
+    #[test]
+    fn crop_stack_bounds_set_crop() {
+        let mut state = UIDrawState::default();
+        state.set_crop(Some(rect([0.0, 0.0], [100.0, 100.0])));
+        state.push_crop(rect([0.0, 0.0], [50.0, 50.0]));
+
+        state.set_crop(Some(rect([50.0, 50.0], [150.0, 150.0])));
+        assert_eq!(state.crop, Some(rect([50.0, 50.0], [100.0, 100.0])));
+        state.set_crop(Some(rect([200.0, 200.0], [300.0, 300.0])));
+        assert_eq!(state.crop, Some(Rect::zero()));
+        // removing the crop falls back to the crop the scope was pushed into
+        state.set_crop(None);
+        assert_eq!(state.crop, Some(rect([0.0, 0.0], [100.0, 100.0])));
+
+        // outside of a scope the crop is replaced
+        state.pop_crop().unwrap();
+        state.set_crop(Some(rect([200.0, 200.0], [300.0, 300.0])));
+        assert_eq!(state.crop, Some(rect([200.0, 200.0], [300.0, 300.0])));
+        state.set_crop(None);
+        assert_eq!(state.crop, None);
+    }
+}
@@ -2,6 +2,14 @@ use core::ffi::{c_void, c_ulong};
 
 use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle, Win32WindowHandle, WindowsDisplayHandle, XlibDisplayHandle, XlibWindowHandle};
 
+// enabled whenever the adapter has them. textures using them are rejected otherwise,
+// without texture binding arrays the ui binds one texture per draw call.
+const OPTIONAL_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_COMPRESSION_BC
+    .union(wgpu::Features::TEXTURE_COMPRESSION_ETC2)
+    .union(wgpu::Features::DEPTH32FLOAT_STENCIL8)
+    .union(wgpu::Features::TEXTURE_BINDING_ARRAY)
+    .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
 pub struct WindowSurface {
     pub surface: wgpu::Surface,
     pub adapter: wgpu::Adapter,
@@ -125,9 +133,9 @@ impl WindowSurface {
                 &wgpu::DeviceDescriptor {
                     label: None,
                     features: 
-                        wgpu::Features::TEXTURE_BINDING_ARRAY |
                         wgpu::Features::MAPPABLE_PRIMARY_BUFFERS |
-                        wgpu::Features::PUSH_CONSTANTS , 
+                        wgpu::Features::PUSH_CONSTANTS |
+                        (adapter.features() & OPTIONAL_FEATURES), 
                     // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
                     limits: {
                         let mut limits = wgpu::Limits::downlevel_webgl2_defaults()
@@ -136,6 +144,8 @@ impl WindowSurface {
                             adapter.limits().max_samplers_per_shader_stage;
                         limits.max_sampled_textures_per_shader_stage =
                             adapter.limits().max_sampled_textures_per_shader_stage;
+                        limits.max_push_constant_size =
+                            adapter.limits().max_push_constant_size;
                         limits
                     },
                 },
@@ -184,9 +194,9 @@ impl WindowSurface {
                 &wgpu::DeviceDescriptor {
                     label: None,
                     features: 
-                        wgpu::Features::TEXTURE_BINDING_ARRAY |
                         wgpu::Features::MAPPABLE_PRIMARY_BUFFERS |
-                        wgpu::Features::PUSH_CONSTANTS , 
+                        wgpu::Features::PUSH_CONSTANTS |
+                        (adapter.features() & OPTIONAL_FEATURES), 
                     // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
                     limits: {
                         let mut limits = wgpu::Limits::downlevel_webgl2_defaults()
@@ -195,6 +205,8 @@ impl WindowSurface {
                             adapter.limits().max_samplers_per_shader_stage;
                         limits.max_sampled_textures_per_shader_stage =
                             adapter.limits().max_sampled_textures_per_shader_stage;
+                        limits.max_push_constant_size =
+                            adapter.limits().max_push_constant_size;
                         limits
                     },
                 },
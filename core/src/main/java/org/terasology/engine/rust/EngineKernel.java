@@ -3,6 +3,8 @@
 
 package org.terasology.engine.rust;
 
+import org.joml.Quaternionfc;
+
 import java.lang.ref.Cleaner;
 
 public final class EngineKernel implements Disposable {
@@ -12,6 +14,7 @@ public final class EngineKernel implements Disposable {
     private final Cleaner.Cleanable cleanable;
     public final UIRenderer ui;
     public final ResourceManager resource;
+    public final GpuMemory memory;
 
 
     public static final class EngineKernelBuild {
@@ -20,30 +23,40 @@ public final class EngineKernel implements Disposable {
         private int windowType;
 
         public enum WindowType {
-            Win32,
-            X11
+            Win32(0),
+            X11(1);
+
+            final int code;
+
+            WindowType(int code) {
+                this.code = code;
+            }
         }
 
         public EngineKernelBuild configureX11Window(long windowHandle, long displayHandle) {
-            this.windowType = WindowType.X11.ordinal();
+            this.windowType = WindowType.X11.code;
             this.displayHandle = displayHandle;
             this.windowHandle = windowHandle;
             return this;
         }
 
         public EngineKernelBuild configureWin32Window(long windowHandle, long displayHandle) {
-            this.windowType = WindowType.Win32.ordinal();
+            this.windowType = WindowType.Win32.code;
             this.displayHandle = displayHandle;
             this.windowHandle = windowHandle;
             return this;
         }
     }
 
+    /**
+     * @throws IllegalArgumentException if no window was configured or the window handles are null
+     */
     public EngineKernel(EngineKernelBuild builder) {
         long kernelPtr = JNI.create(builder);
         rustKernelPtr = kernelPtr;
         this.ui = new UIRenderer(this);
         this.resource = new ResourceManager(this);
+        this.memory = new GpuMemory(this);
         this.cleanable = CLEANER.register(this, () -> {
             JNI.drop(kernelPtr);
         });
@@ -60,8 +73,39 @@ public final class EngineKernel implements Disposable {
     public void cmdPrepare() {
         JNI.cmdPrepare(rustKernelPtr);
     }
+    /**
+     * Draws a skybox behind the UI for the current frame. The camera looks down -z with y up.
+     *
+     * @param cubemap a texture created with {@link TeraTexture.TextureDimension#CUBE}
+     * @param rotation the rotation of the camera in world space, the inverse of the rotation of the view matrix
+     * @param fovY the vertical field of view in radians
+     * @param tintColor RGBA color the skybox is multiplied with
+     * @throws IllegalArgumentException if the texture isn't a cube texture or the field of view is invalid
+     */
+    public void cmdDrawSkybox(TeraTexture cubemap, Quaternionfc rotation, float fovY, int tintColor) {
+        cmdDrawSkybox(cubemap, 0, rotation, fovY, tintColor);
+    }
+
+    /**
+     * Draws one cube of a cube array as the skybox, see {@link #cmdDrawSkybox(TeraTexture, Quaternionfc, float, int)}.
+     */
+    public void cmdDrawSkybox(TeraTexture cubemap, int cube, Quaternionfc rotation, float fovY, int tintColor) {
+        JNI.cmdDrawSkybox(rustKernelPtr, cubemap.rustTexturePtr, cube,
+                rotation.x(), rotation.y(), rotation.z(), rotation.w(), fovY, tintColor);
+    }
+
+    /**
+     * Draws and presents the frame.
+     *
+     * @throws IllegalStateException if a UI crop or transform was pushed but never popped, or a UI command list was
+     *                               never ended. The frame is presented anyway.
+     */
     public void cmdDispatch() {
-        JNI.cmdDispatch(rustKernelPtr);
+        try {
+            JNI.cmdDispatch(rustKernelPtr);
+        } finally {
+            memory.dispatchBudgetEvents();
+        }
     }
 
     @Override
@@ -77,6 +121,8 @@ public final class EngineKernel implements Disposable {
         private static native void resizeSurface(long kernel, int width, int height);
         private static native void cmdPrepare(long kernel);
         private static native void cmdDispatch(long kernel);
+        private static native void cmdDrawSkybox(long kernel, long texture, int cube,
+                                                 float rotX, float rotY, float rotZ, float rotW, float fovY, int tintColor);
 
 
     }
@@ -0,0 +1,136 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import java.util.ArrayList;
+import java.util.List;
+import java.util.concurrent.CopyOnWriteArrayList;
+
+/**
+ * Accounts for the GPU memory of every texture, buffer and UI scratch allocation. Sizes are what the resources need
+ * for their data, drivers add their own padding and alignment on top.
+ */
+public class GpuMemory {
+    private final EngineKernel kernel;
+    private final List<BudgetListener> listeners = new CopyOnWriteArrayList<>();
+    private volatile EvictionHandler evictionHandler;
+
+    public GpuMemory(EngineKernel kernel) {
+        this.kernel = kernel;
+    }
+
+    public enum Category {
+        TEXTURE(0),
+        RENDER_TARGET(1),
+        ATLAS(2),
+        BUFFER(3),
+        UI_SCRATCH(4);
+
+        final int code;
+
+        Category(int code) {
+            this.code = code;
+        }
+    }
+
+    public static final class Allocation {
+        public final long id;
+        public final Category category;
+        public final long size;
+        public final String label;
+
+        Allocation(long id, int category, long size, String label) {
+            this.id = id;
+            this.category = Category.values()[category];
+            this.size = size;
+            this.label = label;
+        }
+    }
+
+    /**
+     * Notified once when a category goes over its budget, and again only after it dropped back below it.
+     */
+    public interface BudgetListener {
+        void onOverBudget(Category category, long used, long budget);
+    }
+
+    /**
+     * Asked to free memory when a category went over its budget, like {@link BudgetListener} only once per overrun.
+     * It runs at the end of the frame on the thread that calls {@link EngineKernel#cmdDispatch()}, before the budget
+     * listeners and after the frame was submitted, so it may dispose and create resources. Exceptions propagate out
+     * of {@code cmdDispatch}.
+     */
+    public interface EvictionHandler {
+        void evict(Category category, long used, long budget);
+    }
+
+    public long getTotal(Category category) {
+        return JNI.getTotal(kernel.rustKernelPtr, category.code);
+    }
+
+    public long getTotal() {
+        long total = 0;
+        for (Category category : Category.values()) {
+            total += getTotal(category);
+        }
+        return total;
+    }
+
+    /**
+     * Every live allocation in the order they were made.
+     */
+    public List<Allocation> getAllocations() {
+        List<Allocation> allocations = new ArrayList<>();
+        JNI.getAllocations(kernel.rustKernelPtr, allocations);
+        return allocations;
+    }
+
+    /**
+     * Sets a soft budget in bytes. Going over it logs a warning, the eviction handler and the budget listeners are
+     * called at the end of the frame.
+     */
+    public void setBudget(Category category, long bytes) {
+        JNI.setBudget(kernel.rustKernelPtr, category.code, bytes);
+    }
+
+    public void clearBudget(Category category) {
+        JNI.setBudget(kernel.rustKernelPtr, category.code, -1);
+    }
+
+    /**
+     * Replaces the eviction handler, null removes it.
+     */
+    public void setEvictionHandler(EvictionHandler handler) {
+        evictionHandler = handler;
+    }
+
+    public void addBudgetListener(BudgetListener listener) {
+        listeners.add(listener);
+    }
+
+    public void removeBudgetListener(BudgetListener listener) {
+        listeners.remove(listener);
+    }
+
+    void dispatchBudgetEvents() {
+        long[] events = JNI.takeBudgetEvents(kernel.rustKernelPtr);
+        for (int i = 0; i < events.length; i += 3) {
+            Category category = Category.values()[(int) events[i]];
+            EvictionHandler handler = evictionHandler;
+            if (handler != null) {
+                handler.evict(category, events[i + 1], events[i + 2]);
+            }
+            for (BudgetListener listener : listeners) {
+                listener.onOverBudget(category, events[i + 1], events[i + 2]);
+            }
+        }
+    }
+
+    private static final class JNI {
+        public static native long getTotal(long kernel, int category);
+        public static native void getAllocations(long kernel, List<Allocation> allocations);
+        public static native void setBudget(long kernel, int category, long budget);
+        public static native long[] takeBudgetEvents(long kernel);
+    }
+}
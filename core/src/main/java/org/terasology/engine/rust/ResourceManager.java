@@ -3,21 +3,99 @@
 
 package org.terasology.engine.rust;
 
+import java.util.concurrent.CompletableFuture;
+
 public class ResourceManager {
     private final EngineKernel kernel;
     public ResourceManager(EngineKernel kernel) {
         this.kernel = kernel;
     }
 
+    /**
+     * @throws IllegalArgumentException if the descriptor is invalid, exceeds the device limits or uses a format or
+     * usage the device doesn't support
+     */
     public TeraTexture createTexture(TeraTexture.TextureDesc desc) {
         return new TeraTexture(this.kernel, ResourceManager.JNI.createTextureResource(this.kernel.rustKernelPtr, desc));
     }
+
+    /**
+     * @param buffer every mip level of the first layer, then every mip level of the next layer, tightly packed
+     * @throws IllegalArgumentException if the descriptor is invalid, see {@link #createTexture(TeraTexture.TextureDesc)},
+     * the buffer is smaller than the layers and mip levels of the descriptor or the format is a depth or stencil format
+     */
     public TeraTexture createTexture(TeraTexture.TextureDesc desc, java.nio.ByteBuffer buffer) {
         return new TeraTexture(this.kernel, ResourceManager.JNI.createTextureResourceFromBuffer(this.kernel.rustKernelPtr, desc, buffer));
     }
 
+    /**
+     * Creates the texture right away and uploads its contents over the next frames, see
+     * {@link TeraTexture#writeTextureBufferAsync}. The future completes once the contents are on the gpu.
+     */
+    public CompletableFuture<TeraTexture> createTextureAsync(TeraTexture.TextureDesc desc, java.nio.ByteBuffer buffer) {
+        TeraTexture texture = createTexture(desc);
+        return texture.writeTextureBufferAsync(buffer).thenApply(ignored -> texture);
+    }
+
+    /**
+     * Limits how many bytes of queued uploads are copied to the gpu per frame. A single upload larger than the budget
+     * still goes through, it just gets the frame to itself.
+     */
+    public void setUploadBudget(long bytesPerFrame) {
+        ResourceManager.JNI.setUploadBudget(this.kernel.rustKernelPtr, bytesPerFrame);
+    }
+
+    /**
+     * Whether textures of the format can be created on this device. Creating a texture with an unsupported format
+     * throws an {@link IllegalArgumentException}.
+     */
+    public boolean isFormatSupported(TeraTexture.ImageFormat format) {
+        return ResourceManager.JNI.isFormatSupported(this.kernel.rustKernelPtr, format.code);
+    }
+
+    /**
+     * Creates a texture from an encoded PNG, JPEG, TGA, DDS or KTX2 file. The container is detected from the data,
+     * mip levels and array layers stored in DDS and KTX2 files are uploaded as well.
+     *
+     * @param buffer a direct buffer holding the whole file
+     * @throws IllegalArgumentException if the file can't be decoded or its pixel format isn't supported
+     */
+    public TeraTexture createTextureFromEncoded(java.nio.ByteBuffer buffer, TeraTexture.DecodeHints hints) {
+        return new TeraTexture(this.kernel, ResourceManager.JNI.createTextureResourceFromEncoded(this.kernel.rustKernelPtr, hints, buffer));
+    }
+
+    /**
+     * @throws IllegalArgumentException if the page sizes or mip levels are invalid
+     */
+    public TeraAtlas createAtlas(TeraAtlas.AtlasDesc desc) {
+        return new TeraAtlas(this.kernel, ResourceManager.JNI.createTextureAtlas(this.kernel.rustKernelPtr, desc));
+    }
+
+    /**
+     * @throws IllegalArgumentException if the anisotropy or lod clamp is invalid
+     */
+    public TeraSampler createSampler(TeraSampler.SamplerDesc desc) {
+        return new TeraSampler(ResourceManager.JNI.createSampler(this.kernel.rustKernelPtr, desc));
+    }
+
+    /**
+     * Loads a TTF or OTF font, the contents of the buffer are copied.
+     *
+     * @param buffer a direct buffer holding the whole font file
+     * @throws IllegalArgumentException if the font can't be parsed
+     */
+    public TeraFont createFont(java.nio.ByteBuffer buffer) {
+        return new TeraFont(ResourceManager.JNI.createFont(this.kernel.rustKernelPtr, buffer));
+    }
+
     private static class JNI {
         public static native long createTextureResourceFromBuffer(long kernelPtr, TeraTexture.TextureDesc desc, java.nio.ByteBuffer buffer);
         public static native long createTextureResource(long kernelPtr,  TeraTexture.TextureDesc desc);
+        public static native long createTextureAtlas(long kernelPtr, TeraAtlas.AtlasDesc desc);
+        public static native long createTextureResourceFromEncoded(long kernelPtr, TeraTexture.DecodeHints hints, java.nio.ByteBuffer buffer);
+        public static native long createSampler(long kernelPtr, TeraSampler.SamplerDesc desc);
+        public static native void setUploadBudget(long kernelPtr, long bytesPerFrame);
+        public static native boolean isFormatSupported(long kernelPtr, int format);
+        public static native long createFont(long kernelPtr, java.nio.ByteBuffer buffer);
     }
 }
@@ -0,0 +1,83 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import java.lang.ref.Cleaner;
+
+import static org.terasology.engine.rust.EngineKernel.CLEANER;
+
+/**
+ * Packs many small RGBA8 images into shared atlas pages so they can be drawn without switching textures.
+ * Pages grow when they fill up, and new pages are added once they reached their max size.
+ */
+public class TeraAtlas implements Disposable {
+    final long rustAtlasPtr;
+    private final Cleaner.Cleanable cleanable;
+    private final EngineKernel kernel;
+
+    TeraAtlas(EngineKernel kernel, long atlasPtr) {
+        this.kernel = kernel;
+        rustAtlasPtr = atlasPtr;
+        this.cleanable = CLEANER.register(this, () -> {
+            TeraAtlas.JNI.drop(atlasPtr);
+        });
+    }
+
+    public static final class AtlasDesc {
+        int pageSize = 512;
+        int maxPageSize = 4096;
+        int padding = 2;
+        int mipLevels = 1;
+        boolean srgb;
+
+        public AtlasDesc setPageSize(int pageSize) {
+            this.pageSize = pageSize;
+            return this;
+        }
+
+        public AtlasDesc setMaxPageSize(int maxPageSize) {
+            this.maxPageSize = maxPageSize;
+            return this;
+        }
+
+        /**
+         * The gutter of repeated edge texels around every image. Every mip level halves the gutter, so it should be
+         * at least {@code 2^(mipLevels - 1)} texels wide to keep images from bleeding into each other.
+         */
+        public AtlasDesc setPadding(int padding) {
+            this.padding = padding;
+            return this;
+        }
+
+        public AtlasDesc setMipLevels(int mipLevels) {
+            this.mipLevels = mipLevels;
+            return this;
+        }
+
+        public AtlasDesc setSrgb(boolean srgb) {
+            this.srgb = srgb;
+            return this;
+        }
+    }
+
+    /**
+     * Packs a tightly packed RGBA8 image into the atlas. The space is freed again once the sub texture is disposed.
+     *
+     * @throws IllegalArgumentException if the buffer is too small or the image doesn't fit into a page
+     */
+    public TeraSubTexture add(int width, int height, java.nio.ByteBuffer buffer) {
+        return new TeraSubTexture(JNI.add(kernel.rustKernelPtr, this.rustAtlasPtr, width, height, buffer));
+    }
+
+    @Override
+    public void dispose() {
+        this.cleanable.clean();
+    }
+
+    private static final class JNI {
+        private static native void drop(long rustPtr);
+
+        public static native long add(long kernelPtr, long atlasPtr, int width, int height, java.nio.ByteBuffer buffer);
+    }
+}
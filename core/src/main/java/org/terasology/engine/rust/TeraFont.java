@@ -0,0 +1,278 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import org.joml.Vector2f;
+
+import java.lang.ref.Cleaner;
+
+import static org.terasology.engine.rust.EngineKernel.CLEANER;
+
+/**
+ * A TTF or OTF font. Text is shaped with the OpenType tables of the font, so ligatures, kerning and complex scripts
+ * come out right, and right to left text is reordered with the Unicode bidi algorithm. Glyphs are rasterized into a
+ * shared glyph atlas the first time they are drawn at a size.
+ */
+public class TeraFont implements Disposable {
+    final long rustFontPtr;
+    private final Cleaner.Cleanable cleanable;
+
+    TeraFont(long fontPtr) {
+        rustFontPtr = fontPtr;
+        this.cleanable = CLEANER.register(this, () -> {
+            TeraFont.JNI.drop(fontPtr);
+        });
+    }
+
+    public enum TextAlign {
+        LEFT(0),
+        CENTER(1),
+        RIGHT(2),
+        /**
+         * Left for left to right paragraphs and right for right to left ones.
+         */
+        START(3),
+        END(4);
+
+        final int code;
+
+        TextAlign(int code) {
+            this.code = code;
+        }
+    }
+
+    public static final class TextLayoutDesc {
+        float size;
+        float maxWidth;
+        int maxLines;
+        boolean wrap;
+        boolean ellipsis;
+        int align = TextAlign.LEFT.code;
+
+        /**
+         * @param size the em size in pixels
+         */
+        public TextLayoutDesc(float size) {
+            this.size = size;
+        }
+
+        public TextLayoutDesc setSize(float size) {
+            this.size = size;
+            return this;
+        }
+
+        /**
+         * The width lines are wrapped or truncated at, 0 leaves lines unbounded. Lines are aligned within this width.
+         */
+        public TextLayoutDesc setMaxWidth(float maxWidth) {
+            this.maxWidth = maxWidth;
+            return this;
+        }
+
+        /**
+         * Lines past the limit are dropped, 0 keeps every line.
+         */
+        public TextLayoutDesc setMaxLines(int maxLines) {
+            this.maxLines = maxLines;
+            return this;
+        }
+
+        /**
+         * Breaks lines between words once they get wider than the max width. Words longer than a line are broken
+         * between characters.
+         */
+        public TextLayoutDesc setWrap(boolean wrap) {
+            this.wrap = wrap;
+            return this;
+        }
+
+        /**
+         * Ends lines that are wider than the max width, or are followed by dropped lines, with an ellipsis.
+         */
+        public TextLayoutDesc setEllipsis(boolean ellipsis) {
+            this.ellipsis = ellipsis;
+            return this;
+        }
+
+        public TextLayoutDesc setAlign(TextAlign align) {
+            this.align = align.code;
+            return this;
+        }
+    }
+
+    /**
+     * Glyph and line positions of laid out text, exactly as {@link UIRenderer#cmdUIDrawText} draws them. Positions
+     * are relative to the top left corner of the text, glyphs are placed with their pen position on the baseline.
+     * Character indices count UTF-16 code units, like the indices of the laid out string. The glyphs of a line are
+     * stored in visual order from left to right.
+     */
+    public static final class TextLayout {
+        float width;
+        float height;
+        float lineAdvance;
+        float[] glyphX;
+        float[] glyphY;
+        float[] glyphAdvance;
+        int[] glyphCharIndex;
+        int[] lineGlyphStart;
+        int[] lineCharStart;
+        float[] lineBaseline;
+        float[] lineX;
+        float[] lineWidth;
+
+        /**
+         * The width of the widest line without trailing whitespace.
+         */
+        public float getWidth() {
+            return width;
+        }
+
+        public float getHeight() {
+            return height;
+        }
+
+        public int getGlyphCount() {
+            return glyphX.length;
+        }
+
+        public float getGlyphX(int glyph) {
+            return glyphX[glyph];
+        }
+
+        /**
+         * The baseline the glyph sits on.
+         */
+        public float getGlyphY(int glyph) {
+            return glyphY[glyph];
+        }
+
+        public float getGlyphAdvance(int glyph) {
+            return glyphAdvance[glyph];
+        }
+
+        /**
+         * The character the glyph was made from, the glyphs of an ellipsis point at the first character they replace.
+         */
+        public int getGlyphCharIndex(int glyph) {
+            return glyphCharIndex[glyph];
+        }
+
+        public int getLineCount() {
+            return lineGlyphStart.length;
+        }
+
+        /**
+         * The first glyph of the line, the line ends where the next one starts.
+         */
+        public int getLineGlyphStart(int line) {
+            return lineGlyphStart[line];
+        }
+
+        public int getLineCharStart(int line) {
+            return lineCharStart[line];
+        }
+
+        public float getLineBaseline(int line) {
+            return lineBaseline[line];
+        }
+
+        /**
+         * Where the line starts after alignment.
+         */
+        public float getLineX(int line) {
+            return lineX[line];
+        }
+
+        public float getLineWidth(int line) {
+            return lineWidth[line];
+        }
+
+        /**
+         * The line at a height, clamped to the first and last line.
+         */
+        public int getLineAt(float y) {
+            int line = (int) Math.floor(y / lineAdvance);
+            return Math.max(0, Math.min(line, getLineCount() - 1));
+        }
+
+        /**
+         * The glyph closest to a position for placing a caret, the caret goes before the returned glyph. Returns the
+         * end of the line if the position is behind its last glyph.
+         */
+        public int getGlyphAt(float x, float y) {
+            int line = getLineAt(y);
+            int end = line + 1 < getLineCount() ? lineGlyphStart[line + 1] : getGlyphCount();
+            for (int glyph = lineGlyphStart[line]; glyph < end; glyph++) {
+                if (x < glyphX[glyph] + glyphAdvance[glyph] * 0.5f) {
+                    return glyph;
+                }
+            }
+            return end;
+        }
+    }
+
+    /**
+     * Sets the fonts searched in order for characters this font has no glyph for, like CJK or symbol fonts. A
+     * fallback's own fallbacks are searched right after it. Line metrics always come from this font.
+     *
+     * @throws IllegalArgumentException if this font would end up as its own fallback
+     */
+    public void setFallbacks(TeraFont... fallbacks) {
+        long[] fallbackPtrs = new long[fallbacks.length];
+        for (int i = 0; i < fallbacks.length; i++) {
+            fallbackPtrs[i] = fallbacks[i].rustFontPtr;
+        }
+        TeraFont.JNI.setFallbacks(this.rustFontPtr, fallbackPtrs);
+    }
+
+    /**
+     * The distance between the baselines of two lines.
+     *
+     * @param size the em size in pixels
+     * @throws IllegalArgumentException if the size isn't positive
+     */
+    public float getLineHeight(float size) {
+        return TeraFont.JNI.getLineHeight(this.rustFontPtr, size);
+    }
+
+    /**
+     * The size of the text without wrapping, see {@link #measure(TextLayoutDesc, String, Vector2f)}.
+     */
+    public Vector2f measure(float size, String text, Vector2f dest) {
+        return measure(new TextLayoutDesc(size), text, dest);
+    }
+
+    /**
+     * The width of the widest line and the height of all lines, from the top of the first line to the bottom of the
+     * last one.
+     *
+     * @throws IllegalArgumentException if the size, max width or max lines are invalid
+     */
+    public Vector2f measure(TextLayoutDesc desc, String text, Vector2f dest) {
+        TeraFont.JNI.measure(this.rustFontPtr, desc, text, dest);
+        return dest;
+    }
+
+    /**
+     * @throws IllegalArgumentException if the size, max width or max lines are invalid
+     */
+    public TextLayout layout(TextLayoutDesc desc, String text) {
+        TextLayout layout = new TextLayout();
+        TeraFont.JNI.layout(this.rustFontPtr, desc, text, layout);
+        return layout;
+    }
+
+    @Override
+    public void dispose() {
+        this.cleanable.clean();
+    }
+
+    private static final class JNI {
+        private static native void drop(long rustPtr);
+        private static native void setFallbacks(long fontPtr, long[] fallbackPtrs);
+        private static native float getLineHeight(long fontPtr, float size);
+        private static native void measure(long fontPtr, TextLayoutDesc desc, String text, Vector2f dest);
+        private static native void layout(long fontPtr, TextLayoutDesc desc, String text, TextLayout result);
+    }
+}
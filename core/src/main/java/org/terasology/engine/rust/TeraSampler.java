@@ -0,0 +1,140 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import java.lang.ref.Cleaner;
+
+import static org.terasology.engine.rust.EngineKernel.CLEANER;
+
+/**
+ * Describes how a texture is filtered and addressed when it is sampled.
+ */
+public class TeraSampler implements Disposable {
+    final long rustSamplerPtr;
+    private final Cleaner.Cleanable cleanable;
+
+    TeraSampler(long samplerPtr) {
+        rustSamplerPtr = samplerPtr;
+        this.cleanable = CLEANER.register(this, () -> {
+            TeraSampler.JNI.drop(samplerPtr);
+        });
+    }
+
+    public enum FilterMode {
+        NEAREST(0),
+        LINEAR(1);
+
+        final int code;
+
+        FilterMode(int code) {
+            this.code = code;
+        }
+    }
+
+    public enum AddressMode {
+        CLAMP_TO_EDGE(0),
+        REPEAT(1),
+        MIRROR_REPEAT(2);
+
+        final int code;
+
+        AddressMode(int code) {
+            this.code = code;
+        }
+    }
+
+    public enum CompareFunction {
+        NEVER(1),
+        LESS(2),
+        EQUAL(3),
+        LESS_EQUAL(4),
+        GREATER(5),
+        NOT_EQUAL(6),
+        GREATER_EQUAL(7),
+        ALWAYS(8);
+
+        final int code;
+
+        CompareFunction(int code) {
+            this.code = code;
+        }
+    }
+
+    public static final class SamplerDesc {
+        int magFilter = FilterMode.LINEAR.code;
+        int minFilter = FilterMode.LINEAR.code;
+        int mipmapFilter = FilterMode.NEAREST.code;
+        int addressModeU = AddressMode.CLAMP_TO_EDGE.code;
+        int addressModeV = AddressMode.CLAMP_TO_EDGE.code;
+        int addressModeW = AddressMode.CLAMP_TO_EDGE.code;
+        int anisotropy = 1;
+        float lodMinClamp = 0.0f;
+        float lodMaxClamp = 32.0f;
+        int compare;
+
+        public SamplerDesc setMagFilter(FilterMode filter) {
+            this.magFilter = filter.code;
+            return this;
+        }
+
+        public SamplerDesc setMinFilter(FilterMode filter) {
+            this.minFilter = filter.code;
+            return this;
+        }
+
+        public SamplerDesc setMipmapFilter(FilterMode filter) {
+            this.mipmapFilter = filter.code;
+            return this;
+        }
+
+        /**
+         * Sets the mag, min and mipmap filter at once.
+         */
+        public SamplerDesc setFilter(FilterMode filter) {
+            return setMagFilter(filter).setMinFilter(filter).setMipmapFilter(filter);
+        }
+
+        public SamplerDesc setAddressMode(AddressMode u, AddressMode v, AddressMode w) {
+            this.addressModeU = u.code;
+            this.addressModeV = v.code;
+            this.addressModeW = w.code;
+            return this;
+        }
+
+        public SamplerDesc setAddressMode(AddressMode mode) {
+            return setAddressMode(mode, mode, mode);
+        }
+
+        /**
+         * The max anisotropy between 1 and 16. Anything above 1 requires all filters to be {@link FilterMode#LINEAR}.
+         */
+        public SamplerDesc setAnisotropy(int anisotropy) {
+            this.anisotropy = anisotropy;
+            return this;
+        }
+
+        public SamplerDesc setLodClamp(float min, float max) {
+            this.lodMinClamp = min;
+            this.lodMaxClamp = max;
+            return this;
+        }
+
+        /**
+         * Turns this into a comparison sampler for depth textures, these can't be used to draw UI textures.
+         */
+        public SamplerDesc setCompare(CompareFunction compare) {
+            this.compare = compare.code;
+            return this;
+        }
+    }
+
+    @Override
+    public void dispose() {
+        this.cleanable.clean();
+    }
+
+    private static final class JNI {
+        private static native void drop(long rustPtr);
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import org.joml.Vector2f;
+import org.joml.Vector2fc;
+import org.terasology.joml.geom.Rectanglef;
+import org.terasology.joml.geom.Rectanglefc;
+
+import java.lang.ref.Cleaner;
+
+import static org.terasology.engine.rust.EngineKernel.CLEANER;
+
+/**
+ * An image packed into a {@link TeraAtlas}.
+ */
+public class TeraSubTexture implements Disposable {
+    final long rustSubTexturePtr;
+    private final Cleaner.Cleanable cleanable;
+    private Vector2f size = new Vector2f();
+    private Rectanglef uv = new Rectanglef();
+
+    TeraSubTexture(long subTexturePtr) {
+        rustSubTexturePtr = subTexturePtr;
+        this.cleanable = CLEANER.register(this, () -> {
+            TeraSubTexture.JNI.drop(subTexturePtr);
+        });
+    }
+
+    public Vector2fc getSize() {
+        JNI.getSize(this.rustSubTexturePtr, this.size);
+        return this.size;
+    }
+
+    /**
+     * The uv rect of the image inside its atlas page. Pages can grow, so this changes when images are added to the atlas.
+     */
+    public Rectanglefc getUV() {
+        JNI.getUV(this.rustSubTexturePtr, this.uv);
+        return this.uv;
+    }
+
+    @Override
+    public void dispose() {
+        this.cleanable.clean();
+    }
+
+    private static final class JNI {
+        private static native void drop(long rustPtr);
+
+        public static native void getSize(long subTexturePtr, Vector2f vec);
+        public static native void getUV(long subTexturePtr, Rectanglef rect);
+    }
+}
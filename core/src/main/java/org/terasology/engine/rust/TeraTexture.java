@@ -5,8 +5,11 @@ package org.terasology.engine.rust;
 
 import org.joml.Vector2f;
 import org.joml.Vector2fc;
+import org.terasology.joml.geom.Rectanglei;
 
 import java.lang.ref.Cleaner;
+import java.nio.ByteBuffer;
+import java.util.concurrent.CompletableFuture;
 
 import static org.terasology.engine.rust.EngineKernel.CLEANER;
 
@@ -25,29 +28,127 @@ public class TeraTexture implements Disposable {
     }
 
     public enum TextureDimension {
-        DIM_1D,
-        DIM_2D,
-        DIM_3D
+        DIM_1D(0),
+        DIM_2D(1),
+        DIM_3D(2),
+        /**
+         * Square 2D layers where every six layers form a cube, see {@link CubeFace} for their order. More than six
+         * layers make a cube array, which not every device supports.
+         */
+        CUBE(3);
+
+        final int code;
+
+        TextureDimension(int code) {
+            this.code = code;
+        }
     }
 
+    /**
+     * Texel formats. Compressed formats and {@link #D32_FLOAT_S8_UINT} are only available if the device supports them,
+     * see {@link ResourceManager#isFormatSupported(ImageFormat)}. Depth formats can't be drawn by the UI.
+     */
     public enum ImageFormat {
-        UNKNOWN,
-        R8_UNORM,
-        R8_SNORM,
-        R8_UINT,
-        R8_SINT,
-        R8G8_UNORM,
-        R8G8_SNORM,
-        R8G8_UINT,
-        R8G8_SINT,
-        R16_UNORM,
-        R16_SNORM,
-        R16_UINT,
-        R16_SINT,
-        R8G8B8A8_UNORM,
-        R8G8B8A8_SNORM,
-        R8G8B8A8_UINT,
-        R8G8B8A8_SINT,
+        UNKNOWN(0),
+        R8_UNORM(1),
+        R8_SNORM(2),
+        R8_UINT(3),
+        R8_SINT(4),
+        R8G8_UNORM(5),
+        R8G8_SNORM(6),
+        R8G8_UINT(7),
+        R8G8_SINT(8),
+        R16_UNORM(9),
+        R16_SNORM(10),
+        R16_UINT(11),
+        R16_SINT(12),
+        R8G8B8A8_UNORM(13),
+        R8G8B8A8_SNORM(14),
+        R8G8B8A8_UINT(15),
+        R8G8B8A8_SINT(16),
+        R8G8B8A8_SRGB(17),
+
+        B8G8R8A8_UNORM(18),
+        B8G8R8A8_SRGB(19),
+
+        R16_FLOAT(32),
+        R16G16B16A16_FLOAT(33),
+        R32_FLOAT(34),
+        R11G11B10_FLOAT(35),
+
+        D16_UNORM(48),
+        D24_PLUS(49),
+        D24_PLUS_S8_UINT(50),
+        D32_FLOAT(51),
+        D32_FLOAT_S8_UINT(52),
+        S8_UINT(53),
+
+        BC1_RGBA_UNORM(64),
+        BC1_RGBA_SRGB(65),
+        BC2_RGBA_UNORM(66),
+        BC2_RGBA_SRGB(67),
+        BC3_RGBA_UNORM(68),
+        BC3_RGBA_SRGB(69),
+        BC4_R_UNORM(70),
+        BC4_R_SNORM(71),
+        BC5_RG_UNORM(72),
+        BC5_RG_SNORM(73),
+        BC6H_RGB_UFLOAT(74),
+        BC6H_RGB_FLOAT(75),
+        BC7_RGBA_UNORM(76),
+        BC7_RGBA_SRGB(77),
+
+        ETC2_RGB8_UNORM(96),
+        ETC2_RGB8_SRGB(97),
+        ETC2_RGB8A1_UNORM(98),
+        ETC2_RGB8A1_SRGB(99),
+        ETC2_RGBA8_UNORM(100),
+        ETC2_RGBA8_SRGB(101),
+        EAC_R11_UNORM(102),
+        EAC_R11_SNORM(103),
+        EAC_RG11_UNORM(104),
+        EAC_RG11_SNORM(105);
+
+        final int code;
+
+        ImageFormat(int code) {
+            this.code = code;
+        }
+    }
+
+    /**
+     * Faces of a cube texture in the order they are stored in its layers.
+     */
+    public enum CubeFace {
+        POSITIVE_X(0),
+        NEGATIVE_X(1),
+        POSITIVE_Y(2),
+        NEGATIVE_Y(3),
+        POSITIVE_Z(4),
+        NEGATIVE_Z(5);
+
+        final int code;
+
+        CubeFace(int code) {
+            this.code = code;
+        }
+    }
+
+    /**
+     * Optional usages of a texture, sampling and uploads are always allowed.
+     */
+    public enum TextureUsage {
+        COPY_SRC(0x1),
+        /**
+         * Allows the UI to draw into the texture, see {@link UIRenderer#cmdUIBeginTarget(TeraTexture, int)}.
+         */
+        RENDER_ATTACHMENT(0x2);
+
+        final int flag;
+
+        TextureUsage(int flag) {
+            this.flag = flag;
+        }
     }
 
     // Texture Resource
@@ -55,9 +156,12 @@ public class TeraTexture implements Disposable {
     public static final class TextureDesc {
         int width;
         int height;
-        int layers;
+        int layers = 1;
+        int mipLevels = 1;
         int dim;
         int format;
+        int usage;
+        String label;
 
         public TextureDesc setWidth(int width) {
             this.width = width;
@@ -74,13 +178,31 @@ public class TeraTexture implements Disposable {
             return this;
         }
 
+        public TextureDesc setMipLevels(int mipLevels) {
+            this.mipLevels = mipLevels;
+            return this;
+        }
+
         public TextureDesc setFormat(ImageFormat format) {
-            this.format = format.ordinal();
+            this.format = format.code;
             return this;
         }
 
         public TextureDesc setDim(TextureDimension dim) {
-            this.dim = dim.ordinal();
+            this.dim = dim.code;
+            return this;
+        }
+
+        public TextureDesc addUsage(TextureUsage usage) {
+            this.usage |= usage.flag;
+            return this;
+        }
+
+        /**
+         * Names the texture in {@link GpuMemory#getAllocations()} and graphics debuggers.
+         */
+        public TextureDesc setLabel(String label) {
+            this.label = label;
             return this;
         }
 
@@ -88,10 +210,130 @@ public class TeraTexture implements Disposable {
     }
 
 
+    /**
+     * Hints for creating a texture from an encoded image file.
+     */
+    public static final class DecodeHints {
+        boolean srgb;
+        int usage;
+        String label;
+
+        /**
+         * Treat 8-bit color data that doesn't specify a color space as sRGB.
+         */
+        public DecodeHints setSrgb(boolean srgb) {
+            this.srgb = srgb;
+            return this;
+        }
+
+        public DecodeHints addUsage(TextureUsage usage) {
+            this.usage |= usage.flag;
+            return this;
+        }
+
+        /**
+         * Names the texture in {@link GpuMemory#getAllocations()} and graphics debuggers.
+         */
+        public DecodeHints setLabel(String label) {
+            this.label = label;
+            return this;
+        }
+    }
+
+    /**
+     * Writes tightly packed texel data to every layer of the first mip level. Like
+     * {@link #writeTextureRegion}, it is ordered behind async writes that are still queued.
+     *
+     * @throws IllegalArgumentException if the buffer is smaller than the texture
+     */
     public void writeTextureBuffer(java.nio.ByteBuffer buffer) {
         JNI.writeTextureBuffer(kernel.rustKernelPtr, this.rustTexturePtr, buffer);
     }
 
+    /**
+     * Writes texel data into a region of a single layer and mip level. While async writes to this texture are still
+     * queued, the write is queued behind them so the writes apply in the order they were made.
+     *
+     * @param layer the array layer, or the depth slice for 3D textures
+     * @param stride the number of bytes between rows in the buffer, 0 if the rows are tightly packed
+     * @throws IllegalArgumentException if the region is outside the texture or the buffer is too small for it
+     */
+    public void writeTextureRegion(int x, int y, int layer, int mip, int width, int height, java.nio.ByteBuffer buffer, int stride) {
+        JNI.writeTextureRegion(kernel.rustKernelPtr, this.rustTexturePtr, x, y, layer, mip, width, height, buffer, stride);
+    }
+
+    /**
+     * Writes tightly packed texel data to a face of a cube texture.
+     *
+     * @param cube the cube of a cube array, 0 for a single cube
+     * @param mip the mip level to write, the buffer has to cover the whole face at that level
+     * @throws IllegalArgumentException if the face is out of range or the buffer is too small for it
+     */
+    public void writeCubeFace(CubeFace face, int cube, int mip, java.nio.ByteBuffer buffer) {
+        Vector2fc size = getSize();
+        int width = Math.max((int) size.x() >> mip, 1);
+        int height = Math.max((int) size.y() >> mip, 1);
+        writeTextureRegion(0, 0, cube * 6 + face.code, mip, width, height, buffer, 0);
+    }
+
+    /**
+     * Queues tightly packed texel data for a face of a cube texture, like {@link #writeTextureBufferAsync}.
+     *
+     * @param cube the cube of a cube array, 0 for a single cube
+     * @param mip the mip level to write, the buffer has to cover the whole face at that level
+     * @throws IllegalArgumentException if the face is out of range or the buffer is too small for it
+     */
+    public CompletableFuture<Void> writeCubeFaceAsync(CubeFace face, int cube, int mip, java.nio.ByteBuffer buffer) {
+        Vector2fc size = getSize();
+        int width = Math.max((int) size.x() >> mip, 1);
+        int height = Math.max((int) size.y() >> mip, 1);
+        return writeTextureRegionAsync(0, 0, cube * 6 + face.code, mip, width, height, buffer, 0);
+    }
+
+    /**
+     * Queues tightly packed texel data for every layer of the first mip level. The data is copied right away, the
+     * upload itself happens over the next frames within the upload budget of {@link ResourceManager#setUploadBudget}.
+     * The future completes once the gpu has finished the upload.
+     *
+     * @throws IllegalArgumentException if the buffer is smaller than the texture
+     */
+    public CompletableFuture<Void> writeTextureBufferAsync(java.nio.ByteBuffer buffer) {
+        CompletableFuture<Void> future = new CompletableFuture<>();
+        JNI.writeTextureBufferAsync(kernel.rustKernelPtr, this.rustTexturePtr, buffer, future);
+        return future;
+    }
+
+    /**
+     * Queues texel data for a region of a single layer and mip level, like {@link #writeTextureBufferAsync}.
+     *
+     * @param layer the array layer, or the depth slice for 3D textures
+     * @param stride the number of bytes between rows in the buffer, 0 if the rows are tightly packed
+     * @throws IllegalArgumentException if the region is outside the texture or the buffer is too small for it
+     */
+    public CompletableFuture<Void> writeTextureRegionAsync(int x, int y, int layer, int mip, int width, int height, java.nio.ByteBuffer buffer, int stride) {
+        CompletableFuture<Void> future = new CompletableFuture<>();
+        JNI.writeTextureRegionAsync(kernel.rustKernelPtr, this.rustTexturePtr, x, y, layer, mip, width, height, buffer, stride, future);
+        return future;
+    }
+
+    /**
+     * Copies a region of a single layer and mip level back to the cpu. The future completes with the tightly packed
+     * texel data once the gpu has finished the copy, which is checked after every dispatched frame.
+     * The texture has to be created with {@link TextureUsage#COPY_SRC}.
+     *
+     * @param layer the array layer, or the depth slice for 3D textures
+     * @throws IllegalArgumentException if the region is outside the texture or the texture can't be copied from
+     */
+    public CompletableFuture<ByteBuffer> readTexture(int x, int y, int width, int height, int mip, int layer) {
+        CompletableFuture<ByteBuffer> future = new CompletableFuture<>();
+        JNI.readTexture(kernel.rustKernelPtr, this.rustTexturePtr, x, y, width, height, mip, layer, future);
+        return future;
+    }
+
+    public CompletableFuture<ByteBuffer> readTexture(Rectanglei region, int mip, int layer) {
+        return readTexture(region.minX(), region.minY(), region.lengthX(), region.lengthY(), mip, layer);
+    }
+
     public Vector2fc getSize() {
         JNI.getSize(this.rustTexturePtr, this.size);
         return this.size;
@@ -108,6 +350,18 @@ public class TeraTexture implements Disposable {
 
         public static native void getSize(long textureResourcePtr, Vector2f vec);
         public static native void writeTextureBuffer(long kernelPtr, long textureResourcePtr, java.nio.ByteBuffer buffer);
+        public static native void writeTextureRegion(long kernelPtr, long textureResourcePtr,
+                                                     int x, int y, int layer, int mip, int width, int height,
+                                                     java.nio.ByteBuffer buffer, int stride);
+        public static native void writeTextureBufferAsync(long kernelPtr, long textureResourcePtr, java.nio.ByteBuffer buffer,
+                                                          CompletableFuture<Void> future);
+        public static native void writeTextureRegionAsync(long kernelPtr, long textureResourcePtr,
+                                                          int x, int y, int layer, int mip, int width, int height,
+                                                          java.nio.ByteBuffer buffer, int stride,
+                                                          CompletableFuture<Void> future);
+        public static native void readTexture(long kernelPtr, long textureResourcePtr,
+                                              int x, int y, int width, int height, int mip, int layer,
+                                              CompletableFuture<ByteBuffer> future);
 
     }
 }
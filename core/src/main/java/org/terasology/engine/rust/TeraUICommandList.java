@@ -0,0 +1,36 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import java.lang.ref.Cleaner;
+
+import static org.terasology.engine.rust.EngineKernel.CLEANER;
+
+/**
+ * UI draws recorded once with {@link UIRenderer#cmdUIBeginList()} and replayed in any later frame with
+ * {@link UIRenderer#cmdUIDrawList(TeraUICommandList, float, float)}. The geometry stays on the GPU, so a replay costs
+ * a single call however many draws were recorded. The list keeps its textures and fonts alive, but the
+ * {@link TeraSubTexture}s drawn into it have to outlive it, their space in the atlas is reused once they are
+ * disposed.
+ */
+public class TeraUICommandList implements Disposable {
+    final long rustListPtr;
+    private final Cleaner.Cleanable cleanable;
+
+    TeraUICommandList(long listPtr) {
+        rustListPtr = listPtr;
+        this.cleanable = CLEANER.register(this, () -> {
+            TeraUICommandList.JNI.drop(listPtr);
+        });
+    }
+
+    @Override
+    public void dispose() {
+        this.cleanable.clean();
+    }
+
+    private static final class JNI {
+        private static native void drop(long rustPtr);
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright 2023 The Terasology Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+package org.terasology.engine.rust;
+
+import org.joml.Vector2fc;
+import org.terasology.joml.geom.Rectanglef;
+
+import java.nio.ByteBuffer;
+import java.nio.ByteOrder;
+import java.nio.charset.StandardCharsets;
+import java.util.Arrays;
+import java.util.IdentityHashMap;
+import java.util.Map;
+import java.util.Optional;
+
+/**
+ * UI commands written into a reusable direct buffer and executed together by
+ * {@link UIRenderer#cmdUISubmit(UICommandStream)}, which takes a single native call however many commands were
+ * written. The commands behave exactly like the {@link UIRenderer} methods of the same name, but their arguments are
+ * only checked once the stream is submitted. Textures, fonts and lists written into the stream are kept alive until
+ * it is submitted.
+ */
+public final class UICommandStream {
+    // the layout matches jni_ui_stream.rs, bump the version on both sides whenever it changes
+    private static final int MAGIC = 0x43495554;
+    private static final int VERSION = 2;
+
+    private static final int OP_SET_CROP = 1;
+    private static final int OP_CLEAR_CROP = 2;
+    private static final int OP_PUSH_CROP = 3;
+    private static final int OP_POP_CROP = 4;
+    private static final int OP_PUSH_TRANSFORM = 5;
+    private static final int OP_POP_TRANSFORM = 6;
+    private static final int OP_TRANSLATE = 7;
+    private static final int OP_ROTATE = 8;
+    private static final int OP_SCALE = 9;
+    private static final int OP_SET_BLEND_MODE = 10;
+    private static final int OP_DRAW_TEXTURE = 11;
+    private static final int OP_DRAW_SUB_TEXTURE = 12;
+    private static final int OP_DRAW_TEXTURE_TILED = 13;
+    private static final int OP_DRAW_NINE_SLICE = 14;
+    private static final int OP_DRAW_SUB_NINE_SLICE = 15;
+    private static final int OP_DRAW_RECT = 16;
+    private static final int OP_DRAW_LINE = 17;
+    private static final int OP_DRAW_POLYLINE = 18;
+    private static final int OP_DRAW_TEXT = 19;
+    private static final int OP_DRAW_TEXT_LAYOUT = 20;
+    private static final int OP_DRAW_LIST = 21;
+    private static final int OP_BEGIN_TARGET = 22;
+    private static final int OP_END_TARGET = 23;
+
+    private ByteBuffer buffer;
+    // the native handles the commands refer to by index
+    private long[] handles = new long[16];
+    private int handleCount;
+    // the objects behind the handles, kept alive until the stream is submitted
+    private final Map<Object, Integer> handleIndices = new IdentityHashMap<>();
+
+    public UICommandStream() {
+        this(4096);
+    }
+
+    /**
+     * @param capacity the initial size of the buffer in bytes, it grows as needed
+     */
+    public UICommandStream(int capacity) {
+        buffer = ByteBuffer.allocateDirect(Math.max(capacity, 64)).order(ByteOrder.LITTLE_ENDIAN);
+        reset();
+    }
+
+    /**
+     * Drops every written command, the buffer is kept for the next commands.
+     */
+    public void reset() {
+        buffer.clear();
+        buffer.putInt(MAGIC).putInt(VERSION);
+        handleIndices.clear();
+        handleCount = 0;
+    }
+
+    ByteBuffer buffer() {
+        return buffer;
+    }
+
+    int length() {
+        return buffer.position();
+    }
+
+    long[] handles() {
+        return handles;
+    }
+
+    int handleCount() {
+        return handleCount;
+    }
+
+    /**
+     * The index of the handle of {@code owner} in the handle table, added on first use.
+     */
+    private int handle(Object owner, long ptr) {
+        Integer index = handleIndices.get(owner);
+        if (index != null) {
+            return index;
+        }
+        if (handleCount == handles.length) {
+            handles = Arrays.copyOf(handles, handles.length * 2);
+        }
+        handles[handleCount] = ptr;
+        handleIndices.put(owner, handleCount);
+        return handleCount++;
+    }
+
+    private ByteBuffer reserve(int bytes) {
+        if (buffer.remaining() < bytes) {
+            int capacity = buffer.capacity();
+            while (capacity - buffer.position() < bytes) {
+                capacity *= 2;
+            }
+            ByteBuffer grown = ByteBuffer.allocateDirect(capacity).order(ByteOrder.LITTLE_ENDIAN);
+            buffer.flip();
+            grown.put(buffer);
+            buffer = grown;
+        }
+        return buffer;
+    }
+
+    private void putRect(Rectanglef rect) {
+        buffer.putFloat(rect.minX()).putFloat(rect.minY()).putFloat(rect.maxX()).putFloat(rect.maxY());
+    }
+
+    private void putSampler(TeraSampler sampler) {
+        buffer.putInt(sampler != null ? handle(sampler, sampler.rustSamplerPtr) : -1);
+    }
+
+    private void putNineSlice(UIRenderer.NineSlice slice) {
+        buffer.putFloat(slice.left).putFloat(slice.top).putFloat(slice.right).putFloat(slice.bottom)
+                .putFloat(slice.scale)
+                .putInt(slice.tile ? 1 : 0);
+    }
+
+    /**
+     * @see UIRenderer#cmdUISetCrop(Optional)
+     */
+    public void cmdUISetCrop(Optional<Rectanglef> rect) {
+        if (rect.isPresent()) {
+            reserve(20).putInt(OP_SET_CROP);
+            putRect(rect.get());
+        } else {
+            reserve(4).putInt(OP_CLEAR_CROP);
+        }
+    }
+
+    /**
+     * @see UIRenderer#cmdUIPushCrop(Rectanglef)
+     */
+    public void cmdUIPushCrop(Rectanglef rect) {
+        reserve(20).putInt(OP_PUSH_CROP);
+        putRect(rect);
+    }
+
+    public void cmdUIPopCrop() {
+        reserve(4).putInt(OP_POP_CROP);
+    }
+
+    public void cmdUIPushTransform() {
+        reserve(4).putInt(OP_PUSH_TRANSFORM);
+    }
+
+    public void cmdUIPopTransform() {
+        reserve(4).putInt(OP_POP_TRANSFORM);
+    }
+
+    public void cmdUITranslate(float x, float y) {
+        reserve(12).putInt(OP_TRANSLATE).putFloat(x).putFloat(y);
+    }
+
+    public void cmdUIRotate(float radians) {
+        reserve(8).putInt(OP_ROTATE).putFloat(radians);
+    }
+
+    public void cmdUIScale(float x, float y) {
+        reserve(12).putInt(OP_SCALE).putFloat(x).putFloat(y);
+    }
+
+    public void cmdUIScale(float scale) {
+        cmdUIScale(scale, scale);
+    }
+
+    public void cmdUISetBlendMode(UIRenderer.BlendMode mode) {
+        reserve(8).putInt(OP_SET_BLEND_MODE).putInt(mode.code);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawList(TeraUICommandList, float, float)
+     */
+    public void cmdUIDrawList(TeraUICommandList list, float x, float y) {
+        reserve(36).putInt(OP_DRAW_LIST).putInt(handle(list, list.rustListPtr)).putFloat(x).putFloat(y).putInt(0)
+                .putFloat(0).putFloat(0).putFloat(0).putFloat(0);
+    }
+
+    public void cmdUIDrawList(TeraUICommandList list, float x, float y, Rectanglef clip) {
+        reserve(36).putInt(OP_DRAW_LIST).putInt(handle(list, list.rustListPtr)).putFloat(x).putFloat(y).putInt(1);
+        putRect(clip);
+    }
+
+    public void cmdUIDrawTexture(TeraTexture tex, Rectanglef uv, Rectanglef pos) {
+        cmdUIDrawTexture(tex, 0, uv, pos, 0xffffffff, null);
+    }
+
+    public void cmdUIDrawTexture(TeraTexture tex, Rectanglef uv, Rectanglef pos, int tintColor) {
+        cmdUIDrawTexture(tex, 0, uv, pos, tintColor, null);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawTexture(TeraTexture, int, Rectanglef, Rectanglef, int, TeraSampler)
+     */
+    public void cmdUIDrawTexture(TeraTexture tex, int layer, Rectanglef uv, Rectanglef pos, int tintColor, TeraSampler sampler) {
+        reserve(52).putInt(OP_DRAW_TEXTURE).putInt(handle(tex, tex.rustTexturePtr)).putInt(layer);
+        putRect(uv);
+        putRect(pos);
+        buffer.putInt(tintColor);
+        putSampler(sampler);
+    }
+
+    public void cmdUIDrawTexture(TeraSubTexture tex, Rectanglef pos, int tintColor) {
+        cmdUIDrawTexture(tex, new Rectanglef(0.0f, 0.0f, 1.0f, 1.0f), pos, tintColor, null);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawTexture(TeraSubTexture, Rectanglef, Rectanglef, int, TeraSampler)
+     */
+    public void cmdUIDrawTexture(TeraSubTexture tex, Rectanglef uv, Rectanglef pos, int tintColor, TeraSampler sampler) {
+        reserve(48).putInt(OP_DRAW_SUB_TEXTURE).putInt(handle(tex, tex.rustSubTexturePtr));
+        putRect(uv);
+        putRect(pos);
+        buffer.putInt(tintColor);
+        putSampler(sampler);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawTextureTiled(TeraTexture, int, Rectanglef, Vector2fc, Vector2fc, int, TeraSampler)
+     */
+    public void cmdUIDrawTextureTiled(TeraTexture tex, int layer, Rectanglef pos, Vector2fc tileSize, Vector2fc offset,
+                                      int tintColor, TeraSampler sampler) {
+        reserve(52).putInt(OP_DRAW_TEXTURE_TILED).putInt(handle(tex, tex.rustTexturePtr)).putInt(layer);
+        putRect(pos);
+        buffer.putFloat(tileSize.x()).putFloat(tileSize.y())
+                .putFloat(offset.x()).putFloat(offset.y())
+                .putInt(tintColor);
+        putSampler(sampler);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawNineSlice(TeraTexture, int, Rectanglef, Rectanglef, UIRenderer.NineSlice, int, TeraSampler)
+     */
+    public void cmdUIDrawNineSlice(TeraTexture tex, int layer, Rectanglef uv, Rectanglef pos, UIRenderer.NineSlice slice,
+                                   int tintColor, TeraSampler sampler) {
+        reserve(76).putInt(OP_DRAW_NINE_SLICE).putInt(handle(tex, tex.rustTexturePtr)).putInt(layer);
+        putRect(uv);
+        putRect(pos);
+        putNineSlice(slice);
+        buffer.putInt(tintColor);
+        putSampler(sampler);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawNineSlice(TeraSubTexture, Rectanglef, Rectanglef, UIRenderer.NineSlice, int, TeraSampler)
+     */
+    public void cmdUIDrawNineSlice(TeraSubTexture tex, Rectanglef uv, Rectanglef pos, UIRenderer.NineSlice slice,
+                                   int tintColor, TeraSampler sampler) {
+        reserve(72).putInt(OP_DRAW_SUB_NINE_SLICE).putInt(handle(tex, tex.rustSubTexturePtr));
+        putRect(uv);
+        putRect(pos);
+        putNineSlice(slice);
+        buffer.putInt(tintColor);
+        putSampler(sampler);
+    }
+
+    public void cmdUIDrawRect(Rectanglef pos, int color) {
+        reserve(24).putInt(OP_DRAW_RECT);
+        putRect(pos);
+        buffer.putInt(color);
+    }
+
+    public void cmdUIDrawLine(Vector2fc from, Vector2fc to, float thickness, int color) {
+        reserve(28).putInt(OP_DRAW_LINE)
+                .putFloat(from.x()).putFloat(from.y()).putFloat(to.x()).putFloat(to.y())
+                .putFloat(thickness)
+                .putInt(color);
+    }
+
+    /**
+     * @see UIRenderer#cmdUIDrawPolyline(float[], float, boolean, int)
+     */
+    public void cmdUIDrawPolyline(float[] points, float thickness, boolean closed, int color) {
+        if (points.length % 2 != 0) {
+            throw new IllegalArgumentException("points need an x and y each, got " + points.length + " values");
+        }
+        reserve(20 + points.length * 4).putInt(OP_DRAW_POLYLINE)
+                .putFloat(thickness)
+                .putInt(closed ? 1 : 0)
+                .putInt(color)
+                .putInt(points.length / 2);
+        for (float value : points) {
+            buffer.putFloat(value);
+        }
+    }
+
+    public void cmdUIDrawText(TeraFont font, float size, float x, float y, int color, String text) {
+        byte[] bytes = text.getBytes(StandardCharsets.UTF_8);
+        reserve(28 + bytes.length).putInt(OP_DRAW_TEXT).putInt(handle(font, font.rustFontPtr))
+                .putFloat(size)
+                .putFloat(x).putFloat(y)
+                .putInt(color)
+                .putInt(bytes.length)
+                .put(bytes);
+    }
+
+    public void cmdUIDrawText(TeraFont font, TeraFont.TextLayoutDesc desc, float x, float y, int color, String text) {
+        byte[] bytes = text.getBytes(StandardCharsets.UTF_8);
+        reserve(48 + bytes.length).putInt(OP_DRAW_TEXT_LAYOUT).putInt(handle(font, font.rustFontPtr))
+                .putFloat(desc.size)
+                .putFloat(desc.maxWidth)
+                .putInt(desc.maxLines)
+                .putInt(desc.wrap ? 1 : 0)
+                .putInt(desc.ellipsis ? 1 : 0)
+                .putInt(desc.align)
+                .putFloat(x).putFloat(y)
+                .putInt(color)
+                .putInt(bytes.length)
+                .put(bytes);
+    }
+
+    public void cmdUIBeginTarget(TeraTexture target) {
+        reserve(16).putInt(OP_BEGIN_TARGET).putInt(handle(target, target.rustTexturePtr)).putInt(0).putInt(0);
+    }
+
+    public void cmdUIBeginTarget(TeraTexture target, int clearColor) {
+        reserve(16).putInt(OP_BEGIN_TARGET).putInt(handle(target, target.rustTexturePtr)).putInt(1).putInt(clearColor);
+    }
+
+    public void cmdUIEndTarget() {
+        reserve(4).putInt(OP_END_TARGET);
+    }
+}
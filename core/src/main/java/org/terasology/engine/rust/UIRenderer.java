@@ -3,8 +3,10 @@
 
 package org.terasology.engine.rust;
 
+import org.joml.Vector2fc;
 import org.terasology.joml.geom.Rectanglef;
 
+import java.nio.ByteBuffer;
 import java.util.Optional;
 
 public class UIRenderer {
@@ -14,6 +16,11 @@ public class UIRenderer {
     }
 
     // User Interface
+    /**
+     * Replaces the current crop, crops pushed before are kept. Inside a {@link #cmdUIPushCrop(Rectanglef)} scope the
+     * crop stays within the crop the scope was pushed into, clearing it falls back to that crop. The rect is given in
+     * the current transform.
+     */
     public void cmdUISetCrop(Optional<Rectanglef> rect) {
         if (rect.isPresent()) {
             Rectanglef r = rect.get();
@@ -23,13 +30,149 @@ public class UIRenderer {
         }
     }
 
+    /**
+     * Crops the following draws to the part of {@code rect} inside the current crop, until the matching
+     * {@link #cmdUIPopCrop()}. Nothing is drawn while the intersection is empty. Every push has to be popped
+     * before {@link EngineKernel#cmdDispatch()}, which throws an {@link IllegalStateException} otherwise.
+     */
+    public void cmdUIPushCrop(Rectanglef rect) {
+        UIRenderer.JNI.cmdUIPushCrop(this.kernel.rustKernelPtr, rect.minX(), rect.minY(), rect.maxX(), rect.maxY());
+    }
+
+    /**
+     * Restores the crop from before the matching {@link #cmdUIPushCrop(Rectanglef)}.
+     *
+     * @throws IllegalStateException if no crop was pushed
+     */
+    public void cmdUIPopCrop() {
+        UIRenderer.JNI.cmdUIPopCrop(this.kernel.rustKernelPtr);
+    }
+
+    /**
+     * Saves the current transform until the matching {@link #cmdUIPopTransform()}. Every push has to be popped
+     * before {@link EngineKernel#cmdDispatch()}, which throws an {@link IllegalStateException} otherwise.
+     */
+    public void cmdUIPushTransform() {
+        UIRenderer.JNI.cmdUIPushTransform(this.kernel.rustKernelPtr);
+    }
+
+    /**
+     * Restores the transform from before the matching {@link #cmdUIPushTransform()}.
+     *
+     * @throws IllegalStateException if no transform was pushed
+     */
+    public void cmdUIPopTransform() {
+        UIRenderer.JNI.cmdUIPopTransform(this.kernel.rustKernelPtr);
+    }
+
+    /**
+     * Moves the origin of the following draws and crops. Like {@link #cmdUIRotate(float)} and
+     * {@link #cmdUIScale(float, float)} this applies before the current transform, so the steps of a child
+     * widget are given in the space of its parent.
+     */
+    public void cmdUITranslate(float x, float y) {
+        UIRenderer.JNI.cmdUITranslate(this.kernel.rustKernelPtr, x, y);
+    }
+
+    /**
+     * Rotates the following draws around the current origin, positive angles turn clockwise on screen.
+     * Crops set while a transform rotates clip to the bounding box of the rotated rect.
+     */
+    public void cmdUIRotate(float radians) {
+        UIRenderer.JNI.cmdUIRotate(this.kernel.rustKernelPtr, radians);
+    }
+
+    public void cmdUIScale(float x, float y) {
+        UIRenderer.JNI.cmdUIScale(this.kernel.rustKernelPtr, x, y);
+    }
+
+    public void cmdUIScale(float scale) {
+        cmdUIScale(scale, scale);
+    }
+
+    /**
+     * Selects how the following draws are combined with what was drawn before them, until the end of the frame.
+     * Recorded command lists keep the mode of every draw. Draws start out with {@link BlendMode#ALPHA}.
+     */
+    public void cmdUISetBlendMode(BlendMode mode) {
+        UIRenderer.JNI.cmdUISetBlendMode(this.kernel.rustKernelPtr, mode.code);
+    }
+
+    /**
+     * Records the following draws into a {@link TeraUICommandList} instead of the frame, until
+     * {@link #cmdUIEndList()}. The list starts without crop and transform. The recording has to end before
+     * {@link EngineKernel#cmdDispatch()}.
+     *
+     * @throws IllegalStateException if a list is already recorded
+     */
+    public void cmdUIBeginList() {
+        UIRenderer.JNI.cmdUIBeginList(this.kernel.rustKernelPtr);
+    }
+
+    /**
+     * Finishes the recorded list and restores the crop and transform of the frame.
+     *
+     * @throws IllegalStateException if no list is recorded, or a crop or transform pushed into it wasn't popped
+     */
+    public TeraUICommandList cmdUIEndList() {
+        return new TeraUICommandList(UIRenderer.JNI.cmdUIEndList(this.kernel.rustKernelPtr));
+    }
+
+    /**
+     * Replays a command list moved by {@code x} and {@code y} pixels. The current crop applies, the current
+     * transform doesn't.
+     *
+     * @throws IllegalStateException if a list is recorded
+     */
+    public void cmdUIDrawList(TeraUICommandList list, float x, float y) {
+        UIRenderer.JNI.cmdUIDrawList(this.kernel.rustKernelPtr, list.rustListPtr, x, y, false, 0, 0, 0, 0);
+    }
+
+    /**
+     * Like {@link #cmdUIDrawList(TeraUICommandList, float, float)}, but also crops the list to {@code clip}, given in
+     * pixels.
+     */
+    public void cmdUIDrawList(TeraUICommandList list, float x, float y, Rectanglef clip) {
+        UIRenderer.JNI.cmdUIDrawList(this.kernel.rustKernelPtr, list.rustListPtr, x, y, true,
+                clip.minX(), clip.minY(), clip.maxX(), clip.maxY());
+    }
+
+    /**
+     * Draws a single layer of a 2D array texture, or a depth slice of a 3D texture.
+     *
+     * @throws IllegalArgumentException if the layer is out of range
+     */
+    public void cmdUIDrawTexture(TeraTexture tex, int layer, Rectanglef uv, Rectanglef pos, int tintColor) {
+        cmdUIDrawTexture(tex, layer, uv, pos, tintColor, null);
+    }
+
+    /**
+     * Draws a single layer of a texture with the given sampler, e.g. a nearest filtering sampler for pixel art.
+     *
+     * @param sampler the sampler to use, or null for the default linear sampler
+     * @throws IllegalArgumentException if the layer is out of range or the sampler is a comparison sampler
+     */
+    public void cmdUIDrawTexture(TeraTexture tex, int layer, Rectanglef uv, Rectanglef pos, int tintColor, TeraSampler sampler) {
+        UIRenderer.JNI.cmdUIDrawTexture(
+                this.kernel.rustKernelPtr,
+                tex.rustTexturePtr,
+                layer,
+                uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                tintColor,
+                sampler != null ? sampler.rustSamplerPtr : 0
+        );
+    }
+
     public void cmdUIDrawTexture(TeraTexture tex, Rectanglef uv, Rectanglef pos, int tintColor) {
         UIRenderer.JNI.cmdUIDrawTexture(
                 this.kernel.rustKernelPtr,
                 tex.rustTexturePtr,
+                0,
                 uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
                 pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
-                tintColor
+                tintColor,
+                0
         );
     }
 
@@ -37,20 +180,361 @@ public class UIRenderer {
         UIRenderer.JNI.cmdUIDrawTexture(
                 this.kernel.rustKernelPtr,
                 tex.rustTexturePtr,
+                0,
+                uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                0xffffffff,
+                0
+        );
+    }
+
+    /**
+     * Draws an image packed into a {@link TeraAtlas}, the uv rect is relative to the image.
+     */
+    public void cmdUIDrawTexture(TeraSubTexture tex, Rectanglef uv, Rectanglef pos, int tintColor) {
+        cmdUIDrawTexture(tex, uv, pos, tintColor, null);
+    }
+
+    /**
+     * @param sampler the sampler to use, or null for the default linear sampler
+     */
+    public void cmdUIDrawTexture(TeraSubTexture tex, Rectanglef uv, Rectanglef pos, int tintColor, TeraSampler sampler) {
+        UIRenderer.JNI.cmdUIDrawSubTexture(
+                this.kernel.rustKernelPtr,
+                tex.rustSubTexturePtr,
+                uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                tintColor,
+                sampler != null ? sampler.rustSamplerPtr : 0
+        );
+    }
+
+    public void cmdUIDrawTexture(TeraSubTexture tex, Rectanglef pos, int tintColor) {
+        UIRenderer.JNI.cmdUIDrawSubTexture(
+                this.kernel.rustKernelPtr,
+                tex.rustSubTexturePtr,
+                0.0f, 0.0f, 1.0f, 1.0f,
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                tintColor,
+                0
+        );
+    }
+
+    /**
+     * Repeats a texture across a rect in a single quad, like a background pattern.
+     *
+     * @param tileSize the size of one repetition in pixels
+     * @param offset shifts the pattern, in pixels
+     * @throws IllegalArgumentException if the tile size isn't positive
+     */
+    public void cmdUIDrawTextureTiled(TeraTexture tex, Rectanglef pos, Vector2fc tileSize, Vector2fc offset, int tintColor) {
+        cmdUIDrawTextureTiled(tex, 0, pos, tileSize, offset, tintColor, null);
+    }
+
+    /**
+     * Images packed into a {@link TeraAtlas} can't be tiled this way, the repetition would cover the whole page.
+     *
+     * @param sampler a sampler that repeats, or null for the built in repeating linear sampler
+     */
+    public void cmdUIDrawTextureTiled(TeraTexture tex, int layer, Rectanglef pos, Vector2fc tileSize, Vector2fc offset,
+                                      int tintColor, TeraSampler sampler) {
+        UIRenderer.JNI.cmdUIDrawTextureTiled(
+                this.kernel.rustKernelPtr,
+                tex.rustTexturePtr,
+                layer,
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                tileSize.x(), tileSize.y(),
+                offset.x(), offset.y(),
+                tintColor,
+                sampler != null ? sampler.rustSamplerPtr : 0
+        );
+    }
+
+    /**
+     * Insets of the corners of a nine slice image, in texels of the texture.
+     */
+    public static final class NineSlice {
+        float left;
+        float top;
+        float right;
+        float bottom;
+        float scale = 1.0f;
+        boolean tile;
+
+        public NineSlice(float left, float top, float right, float bottom) {
+            this.left = left;
+            this.top = top;
+            this.right = right;
+            this.bottom = bottom;
+        }
+
+        /**
+         * The size of a texel of the border on screen. Borders that don't fit into the drawn rect shrink evenly.
+         */
+        public NineSlice setScale(float scale) {
+            this.scale = scale;
+            return this;
+        }
+
+        /**
+         * Repeats the edges and the center at their original size instead of stretching them.
+         */
+        public NineSlice setTile(boolean tile) {
+            this.tile = tile;
+            return this;
+        }
+    }
+
+    /**
+     * Draws a bordered frame from a single image in one call. The corners keep their size, the edges and the center
+     * stretch or tile to fill the rect.
+     *
+     * @throws IllegalArgumentException if an inset is negative or the scale isn't positive
+     */
+    public void cmdUIDrawNineSlice(TeraTexture tex, Rectanglef uv, Rectanglef pos, NineSlice slice, int tintColor) {
+        cmdUIDrawNineSlice(tex, 0, uv, pos, slice, tintColor, null);
+    }
+
+    /**
+     * @param sampler the sampler to use, or null for the default linear sampler
+     */
+    public void cmdUIDrawNineSlice(TeraTexture tex, int layer, Rectanglef uv, Rectanglef pos, NineSlice slice, int tintColor,
+                                   TeraSampler sampler) {
+        UIRenderer.JNI.cmdUIDrawNineSlice(
+                this.kernel.rustKernelPtr,
+                tex.rustTexturePtr,
+                layer,
                 uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
                 pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
-                0xffffffff
+                slice.left, slice.top, slice.right, slice.bottom,
+                slice.scale,
+                slice.tile,
+                tintColor,
+                sampler != null ? sampler.rustSamplerPtr : 0
         );
     }
 
+    /**
+     * Draws a nine slice of an image packed into a {@link TeraAtlas}, the insets are in texels of the image.
+     */
+    public void cmdUIDrawNineSlice(TeraSubTexture tex, Rectanglef pos, NineSlice slice, int tintColor) {
+        cmdUIDrawNineSlice(tex, new Rectanglef(0.0f, 0.0f, 1.0f, 1.0f), pos, slice, tintColor, null);
+    }
+
+    /**
+     * @param uv the part of the image to draw, relative to the image
+     * @param sampler the sampler to use, or null for the default linear sampler
+     */
+    public void cmdUIDrawNineSlice(TeraSubTexture tex, Rectanglef uv, Rectanglef pos, NineSlice slice, int tintColor,
+                                   TeraSampler sampler) {
+        UIRenderer.JNI.cmdUIDrawSubNineSlice(
+                this.kernel.rustKernelPtr,
+                tex.rustSubTexturePtr,
+                uv.minX(), uv.minY(), uv.maxX(), uv.maxY(),
+                pos.minX(), pos.minY(), pos.maxX(), pos.maxY(),
+                slice.left, slice.top, slice.right, slice.bottom,
+                slice.scale,
+                slice.tile,
+                tintColor,
+                sampler != null ? sampler.rustSamplerPtr : 0
+        );
+    }
+
+    /**
+     * Fills a rect with a color. Shapes need no texture and batch with text, edges on fractional positions are
+     * anti-aliased.
+     *
+     * @param color the color in RGBA order, like the tint color of draws
+     */
+    public void cmdUIDrawRect(Rectanglef pos, int color) {
+        UIRenderer.JNI.cmdUIDrawRect(this.kernel.rustKernelPtr, pos.minX(), pos.minY(), pos.maxX(), pos.maxY(), color);
+    }
+
+    /**
+     * Draws an anti-aliased line with flat ends.
+     *
+     * @throws IllegalArgumentException if the thickness isn't positive
+     */
+    public void cmdUIDrawLine(Vector2fc from, Vector2fc to, float thickness, int color) {
+        UIRenderer.JNI.cmdUIDrawLine(this.kernel.rustKernelPtr, from.x(), from.y(), to.x(), to.y(), thickness, color);
+    }
+
+    /**
+     * Draws connected lines with mitered joins.
+     *
+     * @param points x and y of every point, one after the other
+     * @param closed also connect the last point to the first
+     * @throws IllegalArgumentException if the thickness isn't positive or a point is missing its y
+     */
+    public void cmdUIDrawPolyline(float[] points, float thickness, boolean closed, int color) {
+        UIRenderer.JNI.cmdUIDrawPolyline(this.kernel.rustKernelPtr, points, thickness, closed, color);
+    }
+
+    /**
+     * Draws text with its first line starting at the top left corner {@code (x, y)}. Line breaks start a new line
+     * below, other control characters are skipped.
+     *
+     * @param size the em size in pixels
+     * @param color the color in RGBA order, like the tint color of draws
+     * @throws IllegalArgumentException if the size isn't positive
+     */
+    public void cmdUIDrawText(TeraFont font, float size, float x, float y, int color, String text) {
+        UIRenderer.JNI.cmdUIDrawText(this.kernel.rustKernelPtr, font.rustFontPtr, size, x, y, color, text);
+    }
+
+    /**
+     * Draws text wrapped, truncated and aligned like {@link TeraFont#layout}, with its top left corner at
+     * {@code (x, y)}.
+     *
+     * @param color the color in RGBA order, like the tint color of draws
+     * @throws IllegalArgumentException if the size, max width or max lines are invalid
+     */
+    public void cmdUIDrawText(TeraFont font, TeraFont.TextLayoutDesc desc, float x, float y, int color, String text) {
+        UIRenderer.JNI.cmdUIDrawTextLayout(this.kernel.rustKernelPtr, font.rustFontPtr, desc, x, y, color, text);
+    }
+
+    /**
+     * Redirects the following draws into a texture created with {@link TeraTexture.TextureUsage#RENDER_ATTACHMENT},
+     * until {@link #cmdUIEndTarget()} is called. Render targets are drawn before the screen, so the texture can be
+     * sampled by any later draw of the same frame. The previous contents of the texture are kept. Crops and
+     * transforms of the screen don't apply inside the target.
+     *
+     * @throws IllegalArgumentException if another target is active or the texture can't be rendered to
+     */
+    public void cmdUIBeginTarget(TeraTexture target) {
+        UIRenderer.JNI.cmdUIBeginTarget(this.kernel.rustKernelPtr, target.rustTexturePtr, false, 0);
+    }
+
+    /**
+     * Like {@link #cmdUIBeginTarget(TeraTexture)}, but clears the texture first. Draws onto a transparent target
+     * leave their color multiplied by alpha, so the target is drawn with {@link BlendMode#PREMULTIPLIED}.
+     *
+     * @param clearColor the color in RGBA order, like the tint color of draws
+     */
+    public void cmdUIBeginTarget(TeraTexture target, int clearColor) {
+        UIRenderer.JNI.cmdUIBeginTarget(this.kernel.rustKernelPtr, target.rustTexturePtr, true, clearColor);
+    }
+
+    /**
+     * Returns to drawing on the screen, the crop of the screen is restored.
+     *
+     * @throws IllegalArgumentException if no target is active
+     * @throws IllegalStateException if a crop or transform pushed while drawing into the target wasn't popped
+     */
+    public void cmdUIEndTarget() {
+        UIRenderer.JNI.cmdUIEndTarget(this.kernel.rustKernelPtr);
+    }
+
+    /**
+     * Executes every command written into the stream in a single call and resets it for the next commands. Commands
+     * before one that fails stay executed.
+     *
+     * @throws IllegalArgumentException if a command has invalid arguments or the stream is malformed
+     * @throws IllegalStateException if a command is out of order, like popping a crop that wasn't pushed
+     */
+    public void cmdUISubmit(UICommandStream stream) {
+        try {
+            UIRenderer.JNI.cmdUISubmitStream(this.kernel.rustKernelPtr, stream.buffer(), stream.length(),
+                    stream.handles(), stream.handleCount());
+        } finally {
+            stream.reset();
+        }
+    }
+
+    public enum BlendMode {
+        /**
+         * Covers what is below by the alpha of the draw.
+         */
+        ALPHA(0),
+        /**
+         * Like {@link #ALPHA}, for textures whose color is already multiplied by alpha, like the contents of render
+         * targets.
+         */
+        PREMULTIPLIED(1),
+        /**
+         * Adds the color weighted by alpha, the alpha of render targets is kept.
+         */
+        ADDITIVE(2),
+        /**
+         * Multiplies what is below by the color, fading out with alpha. The alpha of render targets is kept.
+         */
+        MULTIPLY(3),
+        /**
+         * Overwrites the color and alpha below, the color is multiplied by alpha.
+         */
+        REPLACE(4);
+
+        final int code;
+
+        BlendMode(int code) {
+            this.code = code;
+        }
+    }
+
     private static final class JNI {
         // User Interface
         public static native void cmdUISetCrop(long kernel, float minX, float minY, float maxX, float maxY);
         public static native void cmdUIClearCrop(long kernel);
+        public static native void cmdUIPushCrop(long kernel, float minX, float minY, float maxX, float maxY);
+        public static native void cmdUIPopCrop(long kernel);
+        public static native void cmdUIPushTransform(long kernel);
+        public static native void cmdUIPopTransform(long kernel);
+        public static native void cmdUITranslate(long kernel, float x, float y);
+        public static native void cmdUIRotate(long kernel, float radians);
+        public static native void cmdUIScale(long kernel, float x, float y);
+        public static native void cmdUISetBlendMode(long kernel, int blendMode);
+        public static native void cmdUIBeginList(long kernel);
+        public static native long cmdUIEndList(long kernel);
+        public static native void cmdUIDrawList(long kernel, long listPtr, float x, float y,
+                                                boolean clip, float clipMinX, float clipMinY, float clipMaxX, float clipMaxY);
         public static native void cmdUIDrawTexture(long kernel,
                                                    long texturePtr,
+                                                   int layer,
                                                    float uvMinX, float uvMinY, float uvMaxX, float uvMaxY,
                                                    float posMinX, float posMinY, float posMaxX, float posMaxY,
-                                                   int tintColor);
+                                                   int tintColor,
+                                                   long samplerPtr);
+        public static native void cmdUIBeginTarget(long kernel, long texturePtr, boolean clear, int clearColor);
+        public static native void cmdUIEndTarget(long kernel);
+        public static native void cmdUISubmitStream(long kernel, ByteBuffer buffer, int length, long[] handles, int handleCount);
+        public static native void cmdUIDrawSubTexture(long kernel,
+                                                      long subTexturePtr,
+                                                      float uvMinX, float uvMinY, float uvMaxX, float uvMaxY,
+                                                      float posMinX, float posMinY, float posMaxX, float posMaxY,
+                                                      int tintColor,
+                                                      long samplerPtr);
+        public static native void cmdUIDrawText(long kernel, long fontPtr, float size, float x, float y, int color, String text);
+        public static native void cmdUIDrawTextureTiled(long kernel,
+                                                        long texturePtr,
+                                                        int layer,
+                                                        float posMinX, float posMinY, float posMaxX, float posMaxY,
+                                                        float tileWidth, float tileHeight,
+                                                        float offsetX, float offsetY,
+                                                        int tintColor,
+                                                        long samplerPtr);
+        public static native void cmdUIDrawNineSlice(long kernel,
+                                                     long texturePtr,
+                                                     int layer,
+                                                     float uvMinX, float uvMinY, float uvMaxX, float uvMaxY,
+                                                     float posMinX, float posMinY, float posMaxX, float posMaxY,
+                                                     float insetLeft, float insetTop, float insetRight, float insetBottom,
+                                                     float scale,
+                                                     boolean tile,
+                                                     int tintColor,
+                                                     long samplerPtr);
+        public static native void cmdUIDrawSubNineSlice(long kernel,
+                                                        long subTexturePtr,
+                                                        float uvMinX, float uvMinY, float uvMaxX, float uvMaxY,
+                                                        float posMinX, float posMinY, float posMaxX, float posMaxY,
+                                                        float insetLeft, float insetTop, float insetRight, float insetBottom,
+                                                        float scale,
+                                                        boolean tile,
+                                                        int tintColor,
+                                                        long samplerPtr);
+        public static native void cmdUIDrawRect(long kernel, float posMinX, float posMinY, float posMaxX, float posMaxY, int color);
+        public static native void cmdUIDrawLine(long kernel, float fromX, float fromY, float toX, float toY, float thickness, int color);
+        public static native void cmdUIDrawPolyline(long kernel, float[] points, float thickness, boolean closed, int color);
+        public static native void cmdUIDrawTextLayout(long kernel, long fontPtr, TeraFont.TextLayoutDesc desc,
+                                                      float x, float y, int color, String text);
     }
 }